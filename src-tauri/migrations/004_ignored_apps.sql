@@ -0,0 +1,13 @@
+-- Per-application capture ignore list.
+-- Clipboard changes whose source app matches any rule are never recorded.
+-- match_type:
+--   bundle_id    : exact app id (macOS bundle id, e.g. 'com.1password.1password')
+--   executable   : executable file name or full path (e.g. 'KeePassXC.exe', '/usr/bin/keepassxc')
+--   window_title : wildcard pattern on the focused window title ('*' / '?'; no wildcard = substring)
+CREATE TABLE IF NOT EXISTS ignored_apps (
+    id          TEXT PRIMARY KEY NOT NULL,
+    match_type  TEXT NOT NULL CHECK(match_type IN ('bundle_id', 'executable', 'window_title')),
+    pattern     TEXT NOT NULL,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (match_type, pattern)
+);
@@ -0,0 +1,12 @@
+-- Items captured from password managers (concealed clipboard markers) can be
+-- kept for a short time instead of being dropped. expires_at is NULL for
+-- regular items; a background timer deletes rows once it has passed.
+ALTER TABLE clipboard_items ADD COLUMN expires_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_clipboard_items_expires_at ON clipboard_items(expires_at)
+    WHERE expires_at IS NOT NULL;
+
+-- concealed_policy: 'skip' (never record) | 'expire' (record, delete after concealed_expire_seconds)
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('concealed_policy', 'skip'),
+    ('concealed_expire_seconds', '30');
@@ -0,0 +1,10 @@
+-- Items flagged by the sensitive-content classifier (card numbers, private keys,
+-- API tokens, passwords). Their text is masked in list previews, never indexed
+-- in clipboard_fts, and they expire via expires_at (see 005_concealed_items.sql).
+ALTER TABLE clipboard_items ADD COLUMN sensitive BOOLEAN NOT NULL DEFAULT 0;
+
+-- sensitive_detection: 'true' | 'false'
+-- sensitive_expire_seconds: lifetime of flagged items; '0' keeps them (still masked)
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('sensitive_detection', 'true'),
+    ('sensitive_expire_seconds', '300');
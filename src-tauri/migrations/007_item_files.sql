@@ -0,0 +1,23 @@
+-- Multi-file clipboard items: every copied path is kept, in clipboard order.
+-- clipboard_items.file_path / file_name keep the first entry for display;
+-- file_count and content_size hold the aggregates.
+CREATE TABLE IF NOT EXISTS item_files (
+    item_id     TEXT NOT NULL REFERENCES clipboard_items(id) ON DELETE CASCADE,
+    position    INTEGER NOT NULL,
+    path        TEXT NOT NULL,
+    name        TEXT NOT NULL DEFAULT '',
+    size        INTEGER NOT NULL DEFAULT 0,
+    is_dir      BOOLEAN NOT NULL DEFAULT 0,
+    PRIMARY KEY (item_id, position)
+);
+
+ALTER TABLE clipboard_items ADD COLUMN file_count INTEGER NOT NULL DEFAULT 0;
+
+-- Backfill existing single-file items
+INSERT OR IGNORE INTO item_files (item_id, position, path, name, size)
+SELECT id, 0, file_path, file_name, content_size
+FROM clipboard_items
+WHERE content_type = 'file' AND file_path IS NOT NULL;
+
+UPDATE clipboard_items SET file_count = 1
+WHERE content_type = 'file' AND file_path IS NOT NULL;
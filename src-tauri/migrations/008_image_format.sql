@@ -0,0 +1,9 @@
+-- Images keep the encoding they were copied in (PNG, JPEG, TIFF, WebP, GIF, ...)
+-- instead of being re-encoded to PNG. Dimensions are recorded at capture time.
+ALTER TABLE clipboard_items ADD COLUMN image_width INTEGER;
+ALTER TABLE clipboard_items ADD COLUMN image_height INTEGER;
+ALTER TABLE clipboard_items ADD COLUMN image_format TEXT;
+
+-- Every image stored before this migration was written as PNG
+UPDATE clipboard_items SET image_format = 'png'
+WHERE content_type = 'image' AND image_path IS NOT NULL;
@@ -0,0 +1,9 @@
+-- Perceptual hash (64-bit dHash, stored as a signed integer) of captured images.
+-- find_and_bump_by_hash treats an image within image_dedup_distance bits of an
+-- existing one as a duplicate, so re-encoded copies of the same picture collapse.
+-- Images captured before this migration keep NULL and only dedup byte-for-byte.
+ALTER TABLE clipboard_items ADD COLUMN image_phash INTEGER;
+
+-- image_dedup_distance: max Hamming distance (0-64); '0' disables perceptual dedup
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('image_dedup_distance', '4');
@@ -0,0 +1,8 @@
+-- Text dedup normalization: line endings unified, trailing whitespace on each
+-- line and trailing blank lines dropped before hashing text, rich-text and
+-- link items, so the same snippet copied from a terminal and an editor
+-- collapses into one entry. Text that needs no normalization hashes exactly
+-- as before, so existing entries keep matching.
+-- normalize_text_dedup: 'true' | 'false'
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('normalize_text_dedup', 'true');
@@ -0,0 +1,12 @@
+-- Soft delete: deleting, clearing history and retention cleanup move items to
+-- the trash by setting deleted_at. Trashed items keep their FTS rows, group
+-- memberships and image files until the trash is emptied (manually, or after
+-- trash_retention_days by the background purge).
+ALTER TABLE clipboard_items ADD COLUMN deleted_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_clipboard_items_deleted_at
+    ON clipboard_items(deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- trash_retention_days: days before trashed items are purged; '0' keeps them until emptied
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('trash_retention_days', '30');
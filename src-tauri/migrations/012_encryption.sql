@@ -0,0 +1,11 @@
+-- Encryption at rest: when enabled, plain_text, rich_content, thumbnail and the
+-- saved image files are sealed with XChaCha20-Poly1305. Text is stored as
+-- 'rce1:' + base64, blobs and files start with the 'RCE1' magic, and image
+-- files get an '.enc' suffix. The FTS index is emptied while encryption is on.
+-- encryption_mode: 'off' | 'passphrase' | 'keychain'
+-- encryption_salt: base64 Argon2id salt (passphrase mode)
+-- encryption_verifier: known plaintext sealed with the key, checked on unlock
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('encryption_mode', 'off'),
+    ('encryption_salt', ''),
+    ('encryption_verifier', '');
@@ -0,0 +1,10 @@
+-- App lock: history is hidden behind an unlock prompt until the lock passphrase
+-- (or, in passphrase encryption mode, the encryption passphrase) is entered.
+-- app_lock_salt / app_lock_verifier: Argon2id salt and verifier, '' = no lock
+-- auto_lock_minutes: lock after this many idle minutes, 0 = never
+-- lock_on_sleep: 'true' | 'false', lock when the system wakes from sleep
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('app_lock_salt', ''),
+    ('app_lock_verifier', ''),
+    ('auto_lock_minutes', '0'),
+    ('lock_on_sleep', 'true');
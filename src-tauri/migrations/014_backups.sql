@@ -0,0 +1,10 @@
+-- Scheduled backups: VACUUM INTO snapshots of the database plus the images folder.
+-- backup_enabled: 'true' | 'false'
+-- backup_interval_hours: minimum hours between scheduled snapshots
+-- backup_keep: number of snapshots kept, older ones are deleted
+-- backup_dir: folder holding the snapshots, '' = backups/ in the app data folder
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('backup_enabled', 'true'),
+    ('backup_interval_hours', '24'),
+    ('backup_keep', '7'),
+    ('backup_dir', '');
@@ -0,0 +1,82 @@
+-- LAN sync between paired machines.
+-- changed_at: when the item's synced state (favorite, updated_at, trash, groups)
+--   last changed, in milliseconds; the newer side wins when peers disagree.
+--   Items applied from a peer keep the peer's changed_at.
+-- sync_seq: local change counter; peers pull the items with a sync_seq past
+--   the last one they have seen.
+ALTER TABLE clipboard_items ADD COLUMN changed_at TEXT;
+ALTER TABLE clipboard_items ADD COLUMN sync_seq INTEGER NOT NULL DEFAULT 0;
+
+UPDATE clipboard_items SET changed_at = updated_at || '.000', sync_seq = rowid;
+
+-- Last sync_seq handed out. Kept apart from the items so it never goes back
+-- when the newest item is deleted for good (a peer would miss the next change).
+CREATE TABLE IF NOT EXISTS sync_counter (
+    id              INTEGER PRIMARY KEY CHECK (id = 1),
+    seq             INTEGER NOT NULL
+);
+INSERT OR IGNORE INTO sync_counter (id, seq)
+    SELECT 1, COALESCE(MAX(sync_seq), 0) FROM clipboard_items;
+
+CREATE INDEX IF NOT EXISTS idx_clipboard_items_sync_seq ON clipboard_items(sync_seq);
+
+-- Local changes bump changed_at and sync_seq. Statements that set changed_at
+-- themselves (applying a peer's change) are left alone.
+CREATE TRIGGER IF NOT EXISTS sync_item_inserted AFTER INSERT ON clipboard_items
+BEGIN
+    UPDATE sync_counter SET seq = seq + 1;
+    UPDATE clipboard_items SET
+        changed_at = COALESCE(NEW.changed_at, strftime('%Y-%m-%d %H:%M:%f', 'now')),
+        sync_seq = (SELECT seq FROM sync_counter)
+    WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_item_updated
+AFTER UPDATE OF is_favorited, updated_at, deleted_at ON clipboard_items
+WHEN NEW.changed_at IS OLD.changed_at
+BEGIN
+    UPDATE sync_counter SET seq = seq + 1;
+    UPDATE clipboard_items SET
+        changed_at = strftime('%Y-%m-%d %H:%M:%f', 'now'),
+        sync_seq = (SELECT seq FROM sync_counter)
+    WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_item_grouped AFTER INSERT ON item_groups
+BEGIN
+    UPDATE sync_counter SET seq = seq + 1;
+    UPDATE clipboard_items SET
+        changed_at = strftime('%Y-%m-%d %H:%M:%f', 'now'),
+        sync_seq = (SELECT seq FROM sync_counter)
+    WHERE id = NEW.item_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_item_ungrouped AFTER DELETE ON item_groups
+BEGIN
+    UPDATE sync_counter SET seq = seq + 1;
+    UPDATE clipboard_items SET
+        changed_at = strftime('%Y-%m-%d %H:%M:%f', 'now'),
+        sync_seq = (SELECT seq FROM sync_counter)
+    WHERE id = OLD.item_id;
+END;
+
+-- Machines this one has synced with. last_seq is the peer's sync_seq up to
+-- which its changes have been pulled.
+CREATE TABLE IF NOT EXISTS sync_peers (
+    device_id       TEXT PRIMARY KEY NOT NULL,
+    name            TEXT NOT NULL DEFAULT '',
+    address         TEXT,
+    last_seq        INTEGER NOT NULL DEFAULT 0,
+    last_sync_at    TEXT
+);
+
+-- sync_enabled: 'true' | 'false'
+-- sync_port: TCP port the sync listener binds to (advertised over mDNS)
+-- sync_device_id / sync_device_name: this machine's identity, set on first start
+-- sync_key: base64 key derived from the pairing secret, '' = not paired
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('sync_enabled', 'false'),
+    ('sync_port', '47800'),
+    ('sync_device_id', ''),
+    ('sync_device_name', ''),
+    ('sync_key', '');
@@ -0,0 +1,14 @@
+-- Sync through a folder shared by a file-sync tool (see sync::folder).
+-- Files in the folder this machine has already ingested or written itself.
+CREATE TABLE IF NOT EXISTS sync_folder_entries (
+    name        TEXT PRIMARY KEY NOT NULL,
+    seen_at     TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- sync_mode: 'lan' | 'folder'
+-- sync_folder: the shared folder, '' = not chosen
+-- sync_folder_seq: local sync_seq up to which items have been written to the folder
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('sync_mode', 'lan'),
+    ('sync_folder', ''),
+    ('sync_folder_seq', '0');
@@ -0,0 +1,6 @@
+-- Opt-in HTTP API on 127.0.0.1 (see commands::http_api).
+-- http_api_token: bearer token clients must send, generated when first enabled
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('http_api_enabled', 'false'),
+    ('http_api_port', '47810'),
+    ('http_api_token', '');
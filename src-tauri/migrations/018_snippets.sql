@@ -0,0 +1,14 @@
+-- Snippets: reusable text the user writes (or promotes from a history item),
+-- with placeholders expanded when pasted (see src/snippet).
+-- Snippets share clipboard_fts with items, indexed under their own id with
+-- the content in plain_text and the name in file_name. Item searches only
+-- match ids in clipboard_items, snippet searches only ids in snippets.
+CREATE TABLE IF NOT EXISTS snippets (
+    id          TEXT PRIMARY KEY NOT NULL,
+    name        TEXT NOT NULL,
+    content     TEXT NOT NULL,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_snippets_updated_at ON snippets(updated_at DESC);
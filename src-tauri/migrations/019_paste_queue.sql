@@ -0,0 +1,6 @@
+-- Paste queue (see commands::paste_queue). The queue itself lives in memory.
+-- paste_queue_order: 'fifo' pastes the first-copied entry first, 'lifo' the last
+-- paste_queue_shortcut: pastes the next entry; registered only while collecting
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('paste_queue_order', 'fifo'),
+    ('paste_queue_shortcut', 'CommandOrControl+Shift+B');
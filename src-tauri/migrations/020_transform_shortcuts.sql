@@ -0,0 +1,5 @@
+-- Paste-time transforms (see commands::transforms)
+-- transform_shortcuts: JSON array of {"shortcut", "transforms"}; each shortcut
+-- pastes the clipboard's text through its transforms
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('transform_shortcuts', '[]');
@@ -0,0 +1,14 @@
+-- Perceptual dedup merged distinct screenshots of the same layout, so it is now
+-- opt-in. 009 stores the old default '4', indistinguishable from a user's
+-- choice, so only a value 009 seeded in this same run (a new or just upgraded
+-- database) is changed; earlier values are left as the user has them.
+UPDATE settings SET value = '0'
+WHERE key = 'image_dedup_distance' AND value = '4'
+    AND EXISTS (
+        SELECT 1 FROM _sqlx_migrations
+        WHERE version = 9 AND installed_on >= datetime('now', '-10 minutes')
+    );
+
+-- find_and_bump_by_hash only compares images of the same size
+CREATE INDEX IF NOT EXISTS idx_clipboard_items_image_size
+    ON clipboard_items(image_width, image_height) WHERE image_phash IS NOT NULL;
@@ -0,0 +1,439 @@
+//! Portable export archive for moving history between machines.
+//!
+//! An archive is a zip file holding:
+//! - `manifest.json`: format name and version, export time, item count
+//! - `items.jsonl`, `groups.jsonl`, `settings.jsonl`: one JSON object per line
+//! - `images/<name>`: the original image of each image item, unencrypted
+//!
+//! Content is always stored in plain form, so an archive can be imported on a
+//! machine with a different (or no) encryption key.
+
+use crate::db::models::{ContentType, ImportItem, ItemFile, NewClipboardItem};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+pub const FORMAT_NAME: &str = "recopy-archive";
+pub const FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const ITEMS_FILE: &str = "items.jsonl";
+const GROUPS_FILE: &str = "groups.jsonl";
+const SETTINGS_FILE: &str = "settings.jsonl";
+const IMAGES_DIR: &str = "images/";
+
+/// Largest entry read from an archive, and the most memory reserved up front
+/// for one (the sizes in the zip directory come from the file, unchecked).
+const MAX_ENTRY_BYTES: u64 = 512 * 1024 * 1024;
+const MAX_ENTRY_PREALLOC: u64 = 16 * 1024 * 1024;
+
+/// Settings that are tied to this machine or change only through dedicated
+/// commands; they are neither exported nor imported.
+const NON_PORTABLE_SETTINGS: &[&str] = &[
+    "encryption_",
+    "app_lock_",
+    "auto_start",
+    "sync_",
+    "backup_dir",
+    "http_api_",
+];
+
+/// Whether a setting travels with an archive.
+pub fn is_portable_setting(key: &str) -> bool {
+    !NON_PORTABLE_SETTINGS
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: String,
+    pub item_count: usize,
+}
+
+/// One line of `items.jsonl`. Blobs are stored base64-encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveItem {
+    pub content_type: String,
+    pub plain_text: String,
+    #[serde(
+        default,
+        with = "base64_bytes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rich_content: Option<Vec<u8>>,
+    #[serde(
+        default,
+        with = "base64_bytes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub thumbnail: Option<Vec<u8>>,
+    /// Entry name of the original image inside the archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<ItemFile>,
+    pub source_app: String,
+    pub source_app_name: String,
+    pub content_size: i64,
+    pub content_hash: String,
+    pub is_favorited: bool,
+    pub sensitive: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_width: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_height: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_phash: Option<i64>,
+    /// Names of the groups the item belongs to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl ArchiveItem {
+    /// Turn the item into an insertable one. `content_type` is the parsed
+    /// `self.content_type`; `image_path` is where its image was saved locally.
+    pub fn into_import(
+        self,
+        content_type: ContentType,
+        image_path: Option<String>,
+        group_ids: Vec<String>,
+    ) -> ImportItem {
+        ImportItem {
+            item: NewClipboardItem {
+                content_type,
+                plain_text: self.plain_text,
+                rich_content: self.rich_content,
+                thumbnail: self.thumbnail,
+                image_path,
+                file_path: self.file_path,
+                file_name: self.file_name,
+                source_app: self.source_app,
+                source_app_name: self.source_app_name,
+                content_size: self.content_size,
+                content_hash: self.content_hash,
+                sensitive: self.sensitive,
+                files: self.files,
+                image_width: self.image_width,
+                image_height: self.image_height,
+                image_format: self.image_format,
+                image_phash: self.image_phash,
+                expires_at: None,
+            },
+            is_favorited: self.is_favorited,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            group_ids,
+        }
+    }
+}
+
+/// Serde adapter storing optional blobs as base64 strings.
+pub(crate) mod base64_bytes {
+    use base64::engine::general_purpose::STANDARD as B64;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &Option<Vec<u8>>, s: S) -> Result<S::Ok, S::Error> {
+        match data {
+            Some(data) => s.serialize_some(&B64.encode(data)),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Vec<u8>>, D::Error> {
+        Option::<String>::deserialize(d)?
+            .map(|v| B64.decode(v).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+/// One line of `groups.jsonl`. Groups are matched by name on import.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArchiveGroup {
+    pub name: String,
+    pub sort_order: i64,
+}
+
+/// One line of `settings.jsonl`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArchiveSetting {
+    pub key: String,
+    pub value: String,
+}
+
+/// Entry name for an image saved at `local_path` (`.enc` suffix dropped).
+pub fn image_entry_name(local_path: &str) -> Option<String> {
+    let name = Path::new(local_path).file_name()?.to_str()?;
+    let name = name
+        .strip_suffix(crate::crypto::FILE_SUFFIX)
+        .unwrap_or(name);
+    Some(format!("{}{}", IMAGES_DIR, name))
+}
+
+// ---- Writing ----
+
+pub struct ArchiveWriter {
+    zip: ZipWriter<File>,
+}
+
+impl ArchiveWriter {
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create archive: {}", e))?;
+        Ok(ArchiveWriter {
+            zip: ZipWriter::new(file),
+        })
+    }
+
+    /// Add an image. Images are already compressed, so they are stored as is.
+    pub fn add_image(&mut self, entry: &str, data: &[u8]) -> Result<(), String> {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Stored)
+            .large_file(data.len() as u64 >= u32::MAX as u64);
+        self.write_entry(entry, data, options)
+    }
+
+    /// Write the manifest and the JSON lines files, then close the archive.
+    pub fn finish(
+        mut self,
+        items: &[ArchiveItem],
+        groups: &[ArchiveGroup],
+        settings: &[ArchiveSetting],
+    ) -> Result<(), String> {
+        let manifest = Manifest {
+            format: FORMAT_NAME.to_string(),
+            version: FORMAT_VERSION,
+            exported_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            item_count: items.len(),
+        };
+        let manifest = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        self.write_entry(MANIFEST_FILE, &manifest, options)?;
+        self.write_entry(ITEMS_FILE, &to_json_lines(items)?, options)?;
+        self.write_entry(GROUPS_FILE, &to_json_lines(groups)?, options)?;
+        self.write_entry(SETTINGS_FILE, &to_json_lines(settings)?, options)?;
+
+        self.zip
+            .finish()
+            .map_err(|e| format!("Failed to write archive: {}", e))?;
+        Ok(())
+    }
+
+    fn write_entry(
+        &mut self,
+        entry: &str,
+        data: &[u8],
+        options: SimpleFileOptions,
+    ) -> Result<(), String> {
+        self.zip
+            .start_file(entry, options)
+            .map_err(|e| format!("Failed to write {}: {}", entry, e))?;
+        self.zip
+            .write_all(data)
+            .map_err(|e| format!("Failed to write {}: {}", entry, e))
+    }
+}
+
+fn to_json_lines<T: Serialize>(rows: &[T]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    for row in rows {
+        serde_json::to_writer(&mut out, row).map_err(|e| e.to_string())?;
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+// ---- Reading ----
+
+pub struct ArchiveReader {
+    zip: ZipArchive<BufReader<File>>,
+}
+
+impl ArchiveReader {
+    /// Open an archive and check that it is one this version can read.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+        let zip = ZipArchive::new(BufReader::new(file))
+            .map_err(|e| format!("Not a valid archive: {}", e))?;
+        let mut reader = ArchiveReader { zip };
+
+        let manifest: Manifest = serde_json::from_slice(&reader.read_entry(MANIFEST_FILE)?)
+            .map_err(|e| format!("Invalid archive manifest: {}", e))?;
+        if manifest.format != FORMAT_NAME {
+            return Err(format!("Unknown archive format: {}", manifest.format));
+        }
+        if manifest.version > FORMAT_VERSION {
+            return Err(format!(
+                "Archive version {} is newer than supported ({})",
+                manifest.version, FORMAT_VERSION
+            ));
+        }
+        Ok(reader)
+    }
+
+    pub fn items(&mut self) -> Result<Vec<ArchiveItem>, String> {
+        self.read_json_lines(ITEMS_FILE)
+    }
+
+    pub fn groups(&mut self) -> Result<Vec<ArchiveGroup>, String> {
+        self.read_json_lines(GROUPS_FILE)
+    }
+
+    pub fn settings(&mut self) -> Result<Vec<ArchiveSetting>, String> {
+        self.read_json_lines(SETTINGS_FILE)
+    }
+
+    pub fn read_image(&mut self, entry: &str) -> Result<Vec<u8>, String> {
+        if !entry.starts_with(IMAGES_DIR) {
+            return Err(format!("Invalid image entry: {}", entry));
+        }
+        self.read_entry(entry)
+    }
+
+    fn read_entry(&mut self, entry: &str) -> Result<Vec<u8>, String> {
+        let file = self
+            .zip
+            .by_name(entry)
+            .map_err(|e| format!("Failed to read {}: {}", entry, e))?;
+        let mut data = Vec::with_capacity(file.size().min(MAX_ENTRY_PREALLOC) as usize);
+        file.take(MAX_ENTRY_BYTES + 1)
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read {}: {}", entry, e))?;
+        if data.len() as u64 > MAX_ENTRY_BYTES {
+            return Err(format!("{} is too large", entry));
+        }
+        Ok(data)
+    }
+
+    fn read_json_lines<T: DeserializeOwned>(&mut self, entry: &str) -> Result<Vec<T>, String> {
+        let data = self.read_entry(entry)?;
+        let mut rows = Vec::new();
+        for (n, line) in data.lines().enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let row = serde_json::from_str(&line)
+                .map_err(|e| format!("{} line {}: {}", entry, n + 1, e))?;
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_archive() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("recopy-archive-{}.zip", uuid::Uuid::new_v4()))
+    }
+
+    fn sample_item() -> ArchiveItem {
+        ArchiveItem {
+            content_type: "image".into(),
+            plain_text: "".into(),
+            rich_content: None,
+            thumbnail: Some(b"thumb".to_vec()),
+            image_file: Some("images/a.png".into()),
+            file_path: None,
+            file_name: None,
+            files: vec![],
+            source_app: "com.test".into(),
+            source_app_name: "Test".into(),
+            content_size: 4,
+            content_hash: "hash-1".into(),
+            is_favorited: true,
+            sensitive: false,
+            image_width: Some(1),
+            image_height: Some(1),
+            image_format: Some("png".into()),
+            image_phash: Some(-42),
+            groups: vec!["Work".into()],
+            created_at: "2026-01-01 00:00:00".into(),
+            updated_at: "2026-01-02 00:00:00".into(),
+        }
+    }
+
+    #[test]
+    fn test_archive_roundtrip() {
+        let path = temp_archive();
+        let groups = vec![ArchiveGroup {
+            name: "Work".into(),
+            sort_order: 0,
+        }];
+        let settings = vec![ArchiveSetting {
+            key: "theme".into(),
+            value: "dark".into(),
+        }];
+
+        let mut writer = ArchiveWriter::create(&path).unwrap();
+        writer.add_image("images/a.png", b"\x89PNG").unwrap();
+        writer.finish(&[sample_item()], &groups, &settings).unwrap();
+
+        let mut reader = ArchiveReader::open(&path).unwrap();
+        let items = reader.items().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].content_hash, "hash-1");
+        assert_eq!(items[0].image_phash, Some(-42));
+        assert_eq!(items[0].thumbnail.as_deref(), Some(&b"thumb"[..]));
+        assert_eq!(items[0].rich_content, None);
+        assert_eq!(items[0].groups, vec!["Work".to_string()]);
+        assert_eq!(reader.groups().unwrap(), groups);
+        assert_eq!(reader.settings().unwrap(), settings);
+        assert_eq!(reader.read_image("images/a.png").unwrap(), b"\x89PNG");
+        assert!(reader.read_image("items.jsonl").is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_open_rejects_foreign_zip() {
+        let path = temp_archive();
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        zip.start_file(MANIFEST_FILE, SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(br#"{"format":"other","version":1,"exported_at":"","item_count":0}"#)
+            .unwrap();
+        zip.finish().unwrap();
+
+        assert!(ArchiveReader::open(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_image_entry_name() {
+        assert_eq!(
+            image_entry_name("/data/images/2026-01/abc.png.enc").as_deref(),
+            Some("images/abc.png")
+        );
+        assert_eq!(
+            image_entry_name("/data/images/2026-01/abc.jpeg").as_deref(),
+            Some("images/abc.jpeg")
+        );
+    }
+
+    #[test]
+    fn test_portable_settings() {
+        assert!(is_portable_setting("theme"));
+        assert!(is_portable_setting("retention_days"));
+        assert!(!is_portable_setting("encryption_mode"));
+        assert!(!is_portable_setting("app_lock_verifier"));
+        assert!(!is_portable_setting("auto_start"));
+        assert!(!is_portable_setting("http_api_token"));
+    }
+}
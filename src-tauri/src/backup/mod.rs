@@ -0,0 +1,289 @@
+//! Snapshot backups of the database and saved images.
+//!
+//! Each snapshot is a directory `recopy-YYYYMMDD-HHMMSS` inside the backup
+//! folder, holding a `VACUUM INTO` copy of the database and a copy of the
+//! `images/` tree. Snapshots are assembled under a `.partial` name and renamed
+//! once complete, so an interrupted backup is never listed or restored.
+//! Image files never change once written, so files already present in the
+//! previous snapshot are hard-linked instead of copied where possible.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DB_FILE: &str = "recopy.db";
+pub const IMAGES_DIR: &str = "images";
+
+const SNAPSHOT_PREFIX: &str = "recopy-";
+const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// A complete snapshot found in the backup folder.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Directory name, used as the backup id.
+    pub id: String,
+    pub path: PathBuf,
+    pub created_at: NaiveDateTime,
+}
+
+/// Directory name of a snapshot taken at `time`.
+pub fn snapshot_name(time: DateTime<Utc>) -> String {
+    format!("{}{}", SNAPSHOT_PREFIX, time.format(SNAPSHOT_TIME_FORMAT))
+}
+
+/// Where a snapshot is assembled before being renamed to `name`.
+pub fn partial_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}{}", name, PARTIAL_SUFFIX))
+}
+
+fn parse_snapshot_name(name: &str) -> Option<NaiveDateTime> {
+    let time = name.strip_prefix(SNAPSHOT_PREFIX)?;
+    NaiveDateTime::parse_from_str(time, SNAPSHOT_TIME_FORMAT).ok()
+}
+
+/// Complete snapshots in `dir`, newest first. A missing folder has none.
+pub fn list_snapshots(dir: &Path) -> Vec<Snapshot> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut snapshots: Vec<Snapshot> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry.file_name().to_str()?.to_string();
+            let created_at = parse_snapshot_name(&id)?;
+            let path = entry.path();
+            path.join(DB_FILE).is_file().then_some(Snapshot {
+                id,
+                path,
+                created_at,
+            })
+        })
+        .collect();
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+    snapshots
+}
+
+/// Delete all but the newest `keep` snapshots, plus leftovers of interrupted
+/// backups. Returns the number of snapshots removed.
+pub fn rotate(dir: &Path, keep: usize) -> io::Result<usize> {
+    remove_snapshots(dir, keep.max(1))
+}
+
+/// Delete every snapshot and leftover, e.g. once they hold history that has
+/// since been encrypted. Returns the number of snapshots removed.
+pub fn remove_all(dir: &Path) -> io::Result<usize> {
+    remove_snapshots(dir, 0)
+}
+
+fn remove_snapshots(dir: &Path, keep: usize) -> io::Result<usize> {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with(SNAPSHOT_PREFIX) && name.ends_with(PARTIAL_SUFFIX) {
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+    }
+
+    let stale = list_snapshots(dir).into_iter().skip(keep);
+    let mut removed = 0;
+    for snapshot in stale {
+        fs::remove_dir_all(&snapshot.path)?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Copy the image tree `src` into `dst`. Files that exist under the same
+/// relative path in `previous` are hard-linked from there instead, falling
+/// back to a copy (e.g. across file systems). Returns the number of files.
+pub fn copy_images(src: &Path, dst: &Path, previous: Option<&Path>) -> io::Result<usize> {
+    if !src.is_dir() {
+        return Ok(0);
+    }
+    fs::create_dir_all(dst)?;
+
+    let mut count = 0;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let name = entry.file_name();
+        let target = dst.join(&name);
+        let prev = previous.map(|p| p.join(&name));
+
+        if file_type.is_dir() {
+            count += copy_images(&entry.path(), &target, prev.as_deref())?;
+        } else if file_type.is_file() {
+            let linked = prev
+                .filter(|p| same_size(p, &entry.path()))
+                .is_some_and(|p| fs::hard_link(p, &target).is_ok());
+            if !linked {
+                fs::copy(entry.path(), &target)?;
+            }
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+fn same_size(a: &Path, b: &Path) -> bool {
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.is_file() && a.len() == b.len(),
+        _ => false,
+    }
+}
+
+/// Total size of the files under `path`, in bytes.
+pub fn tree_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(ft) if ft.is_dir() => tree_size(&entry.path()),
+            Ok(ft) if ft.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Open a snapshot database read-only and run SQLite's integrity check.
+pub async fn verify_database(path: &Path) -> Result<(), String> {
+    let options = SqliteConnectOptions::new().filename(path).read_only(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .map_err(|e| format!("Failed to open backup: {}", e))?;
+    let result: Result<(String,), sqlx::Error> = sqlx::query_as("PRAGMA integrity_check")
+        .fetch_one(&pool)
+        .await;
+    pool.close().await;
+
+    match result {
+        Ok((status,)) if status == "ok" => Ok(()),
+        Ok((status,)) => Err(format!("Backup is damaged: {}", status)),
+        Err(e) => Err(format!("Failed to check backup: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("recopy-backup-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn fake_snapshot(dir: &Path, day: u32) -> String {
+        let name = snapshot_name(Utc.with_ymd_and_hms(2026, 3, day, 12, 0, 0).unwrap());
+        fs::create_dir_all(dir.join(&name)).unwrap();
+        fs::write(dir.join(&name).join(DB_FILE), b"db").unwrap();
+        name
+    }
+
+    #[test]
+    fn test_snapshot_name_roundtrip() {
+        let time = Utc.with_ymd_and_hms(2026, 3, 9, 8, 5, 1).unwrap();
+        let name = snapshot_name(time);
+        assert_eq!(name, "recopy-20260309-080501");
+        assert_eq!(parse_snapshot_name(&name), Some(time.naive_utc()));
+        assert_eq!(parse_snapshot_name("recopy-latest"), None);
+        assert_eq!(parse_snapshot_name("notes-20260309-080501"), None);
+    }
+
+    #[test]
+    fn test_list_and_rotate() {
+        let dir = temp_dir();
+        let old = fake_snapshot(&dir, 1);
+        let mid = fake_snapshot(&dir, 2);
+        let new = fake_snapshot(&dir, 3);
+        // Interrupted backup and unrelated folder
+        fs::create_dir_all(partial_path(&dir, &snapshot_name(Utc::now()))).unwrap();
+        fs::create_dir_all(dir.join("other")).unwrap();
+
+        let ids: Vec<String> = list_snapshots(&dir).into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, [new.clone(), mid.clone(), old.clone()]);
+
+        assert_eq!(rotate(&dir, 2).unwrap(), 1);
+        let ids: Vec<String> = list_snapshots(&dir).into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, [new, mid]);
+        assert!(!dir.join(&old).exists());
+        assert!(dir.join("other").exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+        // rotate always keeps the newest; remove_all does not
+        assert_eq!(rotate(&dir, 0).unwrap(), 1);
+        assert_eq!(remove_all(&dir).unwrap(), 1);
+        assert!(list_snapshots(&dir).is_empty());
+        assert!(dir.join("other").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_copy_images_links_unchanged_files() {
+        let dir = temp_dir();
+        let src = dir.join("images");
+        fs::create_dir_all(src.join("2026-03")).unwrap();
+        fs::write(src.join("2026-03").join("a.png"), b"aaaa").unwrap();
+
+        let first = dir.join("snap1");
+        assert_eq!(copy_images(&src, &first, None).unwrap(), 1);
+
+        fs::write(src.join("2026-03").join("b.png"), b"bb").unwrap();
+        let second = dir.join("snap2");
+        assert_eq!(copy_images(&src, &second, Some(&first)).unwrap(), 2);
+        assert_eq!(
+            fs::read(second.join("2026-03").join("a.png")).unwrap(),
+            b"aaaa"
+        );
+        assert_eq!(
+            fs::read(second.join("2026-03").join("b.png")).unwrap(),
+            b"bb"
+        );
+        assert_eq!(tree_size(&second), 6);
+
+        // A missing source tree is an empty backup, not an error
+        assert_eq!(
+            copy_images(&dir.join("none"), &dir.join("snap3"), None).unwrap(),
+            0
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_verify_database() {
+        let dir = temp_dir();
+        let good = dir.join(DB_FILE);
+        let pool = SqlitePoolOptions::new()
+            .connect_with(
+                SqliteConnectOptions::new()
+                    .filename(&good)
+                    .create_if_missing(true),
+            )
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE t (x INTEGER)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool.close().await;
+        assert!(verify_database(&good).await.is_ok());
+
+        let bad = dir.join("bad.db");
+        fs::write(&bad, b"not a database at all").unwrap();
+        assert!(verify_database(&bad).await.is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
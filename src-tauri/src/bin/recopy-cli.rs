@@ -0,0 +1,396 @@
+//! `recopy-cli`: clipboard history for shell pipelines.
+//!
+//! Talks to the running app over its local socket. When the app is not
+//! running, `list`, `search` and `get` read `recopy.db` directly, read-only.
+
+use recopy_lib::db::{self, models::ItemDetail, queries};
+use recopy_lib::ipc::{CallError, Client};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: recopy-cli [--json] <command> [options]
+
+Commands:
+  list                List recent items
+  search <query>      Search items
+  get <id>            Print an item's full content
+  copy <id>           Put an item on the clipboard
+  paste <id>          Paste an item into the frontmost app
+  add                 Add text read from stdin
+  favorite <id>       Toggle an item's favorite flag
+  delete <id>         Move an item to the trash
+  clear               Move all non-favorite items to the trash
+  watch               Print the id of each item as it is copied or changed
+
+Options:
+  --json              Print JSON instead of plain text
+  --type <type>       Only items of a type (plain_text, rich_text, image, file, link)
+  --limit <n>         Number of items to list or search (default 50)
+  --offset <n>        Items to skip when listing
+  --favorites         Search favorites only
+  --plain             Paste without formatting
+
+Without the app running, list/search/get read the database at $RECOPY_DB,
+or the app's default location.";
+
+/// App identifier, naming the app data folder (see tauri.conf.json).
+const APP_IDENTIFIER: &str = "com.recopy.app";
+
+/// How this program introduces itself to the app.
+const CLIENT_NAME: &str = "recopy-cli";
+
+struct Args {
+    json: bool,
+    command: String,
+    operand: Option<String>,
+    content_type: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    favorites: bool,
+    plain: bool,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        json: false,
+        command: String::new(),
+        operand: None,
+        content_type: None,
+        limit: None,
+        offset: None,
+        favorites: false,
+        plain: false,
+    };
+    let mut positional = Vec::new();
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--json" => args.json = true,
+            "--favorites" => args.favorites = true,
+            "--plain" => args.plain = true,
+            "--type" => args.content_type = Some(iter.next().ok_or("--type needs a value")?),
+            "--limit" | "--offset" => {
+                let value = iter
+                    .next()
+                    .and_then(|v| v.parse::<i64>().ok())
+                    .ok_or_else(|| format!("{} needs a number", arg))?;
+                if arg == "--limit" {
+                    args.limit = Some(value);
+                } else {
+                    args.offset = Some(value);
+                }
+            }
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    args.command = positional.next().ok_or_else(String::new)?;
+    // The search query may be given unquoted as several words
+    let rest: Vec<String> = positional.collect();
+    if !rest.is_empty() {
+        args.operand = Some(rest.join(" "));
+    }
+    Ok(args)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("recopy-cli: {}\n", e);
+            }
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    if args.command == "watch" {
+        return match watch(&args).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("recopy-cli: {}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    match run(&args).await {
+        Ok(value) => match print_result(&args, &value) {
+            // The reader stopped early, as with `watch`
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => {
+                eprintln!("recopy-cli: {}", e);
+                ExitCode::FAILURE
+            }
+            _ => ExitCode::SUCCESS,
+        },
+        Err(e) => {
+            eprintln!("recopy-cli: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: &Args) -> Result<Value, String> {
+    let (method, params) = request(args)?;
+    let mut client = match Client::connect(CLIENT_NAME).await {
+        Ok(client) => client,
+        Err(CallError::NotRunning) => return run_offline(method, &params).await,
+        Err(e) => return Err(e.to_string()),
+    };
+    client.call(method, params).await.map_err(|e| e.to_string())
+}
+
+/// Print clipboard changes until the app quits or the output is closed.
+async fn watch(args: &Args) -> Result<(), String> {
+    let mut client = Client::connect(CLIENT_NAME)
+        .await
+        .map_err(|e| e.to_string())?;
+    client
+        .call("subscribe", json!({}))
+        .await
+        .map_err(|e| e.to_string())?;
+    loop {
+        let notification = match client.next_notification().await {
+            Ok(notification) => notification,
+            // The app quitting ends the watch
+            Err(CallError::Io(_)) => return Ok(()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let line = if args.json {
+            serde_json::to_string(&notification.params).unwrap_or_default()
+        } else {
+            // Deletes and clears name no item
+            match notification.params["id"].as_str() {
+                Some(id) => id.to_string(),
+                None => continue,
+            }
+        };
+        if writeln!(std::io::stdout(), "{}", line).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+/// The socket method and parameters for a command.
+fn request(args: &Args) -> Result<(&'static str, Value), String> {
+    let id = || {
+        args.operand
+            .clone()
+            .ok_or_else(|| format!("{} needs an item id", args.command))
+    };
+    Ok(match args.command.as_str() {
+        "list" => (
+            "list",
+            json!({ "content_type": args.content_type, "limit": args.limit, "offset": args.offset }),
+        ),
+        "search" => (
+            "search",
+            json!({
+                "query": args.operand.clone().ok_or("search needs a query")?,
+                "content_type": args.content_type,
+                "limit": args.limit,
+                "favorites_only": args.favorites,
+            }),
+        ),
+        "get" => ("get", json!({ "id": id()? })),
+        "copy" => ("copy", json!({ "id": id()? })),
+        "paste" => ("paste", json!({ "id": id()?, "plain_text": args.plain })),
+        "favorite" => ("favorite", json!({ "id": id()? })),
+        "delete" => ("delete", json!({ "id": id()? })),
+        "clear" => ("clear", json!({})),
+        "add" => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|e| format!("Failed to read stdin: {}", e))?;
+            ("add", json!({ "text": text, "source": CLIENT_NAME }))
+        }
+        other => return Err(format!("Unknown command {}", other)),
+    })
+}
+
+/// Answer read-only requests from the database while the app is not running.
+async fn run_offline(method: &str, params: &Value) -> Result<Value, String> {
+    if !matches!(method, "list" | "search" | "get") {
+        return Err(format!("Recopy is not running; {} needs the app", method));
+    }
+    let path = db_path().ok_or("Could not locate the Recopy database")?;
+    if !path.exists() {
+        return Err(format!("No database at {}", path.display()));
+    }
+    let pool = db::open_read_only(&path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+
+    // Encrypted or locked history is only readable through the app
+    let encryption = setting(&pool, "encryption_mode").await;
+    if !encryption.is_empty() && encryption != "off" {
+        return Err("History is encrypted; start Recopy to read it".to_string());
+    }
+    if !setting(&pool, "app_lock_verifier").await.is_empty() {
+        return Err("History is locked; start Recopy to read it".to_string());
+    }
+
+    let str_param = |key: &str| params[key].as_str().map(String::from);
+    let limit = params["limit"].as_i64().unwrap_or(50);
+    let value = match method {
+        "list" => {
+            let items = queries::get_items(
+                &pool,
+                str_param("content_type").as_deref(),
+                limit,
+                params["offset"].as_i64().unwrap_or(0),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            serde_json::to_value(items)
+        }
+        "search" => {
+            let items = queries::search_items(
+                &pool,
+                &str_param("query").unwrap_or_default(),
+                str_param("content_type").as_deref(),
+                limit,
+                params["favorites_only"].as_bool().unwrap_or(false),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            serde_json::to_value(items)
+        }
+        _ => {
+            let id = str_param("id").unwrap_or_default();
+            let (
+                content_type,
+                plain_text,
+                rich_content,
+                image_path,
+                file_path,
+                file_name,
+                content_size,
+            ) = queries::get_item_detail(&pool, &id)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or("Item not found")?;
+            serde_json::to_value(ItemDetail {
+                id,
+                content_type,
+                plain_text,
+                rich_content,
+                image_path,
+                file_path,
+                file_name,
+                content_size,
+            })
+        }
+    };
+    value.map_err(|e| e.to_string())
+}
+
+async fn setting(pool: &SqlitePool, key: &str) -> String {
+    queries::get_setting(pool, key)
+        .await
+        .unwrap_or(None)
+        .unwrap_or_default()
+}
+
+/// `$RECOPY_DB`, or `recopy.db` in the app data folder Tauri uses.
+fn db_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("RECOPY_DB").filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let home = || std::env::var_os("HOME").map(PathBuf::from);
+    let data_dir = if cfg!(target_os = "macos") {
+        home()?.join("Library/Application Support")
+    } else if cfg!(windows) {
+        PathBuf::from(std::env::var_os("APPDATA")?)
+    } else {
+        match std::env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => home()?.join(".local/share"),
+        }
+    };
+    Some(data_dir.join(APP_IDENTIFIER).join("recopy.db"))
+}
+
+/// Print a command's result. A closed pipe (e.g. `| head`) comes back as a
+/// BrokenPipe error rather than a panic.
+fn print_result(args: &Args, value: &Value) -> std::io::Result<()> {
+    let mut out = std::io::stdout().lock();
+    if args.json {
+        return writeln!(
+            out,
+            "{}",
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        );
+    }
+    match args.command.as_str() {
+        "list" | "search" => {
+            for item in value.as_array().into_iter().flatten() {
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}",
+                    item["id"].as_str().unwrap_or_default(),
+                    item["content_type"].as_str().unwrap_or_default(),
+                    item["updated_at"].as_str().unwrap_or_default(),
+                    preview(item)
+                )?;
+            }
+        }
+        "get" => {
+            let text = value["plain_text"].as_str().unwrap_or_default();
+            if !text.is_empty() {
+                writeln!(out, "{}", text)?;
+            } else if let Some(path) = value["file_path"].as_str().or(value["image_path"].as_str())
+            {
+                writeln!(out, "{}", path)?;
+            }
+        }
+        "favorite" => writeln!(
+            out,
+            "{}",
+            if value.as_bool().unwrap_or(false) {
+                "favorited"
+            } else {
+                "unfavorited"
+            }
+        )?,
+        "add" => {
+            if let Some(id) = value.as_str() {
+                writeln!(out, "{}", id)?;
+            }
+        }
+        "clear" => writeln!(
+            out,
+            "{} items moved to the trash",
+            value.as_i64().unwrap_or(0)
+        )?,
+        _ => {}
+    }
+    out.flush()
+}
+
+/// One line describing a listed item.
+fn preview(item: &Value) -> String {
+    let text = match item["content_type"].as_str() {
+        Some("image") => format!(
+            "[image {}x{}]",
+            item["image_width"].as_i64().unwrap_or(0),
+            item["image_height"].as_i64().unwrap_or(0)
+        ),
+        Some("file") => item["file_name"].as_str().unwrap_or_default().to_string(),
+        _ => item["plain_text"].as_str().unwrap_or_default().to_string(),
+    };
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(100) {
+        Some((at, _)) => format!("{}…", &line[..at]),
+        None => line,
+    }
+}
@@ -0,0 +1,262 @@
+use super::lock as lock_cmd;
+use crate::archive::{
+    self, ArchiveGroup, ArchiveItem, ArchiveReader, ArchiveSetting, ArchiveWriter,
+};
+use crate::clipboard as clip_util;
+use crate::crypto;
+use crate::db::{
+    models::{ContentType, ExportFilter, ExportSummary, ImportItem, ImportSummary},
+    queries, DbPool,
+};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Export the live history matching `filter` (all items if None) to a zip
+/// archive at `path`, together with the groups and portable settings.
+#[tauri::command]
+pub async fn export_history(
+    db: State<'_, DbPool>,
+    path: String,
+    filter: Option<ExportFilter>,
+) -> Result<ExportSummary, String> {
+    lock_cmd::ensure_unlocked()?;
+    let filter = filter.unwrap_or_default();
+    if let Some(ct) = filter
+        .content_types
+        .iter()
+        .find(|ct| ContentType::from_str(ct).is_none())
+    {
+        return Err(format!("Unknown content type: {}", ct));
+    }
+
+    let items = queries::get_export_items(&db.0, &filter)
+        .await
+        .map_err(|e| e.to_string())?;
+    let groups: Vec<ArchiveGroup> = queries::get_groups(&db.0)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|g| ArchiveGroup {
+            name: g.name,
+            sort_order: g.sort_order,
+        })
+        .collect();
+    let settings: Vec<ArchiveSetting> = queries::get_all_settings(&db.0)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|(key, _)| archive::is_portable_setting(key))
+        .map(|(key, value)| ArchiveSetting { key, value })
+        .collect();
+
+    let path = PathBuf::from(path);
+    let summary = tokio::task::spawn_blocking(move || {
+        let result = write_archive(&path, items, &groups, &settings);
+        if result.is_err() {
+            let _ = std::fs::remove_file(&path);
+        }
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    log::info!(
+        "Exported {} items ({} images)",
+        summary.items,
+        summary.images
+    );
+    Ok(summary)
+}
+
+/// Import an archive written by `export_history`. Items are merged by
+/// content_hash, so importing the same archive twice adds nothing new.
+/// Groups are matched by name; settings are applied only with `include_settings`.
+#[tauri::command]
+pub async fn import_history(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    path: String,
+    include_settings: Option<bool>,
+) -> Result<ImportSummary, String> {
+    lock_cmd::ensure_unlocked()?;
+    let app_data = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let mut summary = ImportSummary::default();
+
+    let path = PathBuf::from(path);
+    let (mut reader, items, groups, settings) = tokio::task::spawn_blocking(move || {
+        let mut reader = ArchiveReader::open(&path)?;
+        let items = reader.items()?;
+        let groups = reader.groups()?;
+        let settings = reader.settings()?;
+        Ok::<_, String>((reader, items, groups, settings))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    // Create the groups that don't exist here yet, in their exported order
+    let mut group_ids: HashMap<String, String> = HashMap::new();
+    for group in queries::get_groups(&db.0)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        group_ids.entry(group.name).or_insert(group.id);
+    }
+    let mut groups = groups;
+    groups.sort_by_key(|g| g.sort_order);
+    let names = groups
+        .into_iter()
+        .map(|g| g.name)
+        .chain(items.iter().flat_map(|i| i.groups.iter().cloned()));
+    for name in names {
+        if group_ids.contains_key(&name) {
+            continue;
+        }
+        let id = queries::create_group(&db.0, &name)
+            .await
+            .map_err(|e| e.to_string())?;
+        group_ids.insert(name, id);
+        summary.groups_created += 1;
+    }
+
+    // Image files are written only for content not already in the history
+    let existing = queries::get_content_hashes(&db.0)
+        .await
+        .map_err(|e| e.to_string())?;
+    let prepared = tokio::task::spawn_blocking(move || {
+        prepare_items(&mut reader, items, &existing, &group_ids, &app_data)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    for (index, import) in prepared.iter().enumerate() {
+        match queries::import_item(&db.0, import).await {
+            Ok(true) => summary.imported += 1,
+            Ok(false) => {
+                summary.merged += 1;
+                remove_images(std::slice::from_ref(import));
+            }
+            Err(e) => {
+                remove_images(&prepared[index..]);
+                return Err(e.to_string());
+            }
+        }
+    }
+
+    if include_settings.unwrap_or(false) {
+        for setting in settings
+            .iter()
+            .filter(|s| archive::is_portable_setting(&s.key))
+        {
+            queries::set_setting(&db.0, &setting.key, &setting.value)
+                .await
+                .map_err(|e| e.to_string())?;
+            summary.settings += 1;
+        }
+    }
+
+    log::info!(
+        "Imported {} items, merged {} duplicates",
+        summary.imported,
+        summary.merged
+    );
+    let _ = app.emit("clipboard-changed", serde_json::json!({}));
+    Ok(summary)
+}
+
+/// Write items (with their original images), groups and settings to a new archive.
+/// Images that can no longer be read are left out; the item is still exported.
+fn write_archive(
+    path: &Path,
+    items: Vec<(ArchiveItem, Option<String>)>,
+    groups: &[ArchiveGroup],
+    settings: &[ArchiveSetting],
+) -> Result<ExportSummary, String> {
+    let mut writer = ArchiveWriter::create(path)?;
+    let mut images = 0;
+    let mut out = Vec::with_capacity(items.len());
+
+    for (mut item, image_path) in items {
+        if let Some(local) = image_path {
+            let entry = archive::image_entry_name(&local);
+            match (entry, crypto::read_image_file(&local)) {
+                (Some(entry), Ok(data)) => {
+                    writer.add_image(&entry, &data)?;
+                    item.image_file = Some(entry);
+                    images += 1;
+                }
+                (_, Err(e)) => log::warn!("Skipping image {}: {}", local, e),
+                (None, Ok(_)) => log::warn!("Skipping image with invalid path {}", local),
+            }
+        }
+        out.push(item);
+    }
+
+    writer.finish(&out, groups, settings)?;
+    Ok(ExportSummary {
+        items: out.len(),
+        images,
+    })
+}
+
+/// Turn archive items into insertable items, saving the images of new content
+/// (sealed while the history is encrypted). Items of unknown type are skipped.
+fn prepare_items(
+    reader: &mut ArchiveReader,
+    items: Vec<ArchiveItem>,
+    existing: &HashSet<String>,
+    group_ids: &HashMap<String, String>,
+    app_data: &Path,
+) -> Result<Vec<ImportItem>, String> {
+    let mut out: Vec<ImportItem> = Vec::with_capacity(items.len());
+
+    for item in items {
+        let Some(content_type) = ContentType::from_str(&item.content_type) else {
+            log::warn!("Skipping item of unknown type {}", item.content_type);
+            continue;
+        };
+        let image_path = match &item.image_file {
+            Some(entry) if !existing.contains(&item.content_hash) => {
+                match save_image(reader, entry, app_data) {
+                    Ok(path) => Some(path),
+                    Err(e) => {
+                        remove_images(&out);
+                        return Err(e);
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        let group_ids = item
+            .groups
+            .iter()
+            .filter_map(|name| group_ids.get(name).cloned())
+            .collect();
+        out.push(item.into_import(content_type, image_path, group_ids));
+    }
+
+    Ok(out)
+}
+
+fn save_image(reader: &mut ArchiveReader, entry: &str, app_data: &Path) -> Result<String, String> {
+    let data = reader.read_image(entry)?;
+    let mut ext = Path::new(entry)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("png")
+        .to_string();
+    if crypto::is_enabled() {
+        ext.push_str(crypto::FILE_SUFFIX);
+    }
+    clip_util::save_original_image(app_data, &crypto::seal_bytes(&data), &ext)
+}
+
+/// Remove the image files saved for items that did not end up in the history.
+fn remove_images(items: &[ImportItem]) {
+    for path in items.iter().filter_map(|i| i.item.image_path.as_deref()) {
+        if let Err(e) = std::fs::remove_file(path) {
+            log::warn!("Failed to remove image file {}: {}", path, e);
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use super::lock as lock_cmd;
+use crate::backup::{self, Snapshot};
+use crate::db::{self, models::BackupInfo, queries, DbPool};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, State};
+
+/// Set while a snapshot is being written, so scheduled and manual backups don't overlap.
+static BACKUP_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// List the available snapshots, newest first.
+#[tauri::command]
+pub async fn list_backups(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+) -> Result<Vec<BackupInfo>, String> {
+    let dir = backup_dir(&app, &db.0).await?;
+    tokio::task::spawn_blocking(move || {
+        backup::list_snapshots(&dir)
+            .iter()
+            .map(backup_info)
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Take a snapshot now, regardless of the schedule.
+#[tauri::command]
+pub async fn create_backup(app: AppHandle, db: State<'_, DbPool>) -> Result<BackupInfo, String> {
+    run_backup(&app, &db.0).await
+}
+
+/// Replace the database and images with a snapshot, then restart the app.
+/// The current database is kept next to it as `recopy.db.pre-restore`.
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    id: String,
+) -> Result<(), String> {
+    lock_cmd::ensure_unlocked()?;
+    let dir = backup_dir(&app, &db.0).await?;
+    let snapshot = backup::list_snapshots(&dir)
+        .into_iter()
+        .find(|s| s.id == id)
+        .ok_or_else(|| format!("Backup not found: {}", id))?;
+    backup::verify_database(&snapshot.path.join(backup::DB_FILE)).await?;
+
+    let app_data = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    if BACKUP_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("A backup is in progress".to_string());
+    }
+
+    // Nothing may write to the database while its file is swapped out
+    db.0.close().await;
+    let result = tokio::task::spawn_blocking(move || restore_files(&snapshot, &app_data))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+    match &result {
+        Ok(()) => log::info!("Restored backup {}, restarting", id),
+        Err(e) => log::error!("Failed to restore backup {}: {}", id, e),
+    }
+
+    // The pool is closed either way; a restart reopens whichever database is in place
+    app.request_restart();
+    result
+}
+
+/// Called periodically by the backup task. Takes a snapshot when `backup_enabled`
+/// and the newest one is at least `backup_interval_hours` old.
+pub async fn run_scheduled_backup(app: &AppHandle) {
+    let Some(pool) = app.try_state::<DbPool>() else {
+        return;
+    };
+    let enabled = queries::get_setting(&pool.0, "backup_enabled")
+        .await
+        .unwrap_or(None)
+        .map(|v| v == "true")
+        .unwrap_or(true);
+    if !enabled {
+        return;
+    }
+    let interval_hours = queries::get_setting(&pool.0, "backup_interval_hours")
+        .await
+        .unwrap_or(None)
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(24)
+        .max(1);
+
+    let dir = match backup_dir(app, &pool.0).await {
+        Ok(dir) => dir,
+        Err(e) => {
+            log::warn!("Scheduled backup skipped: {}", e);
+            return;
+        }
+    };
+    let due = match backup::list_snapshots(&dir).first() {
+        Some(latest) => {
+            chrono::Utc::now().naive_utc() - latest.created_at
+                >= chrono::Duration::hours(interval_hours)
+        }
+        None => true,
+    };
+    if !due {
+        return;
+    }
+
+    match run_backup(app, &pool.0).await {
+        Ok(info) => log::info!("Scheduled backup {} written", info.id),
+        Err(e) => log::warn!("Scheduled backup failed: {}", e),
+    }
+}
+
+/// Delete every snapshot, when the history's protection changes and they would
+/// either leak it or no longer open. Waits for a backup in progress to finish first.
+pub async fn remove_backups(app: &AppHandle, pool: &SqlitePool) -> Result<usize, String> {
+    let dir = backup_dir(app, pool).await?;
+    while BACKUP_RUNNING.swap(true, Ordering::SeqCst) {
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    }
+    let removed = tokio::task::spawn_blocking(move || backup::remove_all(&dir))
+        .await
+        .map_err(|e| e.to_string());
+    BACKUP_RUNNING.store(false, Ordering::SeqCst);
+    removed?.map_err(|e| format!("Failed to remove backups: {}", e))
+}
+
+/// Write a new snapshot and rotate old ones out (keeping `backup_keep`).
+async fn run_backup(app: &AppHandle, pool: &SqlitePool) -> Result<BackupInfo, String> {
+    if BACKUP_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("A backup is already in progress".to_string());
+    }
+    let result = write_snapshot(app, pool).await;
+    BACKUP_RUNNING.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn write_snapshot(app: &AppHandle, pool: &SqlitePool) -> Result<BackupInfo, String> {
+    let app_data = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let dir = backup_dir(app, pool).await?;
+    let keep = queries::get_setting(pool, "backup_keep")
+        .await
+        .unwrap_or(None)
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(7);
+
+    let now = chrono::Utc::now();
+    let name = backup::snapshot_name(now);
+    let target = dir.join(&name);
+    if target.exists() {
+        return Err(format!("Backup {} already exists", name));
+    }
+    let partial = backup::partial_path(&dir, &name);
+    let _ = std::fs::remove_dir_all(&partial);
+    std::fs::create_dir_all(&partial)
+        .map_err(|e| format!("Failed to create backup folder: {}", e))?;
+
+    let db_copy = partial.join(backup::DB_FILE);
+    let expiring = match copy_database(pool, &db_copy).await {
+        Ok(expiring) => expiring,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&partial);
+            return Err(format!("Failed to copy database: {}", e));
+        }
+    };
+
+    let previous = backup::list_snapshots(&dir).into_iter().next();
+    tokio::task::spawn_blocking(move || {
+        let images = app_data.join(backup::IMAGES_DIR);
+        let prev_images = previous.map(|s| s.path.join(backup::IMAGES_DIR));
+        let copied = backup::copy_images(
+            &images,
+            &partial.join(backup::IMAGES_DIR),
+            prev_images.as_deref(),
+        )
+        .and_then(|_| remove_copied_images(&expiring, &images, &partial))
+        .and_then(|_| std::fs::rename(&partial, &target));
+        if let Err(e) = copied {
+            let _ = std::fs::remove_dir_all(&partial);
+            return Err(format!("Failed to write backup: {}", e));
+        }
+
+        if let Err(e) = backup::rotate(&dir, keep) {
+            log::warn!("Failed to remove old backups: {}", e);
+        }
+        Ok(Snapshot {
+            id: name,
+            path: target,
+            created_at: now.naive_utc(),
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map(|snapshot| backup_info(&snapshot))
+}
+
+/// Copy the database to `path`, without the items due to expire: they would
+/// otherwise outlive their expiry in every kept snapshot. Returns the image
+/// paths of the items left out.
+async fn copy_database(pool: &SqlitePool, path: &Path) -> Result<Vec<String>, String> {
+    queries::vacuum_into(pool, &path.to_string_lossy())
+        .await
+        .map_err(|e| e.to_string())?;
+    let copy = db::open_copy(path).await.map_err(|e| e.to_string())?;
+    let expiring = queries::strip_expiring_items(&copy).await;
+    copy.close().await;
+    expiring.map_err(|e| e.to_string())
+}
+
+/// Remove the snapshot's copies of `paths`, images under `images`.
+fn remove_copied_images(paths: &[String], images: &Path, snapshot: &Path) -> std::io::Result<()> {
+    let copied = snapshot.join(backup::IMAGES_DIR);
+    for path in paths {
+        let Ok(relative) = Path::new(path).strip_prefix(images) else {
+            continue;
+        };
+        match std::fs::remove_file(copied.join(relative)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Swap in the snapshot database and copy its images back. The current
+/// database is moved aside first and put back if the copy fails.
+fn restore_files(snapshot: &Snapshot, app_data: &Path) -> Result<(), String> {
+    let live = app_data.join(backup::DB_FILE);
+    let aside = app_data.join(format!("{}.pre-restore", backup::DB_FILE));
+
+    std::fs::rename(&live, &aside)
+        .map_err(|e| format!("Failed to move the current database aside: {}", e))?;
+    // WAL and shared-memory files belong to the database just moved aside
+    for suffix in ["-wal", "-shm"] {
+        let _ = std::fs::remove_file(app_data.join(format!("{}{}", backup::DB_FILE, suffix)));
+    }
+
+    if let Err(e) = std::fs::copy(snapshot.path.join(backup::DB_FILE), &live) {
+        let _ = std::fs::rename(&aside, &live);
+        return Err(format!("Failed to copy the backup database: {}", e));
+    }
+
+    // Images the restored database no longer references are removed by the
+    // orphan cleanup on the next start
+    backup::copy_images(
+        &snapshot.path.join(backup::IMAGES_DIR),
+        &app_data.join(backup::IMAGES_DIR),
+        None,
+    )
+    .map_err(|e| format!("Failed to restore images: {}", e))?;
+    Ok(())
+}
+
+/// The configured `backup_dir`, or `backups/` in the app data folder.
+async fn backup_dir(app: &AppHandle, pool: &SqlitePool) -> Result<PathBuf, String> {
+    let custom = queries::get_setting(pool, "backup_dir")
+        .await
+        .map_err(|e| e.to_string())?
+        .filter(|v| !v.trim().is_empty());
+    match custom {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => Ok(app
+            .path()
+            .app_data_dir()
+            .map_err(|e| e.to_string())?
+            .join("backups")),
+    }
+}
+
+fn backup_info(snapshot: &Snapshot) -> BackupInfo {
+    BackupInfo {
+        id: snapshot.id.clone(),
+        created_at: snapshot.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        size: backup::tree_size(&snapshot.path),
+    }
+}
@@ -1,11 +1,18 @@
+use super::ignored_apps as ignore_cmd;
+use super::lock as lock_cmd;
+use super::paste_queue as queue_cmd;
+use super::transforms as transform_cmd;
 use crate::clipboard as clip_util;
+use crate::crypto;
 use crate::db::{
     models::{
-        ClipboardItem, ContentType, FilePreviewData, ItemDetail, NewClipboardItem, PreviewClosing,
-        PreviewResponse, PreviewState,
+        ClipboardItem, ContentType, FilePreviewData, ImageFingerprint, ItemDetail, ItemFile,
+        NewClipboardItem, PreviewClosing, PreviewResponse, PreviewState,
     },
     queries, DbPool,
 };
+use crate::merge;
+use crate::platform::FrontmostApp;
 use std::process::Command;
 use std::sync::atomic::{AtomicU64, Ordering};
 use tauri::{AppHandle, Emitter, Manager, State};
@@ -20,6 +27,7 @@ pub async fn get_clipboard_items(
     limit: Option<i64>,
     offset: Option<i64>,
 ) -> Result<Vec<ClipboardItem>, String> {
+    lock_cmd::ensure_unlocked()?;
     let limit = limit.unwrap_or(50);
     let offset = offset.unwrap_or(0);
     let ct = content_type.as_deref();
@@ -38,6 +46,8 @@ pub async fn search_clipboard_items(
     limit: Option<i64>,
     favorites_only: Option<bool>,
 ) -> Result<Vec<ClipboardItem>, String> {
+    lock_cmd::ensure_unlocked()?;
+    lock_cmd::touch();
     let limit = limit.unwrap_or(50);
     let ct = content_type.as_deref();
 
@@ -49,13 +59,28 @@ pub async fn search_clipboard_items(
 /// Get the thumbnail for a single clipboard item (lazy loading).
 #[tauri::command]
 pub async fn get_thumbnail(db: State<'_, DbPool>, id: String) -> Result<Option<Vec<u8>>, String> {
+    lock_cmd::ensure_unlocked()?;
     queries::get_thumbnail(&db.0, &id)
         .await
         .map_err(|e| e.to_string())
 }
 
+/// Get the saved original of an image item, decrypted if the history is encrypted.
+#[tauri::command]
+pub async fn get_image_data(db: State<'_, DbPool>, id: String) -> Result<Vec<u8>, String> {
+    lock_cmd::ensure_unlocked()?;
+    let path = queries::get_image_path_by_id(&db.0, &id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Item has no image")?;
+    tokio::task::spawn_blocking(move || crypto::read_image_file(&path))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
 /// Internal helper to load full item detail from DB.
 async fn load_item_detail(db: &DbPool, id: &str) -> Result<ItemDetail, String> {
+    lock_cmd::ensure_unlocked()?;
     let row = queries::get_item_detail(&db.0, id)
         .await
         .map_err(|e| e.to_string())?
@@ -82,27 +107,22 @@ pub async fn get_item_detail(db: State<'_, DbPool>, id: String) -> Result<ItemDe
     load_item_detail(&db, &id).await
 }
 
-/// Delete a clipboard item and remove its original image file if present.
+/// Get all paths of a file item, in clipboard order.
 #[tauri::command]
-pub async fn delete_clipboard_item(db: State<'_, DbPool>, id: String) -> Result<(), String> {
-    // Capture image_path before deleting the DB row
-    let image_path = queries::get_image_path_by_id(&db.0, &id)
+pub async fn get_item_files(db: State<'_, DbPool>, id: String) -> Result<Vec<ItemFile>, String> {
+    lock_cmd::ensure_unlocked()?;
+    queries::get_item_files(&db.0, &id)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())
+}
 
-    queries::delete_item(&db.0, &id)
+/// Move a clipboard item to the trash. Its image file is kept until the trash is emptied.
+#[tauri::command]
+pub async fn delete_clipboard_item(db: State<'_, DbPool>, id: String) -> Result<(), String> {
+    lock_cmd::ensure_unlocked()?;
+    queries::trash_item(&db.0, &id)
         .await
         .map_err(|e| e.to_string())?;
-
-    // Async file removal — best-effort, does not fail the command
-    if let Some(path) = image_path {
-        tokio::spawn(async move {
-            if let Err(e) = tokio::fs::remove_file(&path).await {
-                log::warn!("Failed to delete image file {}: {}", path, e);
-            }
-        });
-    }
-
     Ok(())
 }
 
@@ -114,12 +134,26 @@ pub async fn paste_clipboard_item(
     id: String,
     auto_paste: Option<bool>,
 ) -> Result<(), String> {
+    lock_cmd::ensure_unlocked()?;
+    lock_cmd::touch();
     let row = queries::get_item_by_id(&db.0, &id)
         .await
         .map_err(|e| e.to_string())?
         .ok_or("Item not found")?;
 
-    let (content_type, plain_text, rich_content, image_path, file_path) = row;
+    let (content_type, plain_text, rich_content, image_path, _file_path) = row;
+
+    // File items paste every stored path, not just the first
+    let file_paths: Vec<String> = if content_type == "file" {
+        queries::get_item_files(&db.0, &id)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|f| f.path)
+            .collect()
+    } else {
+        Vec::new()
+    };
 
     // Skip self-monitoring for this clipboard write (clear flag on failure)
     crate::set_skip_next_clipboard_change();
@@ -129,7 +163,7 @@ pub async fn paste_clipboard_item(
         &plain_text,
         &rich_content,
         &image_path,
-        &file_path,
+        &file_paths,
     )
     .await
     {
@@ -155,6 +189,8 @@ pub async fn paste_as_plain_text(
     db: State<'_, DbPool>,
     id: String,
 ) -> Result<(), String> {
+    lock_cmd::ensure_unlocked()?;
+    lock_cmd::touch();
     let row = queries::get_item_by_id(&db.0, &id)
         .await
         .map_err(|e| e.to_string())?
@@ -177,9 +213,116 @@ pub async fn paste_as_plain_text(
     Ok(())
 }
 
+/// Source recorded for merged items saved to history.
+const MERGE_SOURCE: &str = "Recopy";
+
+/// Paste several text items as one, in the given order. `format` is one of
+/// "newline" (default), "comma", "tab", "markdown_list" or "json_array".
+/// With `save`, the merged text is also added to history; returns its id.
+#[tauri::command]
+pub async fn paste_merged_items(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    ids: Vec<String>,
+    format: Option<String>,
+    save: Option<bool>,
+    auto_paste: Option<bool>,
+) -> Result<Option<String>, String> {
+    lock_cmd::ensure_unlocked()?;
+    lock_cmd::touch();
+    if ids.len() < 2 {
+        return Err("Select at least two items to merge".to_string());
+    }
+    let format = match format.as_deref() {
+        Some(name) => {
+            merge::Format::from_str(name).ok_or_else(|| format!("Unknown format {}", name))?
+        }
+        None => merge::Format::default(),
+    };
+
+    let mut rows = Vec::with_capacity(ids.len());
+    for id in &ids {
+        let (content_type, plain_text, rich_content, _image_path, _file_path) =
+            queries::get_item_by_id(&db.0, id)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or("Item not found")?;
+        if !matches!(content_type.as_str(), "plain_text" | "rich_text" | "link") {
+            return Err("Only text items can be merged".to_string());
+        }
+        let html = rich_content
+            .filter(|_| content_type == "rich_text")
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string());
+        rows.push((plain_text, html));
+    }
+    let parts: Vec<merge::Part> = rows
+        .iter()
+        .map(|(text, html)| merge::Part {
+            text,
+            html: html.as_deref(),
+        })
+        .collect();
+    let merged = merge::merge(&parts, format);
+    let (content_type, rich_content) = match merged.html {
+        Some(html) => (ContentType::RichText, Some(html.into_bytes())),
+        None => (ContentType::PlainText, None),
+    };
+
+    let saved = if save.unwrap_or(false) {
+        let source = FrontmostApp {
+            id: MERGE_SOURCE.to_string(),
+            name: MERGE_SOURCE.to_string(),
+            window_title: String::new(),
+            executable: String::new(),
+        };
+        let id = process_clipboard_change(
+            &app,
+            content_type.clone(),
+            merged.text.as_bytes().to_vec(),
+            Some(merged.text.clone()),
+            rich_content.clone(),
+            Vec::new(),
+            source,
+            clip_util::CaptureDecision::Record,
+        )
+        .await?;
+        if let Some(id) = &id {
+            let _ = app.emit("clipboard-changed", serde_json::json!({ "id": id }));
+        }
+        id
+    } else {
+        None
+    };
+
+    // Skip self-monitoring for this clipboard write (clear flag on failure)
+    crate::set_skip_next_clipboard_change();
+    if let Err(e) = write_to_clipboard(
+        &app,
+        content_type.as_str(),
+        &merged.text,
+        &rich_content,
+        &None,
+        &[],
+    )
+    .await
+    {
+        crate::clear_skip_next_clipboard_change();
+        return Err(e);
+    }
+
+    if auto_paste.unwrap_or(true) {
+        // Resign keyboard focus so the previous app receives the Cmd+V
+        crate::platform::platform_resign_before_paste(&app);
+        simulate_paste();
+        crate::hide_main_window(&app);
+    }
+    Ok(saved)
+}
+
 /// Toggle favorite status of a clipboard item.
 #[tauri::command]
 pub async fn toggle_favorite(db: State<'_, DbPool>, id: String) -> Result<bool, String> {
+    lock_cmd::ensure_unlocked()?;
     let current: (bool,) = sqlx::query_as("SELECT is_favorited FROM clipboard_items WHERE id = ?")
         .bind(&id)
         .fetch_one(&db.0)
@@ -197,37 +340,109 @@ pub async fn toggle_favorite(db: State<'_, DbPool>, id: String) -> Result<bool,
     Ok(new_val)
 }
 
+/// Resolve the file an image is pasted from. Encrypted originals are decrypted
+/// into a folder under the app data that only the user can open, for the
+/// platform writers to read; the bool says to remove the file after.
+fn image_for_paste(app: &AppHandle, path: &str) -> Result<(String, bool), String> {
+    let Some(plain_path) = path.strip_suffix(crypto::FILE_SUFFIX) else {
+        return Ok((path.to_string(), false));
+    };
+    let data = crypto::read_image_file(path)?;
+    let ext = std::path::Path::new(plain_path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_else(|| "png".to_string());
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("paste");
+    let tmp = dir.join(format!("{}.{}", uuid::Uuid::new_v4(), ext));
+    write_private_file(&dir, &tmp, &data)
+        .map_err(|e| format!("Failed to write temp image: {}", e))?;
+    Ok((tmp.to_string_lossy().to_string(), true))
+}
+
+/// Write `data` to a new file in `dir`, both readable by the owner only on Unix.
+fn write_private_file(
+    dir: &std::path::Path,
+    path: &std::path::Path,
+    data: &[u8],
+) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+        builder.mode(0o700);
+        options.mode(0o600);
+        builder.create(dir)?;
+        // The folder may predate this, or have been created with other permissions
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    #[cfg(not(unix))]
+    builder.create(dir)?;
+    let result = options.open(path)?.write_all(data);
+    if result.is_err() {
+        let _ = std::fs::remove_file(path);
+    }
+    result
+}
+
+/// Write an image file to the system clipboard.
+async fn write_image_to_clipboard(path: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        crate::platform::platform_write_image_to_pasteboard(path)?;
+    }
+    #[cfg(target_os = "windows")]
+    {
+        // Bitmap and original encoding in one clipboard update
+        let path = path.to_string();
+        tokio::task::spawn_blocking(move || crate::platform::platform_write_image(&path))
+            .await
+            .map_err(|e| e.to_string())??;
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        tauri_plugin_clipboard_x::write_image(path.to_string())
+            .await
+            .map_err(|e| format!("Failed to write image: {}", e))?;
+    }
+    Ok(())
+}
+
 /// Write content to system clipboard based on type.
-async fn write_to_clipboard(
-    _app: &AppHandle,
+pub(crate) async fn write_to_clipboard(
+    app: &AppHandle,
     content_type: &str,
     plain_text: &str,
     rich_content: &Option<Vec<u8>>,
     image_path: &Option<String>,
-    file_path: &Option<String>,
+    file_paths: &[String],
 ) -> Result<(), String> {
     match content_type {
         "image" => {
             if let Some(path) = image_path {
                 let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
                 log::info!("Pasting image from path: {} ({}B)", path, file_size);
-                #[cfg(target_os = "macos")]
-                {
-                    crate::platform::platform_write_image_to_pasteboard(path)?;
-                }
-                #[cfg(not(target_os = "macos"))]
-                {
-                    tauri_plugin_clipboard_x::write_image(path.clone())
-                        .await
-                        .map_err(|e| format!("Failed to write image: {}", e))?;
+                let (path, temporary) = image_for_paste(app, path)?;
+                let result = write_image_to_clipboard(&path).await;
+                if temporary {
+                    let _ = std::fs::remove_file(&path);
                 }
+                result?;
             } else {
                 log::warn!("Paste image: image_path is None!");
             }
         }
         "file" => {
-            if let Some(path) = file_path {
-                tauri_plugin_clipboard_x::write_files(vec![path.clone()])
+            if !file_paths.is_empty() {
+                tauri_plugin_clipboard_x::write_files(file_paths.to_vec())
                     .await
                     .map_err(|e| format!("Failed to write files: {}", e))?;
             }
@@ -255,7 +470,7 @@ async fn write_to_clipboard(
 }
 
 /// Simulate Ctrl+V / Cmd+V paste via platform-specific mechanism.
-fn simulate_paste() {
+pub(crate) fn simulate_paste() {
     #[cfg(target_os = "macos")]
     {
         simulate_paste_cgevent();
@@ -266,6 +481,59 @@ fn simulate_paste() {
     }
 }
 
+/// Press Left `count` times after a paste, to put the caret where a snippet's
+/// `{{cursor}}` was.
+pub(crate) fn simulate_caret_left(count: usize) {
+    #[cfg(target_os = "macos")]
+    {
+        simulate_left_cgevent(count);
+    }
+    #[cfg(target_os = "windows")]
+    {
+        crate::platform::simulate_left_keys(count);
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let _ = count;
+}
+
+/// Press the Left arrow key `count` times via CGEvent. Runs after
+/// simulate_paste_cgevent, which has already checked PostEvent access.
+#[cfg(target_os = "macos")]
+fn simulate_left_cgevent(count: usize) {
+    use std::ffi::c_void;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventCreateKeyboardEvent(
+            source: *const c_void,
+            virtual_key: u16,
+            key_down: bool,
+        ) -> *mut c_void;
+        fn CGEventPost(tap: u32, event: *const c_void);
+    }
+
+    extern "C" {
+        fn CFRelease(cf: *const c_void);
+    }
+
+    const VK_LEFT_ARROW: u16 = 0x7B;
+    const CG_SESSION_EVENT_TAP: u32 = 1; // cgAnnotatedSessionEventTap
+
+    unsafe {
+        for _ in 0..count {
+            for key_down in [true, false] {
+                let event = CGEventCreateKeyboardEvent(std::ptr::null(), VK_LEFT_ARROW, key_down);
+                if event.is_null() {
+                    log::warn!("CGEvent: failed to create arrow key event");
+                    return;
+                }
+                CGEventPost(CG_SESSION_EVENT_TAP, event);
+                CFRelease(event);
+            }
+        }
+    }
+}
+
 /// Simulate Cmd+V using macOS CGEvent API (CoreGraphics).
 /// Uses CGEventSource(combinedSessionState) for reliable event delivery,
 /// adds left-Command flag (0x000008) for Emacs-style app compatibility,
@@ -380,6 +648,7 @@ pub async fn get_favorited_items(
     limit: Option<i64>,
     offset: Option<i64>,
 ) -> Result<Vec<ClipboardItem>, String> {
+    lock_cmd::ensure_unlocked()?;
     queries::get_favorited_items(
         &db.0,
         content_type.as_deref(),
@@ -422,6 +691,19 @@ pub async fn set_setting(
     key: String,
     value: String,
 ) -> Result<(), String> {
+    // Encryption settings change only together with the data (see commands::encryption),
+    // the lock passphrase only through set_lock_passphrase, the sync identity,
+    // pairing key and folder only through commands::sync, and the HTTP API only
+    // through commands::http_api
+    if key.starts_with("encryption_")
+        || key.starts_with("app_lock_")
+        || key == "sync_key"
+        || key == "sync_device_id"
+        || key.starts_with("sync_folder")
+        || key.starts_with("http_api_")
+    {
+        return Err(format!("Setting {} is read-only", key));
+    }
     queries::set_setting(&db.0, &key, &value)
         .await
         .map_err(|e| e.to_string())?;
@@ -469,36 +751,24 @@ pub async fn register_shortcut(app: AppHandle, db: State<'_, DbPool>) -> Result<
                 }
             }
         })
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    // unregister_all also dropped the paste queue's and transform shortcuts
+    let queued = queue_cmd::refresh_shortcut(&app).await;
+    let transforms = transform_cmd::refresh_shortcuts(&app).await;
+    queued.and(transforms)
 }
 
-/// Clear all clipboard history (preserve favorites), removing image files from disk.
+/// Clear all clipboard history (preserve favorites) by moving it to the trash.
 #[tauri::command]
 pub async fn clear_history(db: State<'_, DbPool>) -> Result<i64, String> {
-    // Collect image paths before deleting rows
-    let image_paths = queries::get_non_favorited_image_paths(&db.0)
+    lock_cmd::ensure_unlocked()?;
+    queries::clear_history(&db.0)
         .await
-        .map_err(|e| e.to_string())?;
-
-    let count = queries::clear_history(&db.0)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // Async file removal — best-effort
-    if !image_paths.is_empty() {
-        tokio::spawn(async move {
-            for path in image_paths {
-                if let Err(e) = tokio::fs::remove_file(&path).await {
-                    log::warn!("Failed to delete image file {}: {}", path, e);
-                }
-            }
-        });
-    }
-
-    Ok(count)
+        .map_err(|e| e.to_string())
 }
 
-/// Run retention cleanup based on current settings, removing image files from disk.
+/// Run retention cleanup based on current settings, moving overflow items to the trash.
 #[tauri::command]
 pub async fn run_retention_cleanup(db: State<'_, DbPool>) -> Result<i64, String> {
     let policy = queries::get_setting(&db.0, "retention_policy")
@@ -518,27 +788,9 @@ pub async fn run_retention_cleanup(db: State<'_, DbPool>) -> Result<i64, String>
         .and_then(|v| v.parse::<i64>().ok())
         .unwrap_or(0);
 
-    // Collect image paths before deleting rows
-    let image_paths = queries::get_retention_overflow_image_paths(&db.0, &policy, days, count)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let deleted = queries::cleanup_by_retention(&db.0, &policy, days, count)
+    queries::cleanup_by_retention(&db.0, &policy, days, count)
         .await
-        .map_err(|e| e.to_string())?;
-
-    // Async file removal — best-effort
-    if !image_paths.is_empty() {
-        tokio::spawn(async move {
-            for path in image_paths {
-                if let Err(e) = tokio::fs::remove_file(&path).await {
-                    log::warn!("Failed to delete image file {}: {}", path, e);
-                }
-            }
-        });
-    }
-
-    Ok(deleted)
+        .map_err(|e| e.to_string())
 }
 
 /// Scan for orphan image files on disk not referenced in the DB and delete them.
@@ -782,7 +1034,18 @@ fn calculate_preview_size(detail: &ItemDetail, available_w: f64, available_h: f6
     match detail.content_type.as_str() {
         "image" => {
             if let Some(ref path) = detail.image_path {
-                if let Ok((w, h)) = image::image_dimensions(path) {
+                let dimensions = if path.ends_with(crypto::FILE_SUFFIX) {
+                    crypto::read_image_file(path).ok().and_then(|data| {
+                        image::ImageReader::new(std::io::Cursor::new(data))
+                            .with_guessed_format()
+                            .ok()?
+                            .into_dimensions()
+                            .ok()
+                    })
+                } else {
+                    image::image_dimensions(path).ok()
+                };
+                if let Some((w, h)) = dimensions {
                     let (iw, ih) =
                         fit_image(w, h, max_w - img_chrome_x, max_h - img_chrome_y, min_h);
                     return (iw + img_chrome_x, ih + img_chrome_y);
@@ -1035,6 +1298,48 @@ fn is_image_file(path: &str) -> bool {
         || lower.ends_with(".ico")
 }
 
+/// Store text handed over by another program (e.g. `recopy-cli add`) as if it
+/// had been copied: a pure http(s) URL becomes a link, anything else plain text.
+/// Returns the new or bumped item's id, None if it was not recorded.
+pub async fn add_text_item(
+    app: &AppHandle,
+    text: String,
+    source: FrontmostApp,
+) -> Result<Option<String>, String> {
+    if text.trim().is_empty() {
+        return Err("Nothing to add".to_string());
+    }
+    // The monitor checks the ignore list itself, before reading the clipboard
+    let db = app.state::<DbPool>();
+    if ignore_cmd::is_source_ignored(&db.0, &source).await {
+        log::info!("Skipping text added by ignored source: {}", source.id);
+        return Ok(None);
+    }
+    let trimmed = text.trim();
+    let is_link = url::Url::parse(trimmed).is_ok_and(|u| matches!(u.scheme(), "http" | "https"));
+    let (content_type, text) = if is_link {
+        (ContentType::Link, trimmed.to_string())
+    } else {
+        (ContentType::PlainText, text)
+    };
+
+    let id = process_clipboard_change(
+        app,
+        content_type,
+        text.as_bytes().to_vec(),
+        Some(text),
+        None,
+        Vec::new(),
+        source,
+        clip_util::CaptureDecision::Record,
+    )
+    .await?;
+    if let Some(id) = &id {
+        let _ = app.emit("clipboard-changed", serde_json::json!({ "id": id }));
+    }
+    Ok(id)
+}
+
 /// Process and store a new clipboard entry from the monitoring system.
 /// Called internally, not directly from frontend.
 #[allow(clippy::too_many_arguments)]
@@ -1044,13 +1349,24 @@ pub async fn process_clipboard_change(
     content: Vec<u8>,
     plain_text: Option<String>,
     rich_content: Option<Vec<u8>>,
-    file_path: Option<String>,
-    file_name: Option<String>,
-    source_app: String,
-    source_app_name: String,
+    files: Vec<ItemFile>,
+    source: FrontmostApp,
+    decision: clip_util::CaptureDecision,
 ) -> Result<Option<String>, String> {
     let db = app.state::<DbPool>();
 
+    let mut expire_after = match decision {
+        clip_util::CaptureDecision::Record => None,
+        clip_util::CaptureDecision::Skip => return Ok(None),
+        clip_util::CaptureDecision::Expire(secs) => Some(secs),
+    };
+
+    // Nothing can be stored while the encrypted history is locked
+    if crypto::is_locked() {
+        log::info!("History is locked, skipping clipboard change");
+        return Ok(None);
+    }
+
     // Size check with dynamic limit from settings
     let max_size_mb = queries::get_setting(&db.0, "max_item_size_mb")
         .await
@@ -1066,47 +1382,132 @@ pub async fn process_clipboard_change(
         return Ok(None);
     }
 
-    // Compute hash for dedup
-    let hash = clip_util::compute_hash(&content);
+    // Compute hash for dedup; text can be normalized first so copies that differ
+    // only in line endings or trailing whitespace collapse into one entry
+    let normalize_text = matches!(
+        content_type,
+        ContentType::PlainText | ContentType::RichText | ContentType::Link
+    ) && queries::get_setting(&db.0, "normalize_text_dedup")
+        .await
+        .unwrap_or(None)
+        .map(|v| v != "false")
+        .unwrap_or(true);
+    let hash = if normalize_text {
+        let normalized = clip_util::normalize_for_dedup(&String::from_utf8_lossy(&content));
+        clip_util::compute_hash(normalized.as_bytes())
+    } else {
+        clip_util::compute_hash(&content)
+    };
 
-    // Dedup check
-    if let Some(existing_id) = queries::find_and_bump_by_hash(&db.0, &hash)
+    // Decode images once up front: the perceptual hash feeds the dedup check below
+    // Note: For file-type images, thumbnail is generated asynchronously after insert (see below)
+    let (thumbnail, image_phash, image_info) = if content_type == ContentType::Image {
+        let content_for_img = content.clone();
+        tokio::task::spawn_blocking(move || {
+            let info = clip_util::sniff_image(&content_for_img)
+                .map_err(|e| log::warn!("Failed to sniff image: {}", e))
+                .ok();
+            match clip_util::generate_thumbnail_and_phash(&content_for_img) {
+                Ok((thumb, phash)) => (Some(thumb), Some(phash as i64), info),
+                Err(_) => (None, None, info),
+            }
+        })
         .await
-        .map_err(|e| e.to_string())?
+        .unwrap_or((None, None, None))
+    } else {
+        (None, None, None)
+    };
+
+    // Images within this many bits of an existing one count as duplicates (0 = exact bytes only)
+    let dedup_distance = queries::get_setting(&db.0, "image_dedup_distance")
+        .await
+        .unwrap_or(None)
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(clip_util::DEFAULT_IMAGE_DEDUP_DISTANCE)
+        .min(64);
+
+    // Dedup check
+    if let Some(existing_id) = queries::find_and_bump_by_hash(
+        &db.0,
+        &hash,
+        image_phash
+            .zip(image_info.as_ref())
+            .map(|(phash, info)| ImageFingerprint {
+                phash,
+                width: info.width as i64,
+                height: info.height as i64,
+            })
+            .filter(|_| dedup_distance > 0),
+        dedup_distance,
+    )
+    .await
+    .map_err(|e| e.to_string())?
     {
         log::info!("Duplicate content detected, bumped item {}", existing_id);
         return Ok(Some(existing_id));
     }
 
-    // Process image: generate thumbnail and save original (off the async runtime)
-    // Note: For file-type images, thumbnail is generated asynchronously after insert (see below)
-    let (thumbnail, image_path) = if content_type == ContentType::Image {
+    // Save the original image bytes in whatever encoding the clipboard held
+    // (sealed, with an ".enc" suffix, while the history is encrypted)
+    let image_path = if content_type == ContentType::Image {
         let app_data = app.path().app_data_dir().map_err(|e| e.to_string())?;
+        let mut ext = image_info
+            .as_ref()
+            .map_or("png", |i| i.ext.as_str())
+            .to_string();
+        if crypto::is_enabled() {
+            ext.push_str(crypto::FILE_SUFFIX);
+        }
         let content_for_img = content.clone();
         tokio::task::spawn_blocking(move || {
-            let thumb = clip_util::generate_thumbnail(&content_for_img).ok();
-            let path = clip_util::save_original_image(&app_data, &content_for_img, "png").ok();
-            (thumb, path)
+            let data = crypto::seal_bytes(&content_for_img);
+            clip_util::save_original_image(&app_data, &data, &ext).ok()
         })
         .await
-        .unwrap_or((None, None))
+        .unwrap_or(None)
     } else {
-        (None, None)
+        None
     };
 
-    // For file items, use the actual file size instead of the path string length
-    let content_size = if content_type == ContentType::File {
-        match &file_path {
-            Some(fp) => tokio::fs::metadata(fp)
-                .await
-                .map(|m| m.len() as i64)
-                .unwrap_or(content.len() as i64),
-            None => content.len() as i64,
+    // Flag secrets in text content: masked in lists, excluded from search, auto-expired
+    let detection_enabled = queries::get_setting(&db.0, "sensitive_detection")
+        .await
+        .unwrap_or(None)
+        .map(|v| v != "false")
+        .unwrap_or(true);
+    let sensitive = detection_enabled
+        && matches!(content_type, ContentType::PlainText | ContentType::RichText)
+        && plain_text
+            .as_deref()
+            .and_then(clip_util::classify_sensitive)
+            .is_some();
+    if sensitive {
+        let secs = queries::get_setting(&db.0, "sensitive_expire_seconds")
+            .await
+            .unwrap_or(None)
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(clip_util::DEFAULT_SENSITIVE_EXPIRE_SECS);
+        if secs > 0 {
+            expire_after = Some(expire_after.map_or(secs, |s| s.min(secs)));
         }
+    }
+    let expires_at = expire_after.map(|secs| {
+        (chrono::Utc::now() + chrono::Duration::seconds(secs as i64))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    });
+
+    // For file items, use the total size of all paths instead of the path string length
+    let content_size = if content_type == ContentType::File && !files.is_empty() {
+        files.iter().map(|f| f.size).sum()
     } else {
         content.len() as i64
     };
 
+    // The first path is kept on the item itself for display and previews
+    let file_path = files.first().map(|f| f.path.clone());
+    let file_name = files.first().map(|f| f.name.clone());
+
     let new_item = NewClipboardItem {
         content_type,
         plain_text: plain_text.unwrap_or_default(),
@@ -1115,10 +1516,17 @@ pub async fn process_clipboard_change(
         image_path,
         file_path,
         file_name,
-        source_app,
-        source_app_name,
+        source_app: source.id,
+        source_app_name: source.name,
         content_size,
         content_hash: hash,
+        sensitive,
+        files,
+        image_width: image_info.as_ref().map(|i| i.width as i64),
+        image_height: image_info.as_ref().map(|i| i.height as i64),
+        image_format: image_info.map(|i| i.format),
+        image_phash,
+        expires_at,
     };
 
     let id = queries::insert_item(&db.0, &new_item)
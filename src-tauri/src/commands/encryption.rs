@@ -0,0 +1,397 @@
+use super::backup as backup_cmd;
+use crate::crypto::{self, keychain, EncryptionMode, Key};
+use crate::db::{
+    models::{EncryptionStatus, ItemPayload},
+    queries, DbPool,
+};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, State};
+
+/// Load the encryption mode at startup. Keychain mode unlocks right away;
+/// passphrase mode stays locked until `unlock_history`.
+pub async fn init_encryption(pool: &SqlitePool) {
+    let mode = load_mode(pool).await;
+    crypto::set_enabled(mode != EncryptionMode::Off);
+    if mode == EncryptionMode::Keychain {
+        match keychain_key(pool).await {
+            Ok(key) => crypto::set_key(Some(key)),
+            Err(e) => log::warn!("Failed to unlock history from keychain: {}", e),
+        }
+    }
+}
+
+/// Get the encryption mode and whether the history is locked.
+#[tauri::command]
+pub async fn get_encryption_status(db: State<'_, DbPool>) -> Result<EncryptionStatus, String> {
+    Ok(EncryptionStatus {
+        mode: load_mode(&db.0).await.as_str().to_string(),
+        locked: crypto::is_locked(),
+    })
+}
+
+/// Encrypt the history, including everything already stored.
+/// `mode` is "passphrase" (requires `passphrase`) or "keychain".
+#[tauri::command]
+pub async fn enable_encryption(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    mode: String,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    if crypto::is_enabled() {
+        return Err("History is already encrypted".to_string());
+    }
+    let mode = EncryptionMode::from_str(&mode)
+        .filter(|m| *m != EncryptionMode::Off)
+        .ok_or_else(|| format!("Unknown encryption mode: {}", mode))?;
+
+    let (key, salt) = match mode {
+        EncryptionMode::Passphrase => {
+            let passphrase = passphrase
+                .filter(|p| !p.is_empty())
+                .ok_or("Passphrase cannot be empty")?;
+            let salt = crypto::generate_salt();
+            (derive_key(passphrase, salt.clone()).await?, salt)
+        }
+        _ => {
+            let key = crypto::generate_key();
+            keychain::store_key(&key)?;
+            (key, String::new())
+        }
+    };
+    let verifier = crypto::make_verifier(&key);
+
+    // Seal new captures from now on; they are carried through the re-seal below
+    crypto::set_key(Some(key));
+    crypto::set_enabled(true);
+
+    let settings = [
+        ("encryption_mode", mode.as_str()),
+        ("encryption_salt", salt.as_str()),
+        ("encryption_verifier", verifier.as_str()),
+    ];
+    if let Err(e) = reseal_history(&db.0, key, true, &settings).await {
+        crypto::set_enabled(false);
+        crypto::set_key(None);
+        if mode == EncryptionMode::Keychain {
+            let _ = keychain::delete_key();
+        }
+        return Err(e);
+    }
+    // Best effort: the rows themselves are already sealed
+    if let Err(e) = queries::compact_database(&db.0).await {
+        log::warn!("Failed to compact the database after encrypting: {}", e);
+    }
+    // Earlier snapshots hold the history in plain form
+    match backup_cmd::remove_backups(&app, &db.0).await {
+        Ok(0) => {}
+        Ok(removed) => log::info!("Removed {} unencrypted backups", removed),
+        Err(e) => log::warn!("{}", e),
+    }
+
+    log::info!("History encrypted ({})", mode.as_str());
+    Ok(())
+}
+
+/// Decrypt the history and turn encryption off. Passphrase mode asks for the
+/// passphrase again even while unlocked.
+#[tauri::command]
+pub async fn disable_encryption(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    let mode = load_mode(&db.0).await;
+    if mode == EncryptionMode::Off {
+        return Err("History is not encrypted".to_string());
+    }
+    let key = crypto::current_key().ok_or("History is locked")?;
+    if mode == EncryptionMode::Passphrase {
+        let confirmed = unlock_key(&db.0, &mode, passphrase).await?;
+        if confirmed != key {
+            return Err("Wrong passphrase".to_string());
+        }
+    }
+
+    // Store new captures in plain text from now on; the key stays loaded to open existing rows
+    crypto::set_enabled(false);
+
+    let settings = [
+        ("encryption_mode", EncryptionMode::Off.as_str()),
+        ("encryption_salt", ""),
+        ("encryption_verifier", ""),
+    ];
+    if let Err(e) = reseal_history(&db.0, key, false, &settings).await {
+        crypto::set_enabled(true);
+        return Err(e);
+    }
+    crypto::set_key(None);
+
+    if mode == EncryptionMode::Keychain {
+        if let Err(e) = keychain::delete_key() {
+            log::warn!("{}", e);
+        }
+        // Snapshots sealed with the keychain key can't be opened once it's gone;
+        // passphrase-sealed ones still open with the old passphrase after a restore
+        match backup_cmd::remove_backups(&app, &db.0).await {
+            Ok(0) => {}
+            Ok(removed) => log::info!("Removed {} backups sealed with the keychain key", removed),
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+
+    log::info!("History decrypted");
+    Ok(())
+}
+
+/// Unlock the encrypted history. Keychain mode needs no passphrase.
+#[tauri::command]
+pub async fn unlock_history(
+    db: State<'_, DbPool>,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    let mode = load_mode(&db.0).await;
+    if mode == EncryptionMode::Off {
+        return Ok(());
+    }
+    let key = unlock_key(&db.0, &mode, passphrase).await?;
+    crypto::set_key(Some(key));
+    Ok(())
+}
+
+/// Forget the history key until the next `unlock_history`.
+#[tauri::command]
+pub fn lock_history() -> Result<(), String> {
+    if !crypto::is_enabled() {
+        return Err("History is not encrypted".to_string());
+    }
+    crypto::set_key(None);
+    Ok(())
+}
+
+/// Current encryption mode from settings (Off if unset or unknown).
+pub async fn load_mode(pool: &SqlitePool) -> EncryptionMode {
+    queries::get_setting(pool, "encryption_mode")
+        .await
+        .unwrap_or(None)
+        .and_then(|v| EncryptionMode::from_str(&v))
+        .unwrap_or(EncryptionMode::Off)
+}
+
+/// Argon2 is deliberately slow, so derive off the async runtime.
+pub async fn derive_key(passphrase: String, salt: String) -> Result<Key, String> {
+    tokio::task::spawn_blocking(move || crypto::derive_key(&passphrase, &salt))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Obtain the history key for `mode` and check it against the stored verifier.
+pub async fn unlock_key(
+    pool: &SqlitePool,
+    mode: &EncryptionMode,
+    passphrase: Option<String>,
+) -> Result<Key, String> {
+    match mode {
+        EncryptionMode::Passphrase => {
+            let passphrase = passphrase.ok_or("Passphrase required")?;
+            let salt = queries::get_setting(pool, "encryption_salt")
+                .await
+                .map_err(|e| e.to_string())?
+                .unwrap_or_default();
+            let key = derive_key(passphrase, salt).await?;
+            if !verify_key(pool, &key).await? {
+                return Err("Wrong passphrase".to_string());
+            }
+            Ok(key)
+        }
+        EncryptionMode::Keychain => keychain_key(pool).await,
+        EncryptionMode::Off => Err("History is not encrypted".to_string()),
+    }
+}
+
+async fn keychain_key(pool: &SqlitePool) -> Result<Key, String> {
+    let key = keychain::load_key()?.ok_or("No history key in the keychain")?;
+    if !verify_key(pool, &key).await? {
+        return Err("Keychain key does not match the encrypted history".to_string());
+    }
+    Ok(key)
+}
+
+async fn verify_key(pool: &SqlitePool, key: &Key) -> Result<bool, String> {
+    let verifier = queries::get_setting(pool, "encryption_verifier")
+        .await
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    Ok(crypto::check_verifier(key, &verifier))
+}
+
+/// Rewrite every stored item sealed (`seal`) or opened with `key`, together with
+/// the new encryption settings. Image files are rewritten next to the originals;
+/// the old files are removed only once the database update has committed.
+async fn reseal_history(
+    pool: &SqlitePool,
+    key: Key,
+    seal: bool,
+    settings: &[(&str, &str)],
+) -> Result<(), String> {
+    let payloads = queries::get_item_payloads(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let resealed = tokio::task::spawn_blocking(move || reseal_payloads(payloads, &key, seal))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    if let Err(e) = queries::replace_item_payloads(pool, &resealed.payloads, !seal, settings).await
+    {
+        remove_files(resealed.written);
+        return Err(e.to_string());
+    }
+    remove_files(resealed.replaced);
+    Ok(())
+}
+
+/// Output of `reseal_payloads`: the rewritten rows plus the image files
+/// written and the files they replace.
+struct Resealed {
+    payloads: Vec<ItemPayload>,
+    written: Vec<String>,
+    replaced: Vec<String>,
+}
+
+fn reseal_payloads(payloads: Vec<ItemPayload>, key: &Key, seal: bool) -> Result<Resealed, String> {
+    let mut written = Vec::new();
+    let mut replaced = Vec::new();
+    let mut out = Vec::with_capacity(payloads.len());
+
+    for p in payloads {
+        match reseal_payload(p, key, seal, &mut written, &mut replaced) {
+            Ok(p) => out.push(p),
+            Err(e) => {
+                remove_files(written);
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(Resealed {
+        payloads: out,
+        written,
+        replaced,
+    })
+}
+
+fn reseal_payload(
+    p: ItemPayload,
+    key: &Key,
+    seal: bool,
+    written: &mut Vec<String>,
+    replaced: &mut Vec<String>,
+) -> Result<ItemPayload, String> {
+    let image_path = match p.image_path {
+        Some(path) => Some(reseal_image_file(path, key, seal, written, replaced)?),
+        None => None,
+    };
+    Ok(ItemPayload {
+        id: p.id,
+        plain_text: reseal_text(p.plain_text, key, seal)?,
+        rich_content: p
+            .rich_content
+            .map(|rc| reseal_bytes(rc, key, seal))
+            .transpose()?,
+        thumbnail: p
+            .thumbnail
+            .map(|t| reseal_bytes(t, key, seal))
+            .transpose()?,
+        image_path,
+        content_hash: reseal_hash(p.content_hash, key, seal)?,
+    })
+}
+
+fn reseal_text(text: String, key: &Key, seal: bool) -> Result<String, String> {
+    // Plain text that merely starts with the sealed prefix stays as it is
+    let plain = if crypto::is_sealed_text(&text) {
+        crypto::decrypt_text(key, &text).unwrap_or(text)
+    } else {
+        text
+    };
+    Ok(if seal {
+        crypto::encrypt_text(key, &plain)
+    } else {
+        plain
+    })
+}
+
+fn reseal_hash(hash: String, key: &Key, seal: bool) -> Result<String, String> {
+    let plain = if crypto::is_sealed_hash(&hash) {
+        crypto::decrypt_hash(key, &hash)?
+    } else {
+        hash
+    };
+    Ok(if seal {
+        crypto::encrypt_hash(key, &plain)
+    } else {
+        plain
+    })
+}
+
+fn reseal_bytes(data: Vec<u8>, key: &Key, seal: bool) -> Result<Vec<u8>, String> {
+    let plain = if crypto::is_sealed(&data) {
+        crypto::decrypt(key, &data)?
+    } else {
+        data
+    };
+    Ok(if seal {
+        crypto::encrypt(key, &plain)
+    } else {
+        plain
+    })
+}
+
+/// Rewrite an image file as `<path>.enc` (or back without the suffix) and return
+/// the new path. Files already in the target form, or missing, are left alone.
+fn reseal_image_file(
+    path: String,
+    key: &Key,
+    seal: bool,
+    written: &mut Vec<String>,
+    replaced: &mut Vec<String>,
+) -> Result<String, String> {
+    let plain_path = path.strip_suffix(crypto::FILE_SUFFIX).map(str::to_string);
+    if plain_path.is_some() == seal {
+        return Ok(path);
+    }
+    let data = match std::fs::read(&path) {
+        Ok(data) => data,
+        Err(e) => {
+            log::warn!("Skipping missing image {}: {}", path, e);
+            return Ok(path);
+        }
+    };
+
+    let (new_path, new_data) = if seal {
+        (
+            format!("{}{}", path, crypto::FILE_SUFFIX),
+            crypto::encrypt(key, &data),
+        )
+    } else {
+        (
+            plain_path.unwrap_or_else(|| path.clone()),
+            crypto::decrypt(key, &data)?,
+        )
+    };
+    std::fs::write(&new_path, new_data)
+        .map_err(|e| format!("Failed to write image {}: {}", new_path, e))?;
+
+    written.push(new_path.clone());
+    replaced.push(path);
+    Ok(new_path)
+}
+
+fn remove_files(paths: Vec<String>) {
+    for path in paths {
+        if let Err(e) = std::fs::remove_file(&path) {
+            log::warn!("Failed to remove image file {}: {}", path, e);
+        }
+    }
+}
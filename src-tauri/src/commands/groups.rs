@@ -0,0 +1,115 @@
+use super::lock as lock_cmd;
+use crate::db::{
+    models::{ClipboardItem, Group},
+    queries, DbPool,
+};
+use tauri::State;
+
+/// Trim a group name and reject empty ones.
+fn normalize_group_name(name: &str) -> Result<String, String> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err("Group name cannot be empty".to_string());
+    }
+    Ok(trimmed.to_string())
+}
+
+/// Get all groups in display order.
+#[tauri::command]
+pub async fn get_groups(db: State<'_, DbPool>) -> Result<Vec<Group>, String> {
+    queries::get_groups(&db.0).await.map_err(|e| e.to_string())
+}
+
+/// Create a new group, returning its id.
+#[tauri::command]
+pub async fn create_group(db: State<'_, DbPool>, name: String) -> Result<String, String> {
+    let name = normalize_group_name(&name)?;
+    queries::create_group(&db.0, &name)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Rename an existing group.
+#[tauri::command]
+pub async fn rename_group(db: State<'_, DbPool>, id: String, name: String) -> Result<(), String> {
+    let name = normalize_group_name(&name)?;
+    let found = queries::rename_group(&db.0, &id, &name)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !found {
+        return Err("Group not found".to_string());
+    }
+    Ok(())
+}
+
+/// Delete a group. Items in the group are kept in history.
+#[tauri::command]
+pub async fn delete_group(db: State<'_, DbPool>, id: String) -> Result<(), String> {
+    queries::delete_group(&db.0, &id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Persist a new group order (`ids` in display order).
+#[tauri::command]
+pub async fn reorder_groups(db: State<'_, DbPool>, ids: Vec<String>) -> Result<(), String> {
+    queries::reorder_groups(&db.0, &ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Add a clipboard item to a group.
+#[tauri::command]
+pub async fn add_item_to_group(
+    db: State<'_, DbPool>,
+    item_id: String,
+    group_id: String,
+) -> Result<(), String> {
+    queries::add_item_to_group(&db.0, &item_id, &group_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Remove a clipboard item from a group.
+#[tauri::command]
+pub async fn remove_item_from_group(
+    db: State<'_, DbPool>,
+    item_id: String,
+    group_id: String,
+) -> Result<(), String> {
+    queries::remove_item_from_group(&db.0, &item_id, &group_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the ids of the groups an item belongs to (for context menu check marks).
+#[tauri::command]
+pub async fn get_item_groups(
+    db: State<'_, DbPool>,
+    item_id: String,
+) -> Result<Vec<String>, String> {
+    queries::get_item_group_ids(&db.0, &item_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get items in a group, optionally filtered by content type.
+#[tauri::command]
+pub async fn get_group_items(
+    db: State<'_, DbPool>,
+    group_id: String,
+    content_type: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<ClipboardItem>, String> {
+    lock_cmd::ensure_unlocked()?;
+    queries::get_group_items(
+        &db.0,
+        &group_id,
+        content_type.as_deref(),
+        limit.unwrap_or(200),
+        offset.unwrap_or(0),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,185 @@
+use crate::db::{models::HttpApiStatus, queries, DbPool};
+use crate::http;
+use sqlx::SqlitePool;
+use std::sync::Mutex;
+use tauri::{AppHandle, EventId, Listener, Manager, State};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+
+const DEFAULT_PORT: u16 = 47810;
+
+/// Events buffered per event stream before a slow client starts missing some.
+const EVENT_BUFFER: usize = 64;
+
+/// Settings the running server was started with.
+#[derive(PartialEq)]
+struct Config {
+    port: u16,
+    token: String,
+}
+
+/// The HTTP server, while the API is on.
+struct Service {
+    config: Config,
+    server: tauri::async_runtime::JoinHandle<()>,
+    /// Forwards `clipboard-changed` to the server's event streams.
+    forward: EventId,
+}
+
+static SERVICE: Mutex<Option<Service>> = Mutex::new(None);
+
+/// Start the server at launch if the API is on.
+pub async fn init_http_api(app: &AppHandle) {
+    let Some(pool) = app.try_state::<DbPool>() else {
+        return;
+    };
+    if let Err(e) = refresh_service(app, &pool.0).await {
+        log::warn!("HTTP API unavailable: {}", e);
+    }
+}
+
+/// Get whether the API is on, its port and token.
+#[tauri::command]
+pub async fn get_http_api_status(db: State<'_, DbPool>) -> Result<HttpApiStatus, String> {
+    status(&db.0).await
+}
+
+/// Turn the API on or off, optionally on another port. The first time it is
+/// turned on a token is generated.
+#[tauri::command]
+pub async fn set_http_api_enabled(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    enabled: bool,
+    port: Option<u16>,
+) -> Result<HttpApiStatus, String> {
+    if let Some(port) = port {
+        if port < 1024 {
+            return Err(format!("Port {} is reserved; use 1024 or above", port));
+        }
+        queries::set_setting(&db.0, "http_api_port", &port.to_string())
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    if enabled && setting(&db.0, "http_api_token").await.is_empty() {
+        queries::set_setting(&db.0, "http_api_token", &http::generate_token())
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    queries::set_setting(
+        &db.0,
+        "http_api_enabled",
+        if enabled { "true" } else { "false" },
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    refresh_service(&app, &db.0).await?;
+    status(&db.0).await
+}
+
+/// Replace the token, locking out every client that has the old one.
+#[tauri::command]
+pub async fn regenerate_http_api_token(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+) -> Result<HttpApiStatus, String> {
+    queries::set_setting(&db.0, "http_api_token", &http::generate_token())
+        .await
+        .map_err(|e| e.to_string())?;
+    refresh_service(&app, &db.0).await?;
+    status(&db.0).await
+}
+
+// ---- Service ----
+
+/// Start, restart or stop the server to match the settings.
+async fn refresh_service(app: &AppHandle, pool: &SqlitePool) -> Result<(), String> {
+    let config = load_config(pool).await;
+    {
+        let service = SERVICE.lock().unwrap_or_else(|e| e.into_inner());
+        if service.as_ref().map(|s| &s.config) == config.as_ref() {
+            return Ok(());
+        }
+    }
+    stop_service(app);
+    match config {
+        Some(config) => start_service(app, config).await,
+        None => Ok(()),
+    }
+}
+
+async fn start_service(app: &AppHandle, config: Config) -> Result<(), String> {
+    // Loopback only: the API is for programs on this machine
+    let listener = TcpListener::bind(("127.0.0.1", config.port))
+        .await
+        .map_err(|e| format!("Failed to listen on port {}: {}", config.port, e))?;
+
+    let (events, _) = broadcast::channel::<String>(EVENT_BUFFER);
+    let sender = events.clone();
+    let forward = app.listen("clipboard-changed", move |event: tauri::Event| {
+        let _ = sender.send(event.payload().to_string());
+    });
+    let server = tauri::async_runtime::spawn(http::server::serve(
+        app.clone(),
+        listener,
+        config.token.clone(),
+        events,
+    ));
+
+    log::info!("HTTP API listening on 127.0.0.1:{}", config.port);
+    *SERVICE.lock().unwrap_or_else(|e| e.into_inner()) = Some(Service {
+        config,
+        server,
+        forward,
+    });
+    Ok(())
+}
+
+fn stop_service(app: &AppHandle) {
+    let service = SERVICE.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(service) = service {
+        // Dropping the server also drops its open connections and event streams
+        service.server.abort();
+        app.unlisten(service.forward);
+        log::info!("HTTP API stopped");
+    }
+}
+
+/// The settings to serve with, None while the API is off.
+async fn load_config(pool: &SqlitePool) -> Option<Config> {
+    if setting(pool, "http_api_enabled").await != "true" {
+        return None;
+    }
+    let token = setting(pool, "http_api_token").await;
+    if token.is_empty() {
+        return None;
+    }
+    Some(Config {
+        port: port(pool).await,
+        token,
+    })
+}
+
+async fn status(pool: &SqlitePool) -> Result<HttpApiStatus, String> {
+    let running = SERVICE.lock().unwrap_or_else(|e| e.into_inner()).is_some();
+    Ok(HttpApiStatus {
+        enabled: setting(pool, "http_api_enabled").await == "true",
+        running,
+        port: port(pool).await,
+        token: Some(setting(pool, "http_api_token").await).filter(|t| !t.is_empty()),
+    })
+}
+
+async fn port(pool: &SqlitePool) -> u16 {
+    setting(pool, "http_api_port")
+        .await
+        .parse()
+        .unwrap_or(DEFAULT_PORT)
+}
+
+async fn setting(pool: &SqlitePool, key: &str) -> String {
+    queries::get_setting(pool, key)
+        .await
+        .unwrap_or(None)
+        .unwrap_or_default()
+}
@@ -0,0 +1,74 @@
+use crate::clipboard as clip_util;
+use crate::db::{
+    models::{IgnoreMatchType, IgnoredApp},
+    queries, DbPool,
+};
+use crate::platform::FrontmostApp;
+use sqlx::SqlitePool;
+use tauri::State;
+
+/// Get all capture ignore rules.
+#[tauri::command]
+pub async fn get_ignored_apps(db: State<'_, DbPool>) -> Result<Vec<IgnoredApp>, String> {
+    queries::get_ignored_apps(&db.0)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Add a capture ignore rule, returning its id.
+/// `match_type` is one of "bundle_id", "executable" or "window_title".
+#[tauri::command]
+pub async fn add_ignored_app(
+    db: State<'_, DbPool>,
+    match_type: String,
+    pattern: String,
+) -> Result<String, String> {
+    let match_type = IgnoreMatchType::from_str(&match_type)
+        .ok_or_else(|| format!("Unknown match type: {}", match_type))?;
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return Err("Ignore pattern cannot be empty".to_string());
+    }
+    queries::add_ignored_app(&db.0, match_type.as_str(), pattern)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Remove a capture ignore rule.
+#[tauri::command]
+pub async fn remove_ignored_app(db: State<'_, DbPool>, id: String) -> Result<(), String> {
+    queries::remove_ignored_app(&db.0, &id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Check whether clipboard changes from `source` should be dropped.
+/// Called by the clipboard monitor before reading the clipboard, and for text
+/// added by other programs (CLI, HTTP API, links).
+pub async fn is_source_ignored(pool: &SqlitePool, source: &FrontmostApp) -> bool {
+    let rules = match queries::get_ignored_apps(pool).await {
+        Ok(rules) => rules,
+        Err(e) => {
+            log::warn!("Failed to load ignore rules: {}", e);
+            return false;
+        }
+    };
+
+    // Only macOS apps have bundle ids; elsewhere the id is the executable path
+    let bundle_id = if cfg!(target_os = "macos") {
+        source.id.as_str()
+    } else {
+        ""
+    };
+    rules.iter().any(|rule| {
+        IgnoreMatchType::from_str(&rule.match_type).is_some_and(|match_type| {
+            clip_util::matches_ignore_rule(
+                &match_type,
+                &rule.pattern,
+                bundle_id,
+                &source.executable,
+                &source.window_title,
+            )
+        })
+    })
+}
@@ -0,0 +1,230 @@
+use super::encryption as crypt_cmd;
+use crate::crypto::{self, EncryptionMode};
+use crate::db::{models::LockStatus, queries, DbPool};
+use sqlx::SqlitePool;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, Ordering};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// True while the app lock hides the history (independent of encryption).
+static APP_LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// Unix time of the last user interaction; auto-lock counts idle time from here.
+static LAST_ACTIVITY: AtomicI64 = AtomicI64::new(0);
+
+/// Wrong passphrases in a row since the last unlock, and the Unix time before
+/// which `unlock_app` refuses another attempt.
+static FAILED_UNLOCKS: AtomicU32 = AtomicU32::new(0);
+static UNLOCK_RETRY_AT: AtomicI64 = AtomicI64::new(0);
+
+/// True while `unlock_app` is checking a passphrase; attempts don't overlap.
+static UNLOCKING: AtomicBool = AtomicBool::new(false);
+
+/// Longest wait between unlock attempts, in seconds.
+const MAX_UNLOCK_DELAY_SECS: i64 = 60;
+
+/// Whether history content must be withheld: app-locked, or encrypted without a key.
+pub fn is_locked() -> bool {
+    APP_LOCKED.load(Ordering::SeqCst) || crypto::is_locked()
+}
+
+/// Record a user interaction (showing the window, searching, pasting) for the
+/// idle timer. Reads alone don't count: the window refreshes on its own.
+pub fn touch() {
+    LAST_ACTIVITY.store(chrono::Utc::now().timestamp(), Ordering::SeqCst);
+}
+
+/// Guard for commands that return or paste history content.
+pub fn ensure_unlocked() -> Result<(), String> {
+    if is_locked() {
+        return Err("History is locked".to_string());
+    }
+    Ok(())
+}
+
+/// Lock at startup when a lock passphrase is configured.
+pub async fn init_lock(pool: &SqlitePool) {
+    APP_LOCKED.store(is_lock_enabled(pool).await, Ordering::SeqCst);
+    touch();
+}
+
+/// Get whether the app lock is set up and whether history is currently locked.
+#[tauri::command]
+pub async fn get_lock_status(db: State<'_, DbPool>) -> Result<LockStatus, String> {
+    Ok(LockStatus {
+        enabled: is_lock_enabled(&db.0).await,
+        locked: is_locked(),
+    })
+}
+
+/// Lock now. Requires a lock passphrase so the history can be unlocked again.
+#[tauri::command]
+pub async fn lock_app(app: AppHandle, db: State<'_, DbPool>) -> Result<(), String> {
+    if !is_lock_enabled(&db.0).await {
+        return Err("No lock passphrase is set".to_string());
+    }
+    lock_now(&app, &db.0).await;
+    Ok(())
+}
+
+/// Unlock with the app lock passphrase, or the encryption passphrase when the
+/// history is passphrase-encrypted (which also loads the history key). Each
+/// wrong passphrase doubles the wait before the next attempt, up to a minute.
+#[tauri::command]
+pub async fn unlock_app(db: State<'_, DbPool>, passphrase: String) -> Result<(), String> {
+    let wait = UNLOCK_RETRY_AT.load(Ordering::SeqCst) - chrono::Utc::now().timestamp();
+    if wait > 0 {
+        return Err(format!("Too many attempts, try again in {}s", wait));
+    }
+    if UNLOCKING.swap(true, Ordering::SeqCst) {
+        return Err("Already unlocking".to_string());
+    }
+    let result = unlock(&db.0, passphrase).await;
+    UNLOCKING.store(false, Ordering::SeqCst);
+
+    match &result {
+        Ok(()) => FAILED_UNLOCKS.store(0, Ordering::SeqCst),
+        Err(e) if e == WRONG_PASSPHRASE => {
+            let failures = FAILED_UNLOCKS.fetch_add(1, Ordering::SeqCst) + 1;
+            let delay = unlock_delay_secs(failures);
+            UNLOCK_RETRY_AT.store(chrono::Utc::now().timestamp() + delay, Ordering::SeqCst);
+            log::warn!("Wrong unlock passphrase ({} in a row)", failures);
+        }
+        Err(_) => {}
+    }
+    result
+}
+
+const WRONG_PASSPHRASE: &str = "Wrong passphrase";
+
+/// Seconds to wait after `failures` wrong passphrases in a row: 1, 2, 4, ...
+fn unlock_delay_secs(failures: u32) -> i64 {
+    1i64.checked_shl(failures.saturating_sub(1))
+        .unwrap_or(i64::MAX)
+        .min(MAX_UNLOCK_DELAY_SECS)
+}
+
+async fn unlock(pool: &SqlitePool, passphrase: String) -> Result<(), String> {
+    let mode = crypt_cmd::load_mode(pool).await;
+    if mode == EncryptionMode::Passphrase {
+        let key = crypt_cmd::unlock_key(pool, &mode, Some(passphrase)).await?;
+        crypto::set_key(Some(key));
+    } else if has_app_passphrase(pool).await && !check_app_passphrase(pool, passphrase).await? {
+        return Err(WRONG_PASSPHRASE.to_string());
+    }
+
+    // A keychain key that failed to load at startup gets another try
+    if crypto::is_locked() {
+        let key = crypt_cmd::unlock_key(pool, &mode, None).await?;
+        crypto::set_key(Some(key));
+    }
+
+    APP_LOCKED.store(false, Ordering::SeqCst);
+    touch();
+    Ok(())
+}
+
+/// Set, change or (with `passphrase` None or empty) remove the app lock passphrase.
+/// Changing or removing it requires the `current` passphrase.
+#[tauri::command]
+pub async fn set_lock_passphrase(
+    db: State<'_, DbPool>,
+    passphrase: Option<String>,
+    current: Option<String>,
+) -> Result<(), String> {
+    if crypt_cmd::load_mode(&db.0).await == EncryptionMode::Passphrase {
+        return Err("The encryption passphrase already locks the history".to_string());
+    }
+    if has_app_passphrase(&db.0).await
+        && !check_app_passphrase(&db.0, current.unwrap_or_default()).await?
+    {
+        return Err("Wrong passphrase".to_string());
+    }
+
+    let (salt, verifier) = match passphrase.filter(|p| !p.is_empty()) {
+        Some(passphrase) => {
+            let salt = crypto::generate_salt();
+            let key = crypt_cmd::derive_key(passphrase, salt.clone()).await?;
+            (salt, crypto::make_verifier(&key))
+        }
+        None => (String::new(), String::new()),
+    };
+    queries::set_setting(&db.0, "app_lock_salt", &salt)
+        .await
+        .map_err(|e| e.to_string())?;
+    queries::set_setting(&db.0, "app_lock_verifier", &verifier)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if verifier.is_empty() {
+        APP_LOCKED.store(false, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+/// Called periodically by the auto-lock task. Locks after `auto_lock_minutes`
+/// without history access, or after a system sleep (`slept`) if `lock_on_sleep`.
+pub async fn auto_lock_tick(app: &AppHandle, slept: bool) {
+    if is_locked() {
+        return;
+    }
+    let pool = app.state::<DbPool>();
+    if !is_lock_enabled(&pool.0).await {
+        return;
+    }
+
+    let lock_on_sleep = queries::get_setting(&pool.0, "lock_on_sleep")
+        .await
+        .unwrap_or(None)
+        .map(|v| v != "false")
+        .unwrap_or(true);
+    let idle_minutes = queries::get_setting(&pool.0, "auto_lock_minutes")
+        .await
+        .unwrap_or(None)
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(0);
+    let idle_secs = chrono::Utc::now().timestamp() - LAST_ACTIVITY.load(Ordering::SeqCst);
+
+    if slept && lock_on_sleep {
+        log::info!("System woke from sleep, locking history");
+        lock_now(app, &pool.0).await;
+    } else if idle_minutes > 0 && idle_secs >= idle_minutes * 60 {
+        log::info!("Idle for {}s, locking history", idle_secs);
+        lock_now(app, &pool.0).await;
+    }
+}
+
+/// Lock, drop a passphrase-derived history key, close the preview and tell the
+/// main window to swap the history for the unlock prompt.
+async fn lock_now(app: &AppHandle, pool: &SqlitePool) {
+    APP_LOCKED.store(true, Ordering::SeqCst);
+    if crypt_cmd::load_mode(pool).await == EncryptionMode::Passphrase {
+        crypto::set_key(None);
+    }
+    crate::platform::platform_hide_preview(app);
+    let _ = app.emit("recopy-locked", ());
+}
+
+/// A lock passphrase exists: the app lock's own, or the encryption passphrase.
+async fn is_lock_enabled(pool: &SqlitePool) -> bool {
+    has_app_passphrase(pool).await || crypt_cmd::load_mode(pool).await == EncryptionMode::Passphrase
+}
+
+async fn has_app_passphrase(pool: &SqlitePool) -> bool {
+    queries::get_setting(pool, "app_lock_verifier")
+        .await
+        .unwrap_or(None)
+        .is_some_and(|v| !v.is_empty())
+}
+
+async fn check_app_passphrase(pool: &SqlitePool, passphrase: String) -> Result<bool, String> {
+    let salt = queries::get_setting(pool, "app_lock_salt")
+        .await
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    let verifier = queries::get_setting(pool, "app_lock_verifier")
+        .await
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    let key = crypt_cmd::derive_key(passphrase, salt).await?;
+    Ok(crypto::check_verifier(&key, &verifier))
+}
@@ -1 +1,13 @@
+pub mod archive;
+pub mod backup;
 pub mod clipboard;
+pub mod encryption;
+pub mod groups;
+pub mod http_api;
+pub mod ignored_apps;
+pub mod lock;
+pub mod paste_queue;
+pub mod snippets;
+pub mod sync;
+pub mod transforms;
+pub mod trash;
@@ -0,0 +1,265 @@
+use super::clipboard as clip_cmd;
+use super::lock as lock_cmd;
+use crate::db::{models::PasteQueueStatus, queries, DbPool};
+use crate::paste_queue::{Order, PasteQueue};
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const DEFAULT_SHORTCUT: &str = "CommandOrControl+Shift+B";
+
+/// In-memory queue state. Collecting starts off at launch with an empty queue.
+struct Queue {
+    entries: PasteQueue,
+    collecting: bool,
+    /// The paste-next shortcut, while registered.
+    shortcut: Option<String>,
+}
+
+static QUEUE: Mutex<Queue> = Mutex::new(Queue {
+    entries: PasteQueue::new(),
+    collecting: false,
+    shortcut: None,
+});
+
+fn queue() -> MutexGuard<'static, Queue> {
+    QUEUE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Append a newly captured item while collecting. Self-initiated writes
+/// (pastes, including from the queue) never get here: they are skipped
+/// before capture by the SKIP_NEXT_CLIPBOARD_CHANGE guard.
+pub fn on_item_captured(app: &AppHandle, id: &str) {
+    let count = {
+        let mut queue = queue();
+        if !queue.collecting {
+            return;
+        }
+        queue.entries.push(id.to_string());
+        queue.entries.len()
+    };
+    emit_changed(app, count);
+}
+
+/// Get the queue, in paste order, and whether it is collecting.
+#[tauri::command]
+pub async fn get_paste_queue(db: State<'_, DbPool>) -> Result<PasteQueueStatus, String> {
+    lock_cmd::ensure_unlocked()?;
+    status(&db.0).await
+}
+
+/// Start or stop collecting captures. The paste-next shortcut is registered
+/// only while collecting, so it doesn't shadow other apps' shortcuts.
+#[tauri::command]
+pub async fn set_paste_queue_collecting(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    collecting: bool,
+) -> Result<PasteQueueStatus, String> {
+    queue().collecting = collecting;
+    if collecting {
+        if let Err(e) = refresh_shortcut(&app).await {
+            queue().collecting = false;
+            return Err(e);
+        }
+    } else {
+        unregister_shortcut(&app);
+    }
+    let count = queue().entries.len();
+    emit_changed(&app, count);
+    status(&db.0).await
+}
+
+/// Choose whether the first-copied ("fifo") or last-copied ("lifo") entry is
+/// pasted first.
+#[tauri::command]
+pub async fn set_paste_queue_order(
+    db: State<'_, DbPool>,
+    order: String,
+) -> Result<PasteQueueStatus, String> {
+    let order = Order::from_str(&order).ok_or_else(|| format!("Unknown order {}", order))?;
+    queries::set_setting(&db.0, "paste_queue_order", order.as_str())
+        .await
+        .map_err(|e| e.to_string())?;
+    status(&db.0).await
+}
+
+/// Rearrange the queue; `ids` lists every queued entry in the new paste order.
+#[tauri::command]
+pub async fn reorder_paste_queue(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    ids: Vec<String>,
+) -> Result<PasteQueueStatus, String> {
+    lock_cmd::ensure_unlocked()?;
+    let order = load_order(&db.0).await;
+    let count = {
+        let mut queue = queue();
+        queue.entries.reorder(order, &ids)?;
+        queue.entries.len()
+    };
+    emit_changed(&app, count);
+    status(&db.0).await
+}
+
+/// Remove the next queued entry for an item.
+#[tauri::command]
+pub async fn remove_from_paste_queue(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    id: String,
+) -> Result<PasteQueueStatus, String> {
+    lock_cmd::ensure_unlocked()?;
+    let order = load_order(&db.0).await;
+    let count = {
+        let mut queue = queue();
+        if !queue.entries.remove(order, &id) {
+            return Err("Item is not queued".to_string());
+        }
+        queue.entries.len()
+    };
+    emit_changed(&app, count);
+    status(&db.0).await
+}
+
+#[tauri::command]
+pub fn clear_paste_queue(app: AppHandle) -> Result<(), String> {
+    lock_cmd::ensure_unlocked()?;
+    queue().entries.clear();
+    emit_changed(&app, 0);
+    Ok(())
+}
+
+/// Paste the next entry into the frontmost app and advance. Returns the
+/// pasted item's id, None if the queue is empty.
+#[tauri::command]
+pub async fn paste_next_from_queue(app: AppHandle) -> Result<Option<String>, String> {
+    paste_next(&app).await
+}
+
+async fn paste_next(app: &AppHandle) -> Result<Option<String>, String> {
+    lock_cmd::ensure_unlocked()?;
+    lock_cmd::touch();
+    let db = app.state::<DbPool>();
+    let order = load_order(&db.0).await;
+    loop {
+        let Some(id) = queue().entries.next(order) else {
+            return Ok(None);
+        };
+        // Items deleted since they were queued are skipped
+        let live = queries::get_items_by_ids(&db.0, std::slice::from_ref(&id))
+            .await
+            .map_err(|e| e.to_string())?;
+        if live.is_empty() {
+            continue;
+        }
+
+        let pasted =
+            clip_cmd::paste_clipboard_item(app.clone(), app.state(), id.clone(), Some(true)).await;
+        let count = {
+            let mut queue = queue();
+            if pasted.is_err() {
+                queue.entries.push_next(order, id.clone());
+            }
+            queue.entries.len()
+        };
+        emit_changed(app, count);
+        return pasted.map(|()| Some(id));
+    }
+}
+
+// ---- Shortcut ----
+
+/// Register the paste-next shortcut from settings while collecting. Also
+/// called after the main shortcut is re-registered, which unregisters all.
+pub async fn refresh_shortcut(app: &AppHandle) -> Result<(), String> {
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+    unregister_shortcut(app);
+    if !queue().collecting {
+        return Ok(());
+    }
+    let Some(pool) = app.try_state::<DbPool>() else {
+        return Ok(());
+    };
+    let shortcut = load_shortcut(&pool.0).await?;
+
+    app.global_shortcut()
+        .on_shortcut(shortcut.as_str(), |app, _shortcut, event| {
+            // On release rather than press, so the simulated Cmd+V isn't mixed
+            // with the shortcut's keys still being held (Cmd+Shift+V)
+            if event.state == ShortcutState::Released {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = paste_next(&app).await {
+                        log::warn!("Paste queue: {}", e);
+                    }
+                });
+            }
+        })
+        .map_err(|e| format!("Failed to register {}: {}", shortcut, e))?;
+    log::info!("Paste queue shortcut registered: {}", shortcut);
+    queue().shortcut = Some(shortcut);
+    Ok(())
+}
+
+fn unregister_shortcut(app: &AppHandle) {
+    use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+    let shortcut = queue().shortcut.take();
+    if let Some(shortcut) = shortcut {
+        // Already gone if the main shortcut was re-registered in between
+        let _ = app.global_shortcut().unregister(shortcut.as_str());
+    }
+}
+
+// ---- Helpers ----
+
+async fn status(pool: &SqlitePool) -> Result<PasteQueueStatus, String> {
+    let order = load_order(pool).await;
+    let shortcut = load_shortcut(pool).await?;
+    let ids = queue().entries.upcoming(order);
+    let items = queries::get_items_by_ids(pool, &ids)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Forget entries deleted since they were queued, so the listed items are
+    // exactly what reorder_paste_queue expects back
+    let live: HashSet<&str> = items.iter().map(|item| item.id.as_str()).collect();
+    let gone: HashSet<&str> = ids
+        .iter()
+        .map(String::as_str)
+        .filter(|id| !live.contains(id))
+        .collect();
+    let mut queue = queue();
+    queue.entries.retain(|id| !gone.contains(id));
+
+    Ok(PasteQueueStatus {
+        collecting: queue.collecting,
+        order: order.as_str().to_string(),
+        shortcut,
+        items,
+    })
+}
+
+async fn load_shortcut(pool: &SqlitePool) -> Result<String, String> {
+    Ok(queries::get_setting(pool, "paste_queue_shortcut")
+        .await
+        .map_err(|e| e.to_string())?
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| DEFAULT_SHORTCUT.to_string()))
+}
+
+async fn load_order(pool: &SqlitePool) -> Order {
+    queries::get_setting(pool, "paste_queue_order")
+        .await
+        .ok()
+        .flatten()
+        .and_then(|v| Order::from_str(&v))
+        .unwrap_or_default()
+}
+
+fn emit_changed(app: &AppHandle, count: usize) {
+    let _ = app.emit("paste-queue-changed", serde_json::json!({ "count": count }));
+}
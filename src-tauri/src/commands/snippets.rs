@@ -0,0 +1,190 @@
+use super::clipboard as clip_cmd;
+use super::lock as lock_cmd;
+use crate::crypto;
+use crate::db::{models::Snippet, queries, DbPool};
+use crate::snippet::{self, Part};
+use std::collections::HashMap;
+use tauri::{AppHandle, State};
+
+/// Longest name given to a snippet promoted from history.
+const MAX_PROMOTED_NAME_CHARS: usize = 40;
+
+/// Caret moves beyond this are skipped: pressing Left thousands of times is
+/// slower than the user moving the caret themselves.
+const MAX_CURSOR_BACK: usize = 5000;
+
+/// Trim a snippet name and check the content is a valid template.
+fn validate_snippet(name: &str, content: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Snippet name cannot be empty".to_string());
+    }
+    if content.is_empty() {
+        return Err("Snippet cannot be empty".to_string());
+    }
+    snippet::parse(content)?;
+    Ok(name.to_string())
+}
+
+/// Get all snippets, most recently edited first.
+#[tauri::command]
+pub async fn get_snippets(db: State<'_, DbPool>) -> Result<Vec<Snippet>, String> {
+    lock_cmd::ensure_unlocked()?;
+    queries::get_snippets(&db.0)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Search snippet names and content.
+#[tauri::command]
+pub async fn search_snippets(
+    db: State<'_, DbPool>,
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<Snippet>, String> {
+    lock_cmd::ensure_unlocked()?;
+    queries::search_snippets(&db.0, &query, limit.unwrap_or(50))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Create a snippet, returning its id.
+#[tauri::command]
+pub async fn create_snippet(
+    db: State<'_, DbPool>,
+    name: String,
+    content: String,
+) -> Result<String, String> {
+    lock_cmd::ensure_unlocked()?;
+    let name = validate_snippet(&name, &content)?;
+    queries::create_snippet(&db.0, &name, &content)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Replace a snippet's name and content.
+#[tauri::command]
+pub async fn update_snippet(
+    db: State<'_, DbPool>,
+    id: String,
+    name: String,
+    content: String,
+) -> Result<(), String> {
+    lock_cmd::ensure_unlocked()?;
+    let name = validate_snippet(&name, &content)?;
+    let found = queries::update_snippet(&db.0, &id, &name, &content)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !found {
+        return Err("Snippet not found".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_snippet(db: State<'_, DbPool>, id: String) -> Result<(), String> {
+    lock_cmd::ensure_unlocked()?;
+    queries::delete_snippet(&db.0, &id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Turn a text item from history into a snippet, named after its first line
+/// unless a name is given. Returns the new snippet's id.
+///
+/// Snippets are stored unencrypted, so with encryption on this is refused
+/// rather than copying encrypted history out in the clear.
+#[tauri::command]
+pub async fn create_snippet_from_item(
+    db: State<'_, DbPool>,
+    item_id: String,
+    name: Option<String>,
+) -> Result<String, String> {
+    lock_cmd::ensure_unlocked()?;
+    if crypto::is_enabled() {
+        return Err("Items can't become snippets while encryption is on".to_string());
+    }
+    let (content_type, plain_text, _rich_content, _image_path, _file_path) =
+        queries::get_item_by_id(&db.0, &item_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or("Item not found")?;
+    if !matches!(content_type.as_str(), "plain_text" | "rich_text" | "link") {
+        return Err("Only text items can become snippets".to_string());
+    }
+
+    let name = name.unwrap_or_else(|| {
+        let first_line = plain_text.trim().lines().next().unwrap_or_default();
+        first_line.chars().take(MAX_PROMOTED_NAME_CHARS).collect()
+    });
+    let name = validate_snippet(&name, &plain_text)?;
+    queries::create_snippet(&db.0, &name, &plain_text)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Names of the prompts the user must fill in before pasting a snippet.
+#[tauri::command]
+pub async fn get_snippet_prompts(db: State<'_, DbPool>, id: String) -> Result<Vec<String>, String> {
+    lock_cmd::ensure_unlocked()?;
+    let snippet = load_snippet(&db, &id).await?;
+    snippet::prompts(&snippet.content)
+}
+
+/// Expand a snippet and paste it into the frontmost app. `values` fills its
+/// prompts; a `{{cursor}}` placeholder puts the caret back where it was.
+#[tauri::command]
+pub async fn paste_snippet(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    id: String,
+    values: Option<HashMap<String, String>>,
+) -> Result<(), String> {
+    lock_cmd::ensure_unlocked()?;
+    lock_cmd::touch();
+    let snippet = load_snippet(&db, &id).await?;
+
+    // Read the clipboard before the expansion replaces it
+    let clipboard = if snippet::parse(&snippet.content)?.contains(&Part::Clipboard) {
+        tauri_plugin_clipboard_x::read_text()
+            .await
+            .unwrap_or_default()
+    } else {
+        String::new()
+    };
+    let values = values.unwrap_or_default();
+    let expansion = snippet::expand(
+        &snippet.content,
+        &snippet::Context {
+            now: chrono::Local::now(),
+            clipboard: &clipboard,
+            values: &values,
+        },
+    )?;
+
+    // Skip self-monitoring for this clipboard write (clear flag on failure)
+    crate::set_skip_next_clipboard_change();
+    if let Err(e) =
+        clip_cmd::write_to_clipboard(&app, "plain_text", &expansion.text, &None, &None, &[]).await
+    {
+        crate::clear_skip_next_clipboard_change();
+        return Err(e);
+    }
+
+    // Resign keyboard focus so the previous app receives the Cmd+V
+    crate::platform::platform_resign_before_paste(&app);
+    clip_cmd::simulate_paste();
+    match expansion.cursor_back {
+        Some(back) if back > 0 && back <= MAX_CURSOR_BACK => clip_cmd::simulate_caret_left(back),
+        _ => {}
+    }
+    crate::hide_main_window(&app);
+    Ok(())
+}
+
+async fn load_snippet(db: &DbPool, id: &str) -> Result<Snippet, String> {
+    queries::get_snippet(&db.0, id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Snippet not found".to_string())
+}
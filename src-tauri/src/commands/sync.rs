@@ -0,0 +1,602 @@
+use crate::crypto::{self, Key};
+use crate::db::{
+    models::{SyncStatus, SyncSummary},
+    queries, DbPool,
+};
+use crate::sync::{self, folder, lan, SyncContext};
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use sqlx::SqlitePool;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::net::TcpListener;
+
+/// mDNS service type sync peers advertise and browse for.
+const SERVICE_TYPE: &str = "_recopy-sync._tcp.local.";
+
+const DEFAULT_PORT: u16 = 47800;
+
+/// Incoming connections handled at once; more are closed right away.
+const MAX_PENDING_ACCEPTS: usize = 4;
+
+/// Settings the running service was started with.
+#[derive(PartialEq)]
+struct Config {
+    key: Key,
+    device_id: String,
+    device_name: String,
+    port: u16,
+}
+
+/// The sync listener and mDNS registration, while sync is on and paired.
+struct Service {
+    config: Config,
+    ctx: SyncContext,
+    listener: tauri::async_runtime::JoinHandle<()>,
+    mdns: Option<ServiceDaemon>,
+}
+
+static SERVICE: Mutex<Option<Service>> = Mutex::new(None);
+
+/// A peer currently advertised over mDNS.
+struct Discovered {
+    device_id: String,
+    fullname: String,
+    addr: SocketAddr,
+}
+
+static DISCOVERED: Mutex<Vec<Discovered>> = Mutex::new(Vec::new());
+
+/// Held for the length of each sync session, so two sessions never apply
+/// the same items at once.
+static SESSION: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Give this machine a sync identity on first start.
+pub async fn init_sync(pool: &SqlitePool) {
+    if setting(pool, "sync_device_id").await.is_empty() {
+        let id = uuid::Uuid::new_v4().to_string();
+        if let Err(e) = queries::set_setting(pool, "sync_device_id", &id).await {
+            log::warn!("Failed to store sync device id: {}", e);
+        }
+    }
+    if setting(pool, "sync_device_name").await.is_empty() {
+        let name = tauri_plugin_os::hostname();
+        let _ = queries::set_setting(pool, "sync_device_name", &name).await;
+    }
+}
+
+/// Get the sync settings, this machine's identity and the known peers.
+#[tauri::command]
+pub async fn get_sync_status(db: State<'_, DbPool>) -> Result<SyncStatus, String> {
+    status(&db.0).await
+}
+
+/// Turn LAN sync on or off. Takes effect immediately.
+#[tauri::command]
+pub async fn set_sync_enabled(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    enabled: bool,
+) -> Result<SyncStatus, String> {
+    queries::set_setting(
+        &db.0,
+        "sync_enabled",
+        if enabled { "true" } else { "false" },
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    refresh_service(&app, &db.0).await?;
+    status(&db.0).await
+}
+
+/// Sync through a folder shared by a file-sync tool instead of the network,
+/// or back over the network with `None`. Choosing another folder starts over,
+/// writing every item into it.
+#[tauri::command]
+pub async fn set_sync_folder(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    folder: Option<String>,
+) -> Result<SyncStatus, String> {
+    match folder.as_deref().map(str::trim) {
+        Some(folder) => {
+            if !Path::new(folder).is_dir() {
+                return Err(format!("Folder not found: {}", folder));
+            }
+            if setting(&db.0, "sync_folder").await != folder {
+                queries::set_setting(&db.0, "sync_folder", folder)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                queries::reset_folder_sync(&db.0)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+            queries::set_setting(&db.0, "sync_mode", "folder")
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        None => {
+            queries::set_setting(&db.0, "sync_mode", "lan")
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    refresh_service(&app, &db.0).await?;
+    status(&db.0).await
+}
+
+/// Pair this machine with a new random secret, returned for entering on the
+/// other machines with `pair_sync`.
+#[tauri::command]
+pub async fn create_sync_secret(app: AppHandle, db: State<'_, DbPool>) -> Result<String, String> {
+    let secret = sync::generate_secret();
+    pair_with(&app, &db.0, &secret).await?;
+    Ok(secret)
+}
+
+/// Pair this machine with a secret created on another machine.
+#[tauri::command]
+pub async fn pair_sync(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    secret: String,
+) -> Result<(), String> {
+    pair_with(&app, &db.0, &secret).await
+}
+
+/// Forget the pairing secret and all peers, and stop syncing.
+#[tauri::command]
+pub async fn unpair_sync(app: AppHandle, db: State<'_, DbPool>) -> Result<(), String> {
+    queries::set_setting(&db.0, "sync_key", "")
+        .await
+        .map_err(|e| e.to_string())?;
+    queries::clear_sync_peers(&db.0)
+        .await
+        .map_err(|e| e.to_string())?;
+    queries::reset_folder_sync(&db.0)
+        .await
+        .map_err(|e| e.to_string())?;
+    refresh_service(&app, &db.0).await
+}
+
+/// Sync with a peer at "host:port", for networks where mDNS doesn't reach.
+/// The peer is remembered once the handshake succeeds.
+#[tauri::command]
+pub async fn add_sync_peer(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    address: String,
+) -> Result<SyncSummary, String> {
+    let ctx = running_context(&app, &db.0).await?;
+    let addr = tokio::net::lookup_host(address.trim())
+        .await
+        .map_err(|e| format!("Invalid peer address {}: {}", address, e))?
+        .next()
+        .ok_or_else(|| format!("Invalid peer address {}", address))?;
+
+    let _session = SESSION.lock().await;
+    let (peer, summary) = lan::connect(&ctx, addr).await?;
+    report(&app, &peer.name, &summary);
+    Ok(summary)
+}
+
+/// Forget a peer. It is added again if it shows up on the network.
+#[tauri::command]
+pub async fn remove_sync_peer(db: State<'_, DbPool>, device_id: String) -> Result<(), String> {
+    queries::remove_sync_peer(&db.0, &device_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Sync with every known peer, or with the sync folder, now.
+#[tauri::command]
+pub async fn sync_now(app: AppHandle, db: State<'_, DbPool>) -> Result<SyncSummary, String> {
+    if crypto::is_locked() {
+        return Err("History is locked".to_string());
+    }
+    if let Some((ctx, dir)) = folder_context(&app, &db.0).await? {
+        return folder_round(&app, &ctx, &dir).await;
+    }
+    let ctx = running_context(&app, &db.0).await?;
+    Ok(sync_round(&app, &ctx, true).await)
+}
+
+/// Called periodically by the sync task: start or stop the service to match
+/// the settings, then sync with the peers this machine is the one to call,
+/// or with the sync folder.
+pub async fn run_scheduled_sync(app: &AppHandle) {
+    let Some(pool) = app.try_state::<DbPool>() else {
+        return;
+    };
+    if let Err(e) = refresh_service(app, &pool.0).await {
+        log::warn!("LAN sync unavailable: {}", e);
+        return;
+    }
+    if crypto::is_locked() {
+        return;
+    }
+    let summary = match folder_context(app, &pool.0).await {
+        Ok(Some((ctx, dir))) => match folder_round(app, &ctx, &dir).await {
+            Ok(summary) => summary,
+            Err(e) => {
+                log::warn!("Folder sync failed: {}", e);
+                return;
+            }
+        },
+        Ok(None) => {
+            let Some(ctx) = current_context() else {
+                return;
+            };
+            sync_round(app, &ctx, false).await
+        }
+        Err(e) => {
+            log::warn!("Folder sync unavailable: {}", e);
+            return;
+        }
+    };
+    if summary.inserted + summary.updated > 0 {
+        log::info!(
+            "Synced with {} peers: {} new, {} updated",
+            summary.peers,
+            summary.inserted,
+            summary.updated
+        );
+    }
+}
+
+/// Sync with the known and discovered peers. Sessions run both ways, so on a
+/// schedule only the side with the smaller device id connects (`all` = every peer).
+async fn sync_round(app: &AppHandle, ctx: &SyncContext, all: bool) -> SyncSummary {
+    let mut targets: Vec<(String, SocketAddr, bool)> = Vec::new();
+    for peer in queries::get_sync_peers(&ctx.pool).await.unwrap_or_default() {
+        if let Some(addr) = peer.address.and_then(|a| a.parse().ok()) {
+            targets.push((peer.device_id, addr, true));
+        }
+    }
+    for found in DISCOVERED.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+        match targets.iter_mut().find(|(id, _, _)| *id == found.device_id) {
+            // The advertised address is fresher than the stored one
+            Some(target) => target.1 = found.addr,
+            None => targets.push((found.device_id.clone(), found.addr, false)),
+        }
+    }
+
+    let mut total = SyncSummary::default();
+    for (device_id, addr, known) in targets {
+        if !all && device_id < ctx.device_id {
+            continue;
+        }
+        let _session = SESSION.lock().await;
+        match lan::connect(ctx, addr).await {
+            Ok((peer, summary)) => {
+                report(app, &peer.name, &summary);
+                total.peers += 1;
+                total.inserted += summary.inserted;
+                total.updated += summary.updated;
+                total.sent += summary.sent;
+            }
+            // Discovered machines may be paired with a different secret
+            Err(e) if !known => log::debug!("Skipped sync peer at {}: {}", addr, e),
+            Err(e) => log::warn!("Sync with {} failed: {}", addr, e),
+        }
+    }
+    total
+}
+
+/// Read the items other machines wrote to the sync folder, then write the
+/// ones changed here since the last round.
+async fn folder_round(
+    app: &AppHandle,
+    ctx: &SyncContext,
+    dir: &Path,
+) -> Result<SyncSummary, String> {
+    let _session = SESSION.lock().await;
+    let since = setting(&ctx.pool, "sync_folder_seq")
+        .await
+        .parse()
+        .unwrap_or(0);
+    let mut summary = SyncSummary::default();
+    folder::ingest_items(ctx, dir, &mut summary).await?;
+    let next = folder::export_items(ctx, dir, since, &mut summary).await?;
+    queries::set_setting(&ctx.pool, "sync_folder_seq", &next.to_string())
+        .await
+        .map_err(|e| e.to_string())?;
+    report(app, "sync folder", &summary);
+    Ok(summary)
+}
+
+/// The context and entry folder for folder sync, when it is on and paired.
+async fn folder_context(
+    app: &AppHandle,
+    pool: &SqlitePool,
+) -> Result<Option<(SyncContext, PathBuf)>, String> {
+    let Some(folder) = sync_folder(pool).await else {
+        return Ok(None);
+    };
+    let Some(config) = load_config(pool).await else {
+        return Ok(None);
+    };
+    let ctx = SyncContext {
+        pool: pool.clone(),
+        key: config.key,
+        device_id: config.device_id,
+        device_name: config.device_name,
+        port: config.port,
+        app_data: app.path().app_data_dir().map_err(|e| e.to_string())?,
+    };
+    Ok(Some((ctx, folder.join(folder::DIR_NAME))))
+}
+
+/// Start, restart or stop the service to match the current settings. The
+/// service only runs for network sync.
+async fn refresh_service(app: &AppHandle, pool: &SqlitePool) -> Result<(), String> {
+    let config = match sync_folder(pool).await {
+        Some(_) => None,
+        None => load_config(pool).await,
+    };
+    {
+        let service = SERVICE.lock().unwrap_or_else(|e| e.into_inner());
+        if service.as_ref().map(|s| &s.config) == config.as_ref() {
+            return Ok(());
+        }
+    }
+    stop_service();
+    match config {
+        Some(config) => start_service(app, pool, config).await,
+        None => Ok(()),
+    }
+}
+
+async fn start_service(app: &AppHandle, pool: &SqlitePool, config: Config) -> Result<(), String> {
+    let app_data = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let listener = TcpListener::bind(("0.0.0.0", config.port))
+        .await
+        .map_err(|e| format!("Failed to listen on port {}: {}", config.port, e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+    let ctx = SyncContext {
+        pool: pool.clone(),
+        key: config.key,
+        device_id: config.device_id.clone(),
+        device_name: config.device_name.clone(),
+        port,
+        app_data,
+    };
+    let handle = tauri::async_runtime::spawn(accept_loop(app.clone(), ctx.clone(), listener));
+    let mdns = match start_mdns(&ctx) {
+        Ok(daemon) => Some(daemon),
+        Err(e) => {
+            log::warn!(
+                "mDNS unavailable, only peers added by address will sync: {}",
+                e
+            );
+            None
+        }
+    };
+
+    log::info!("LAN sync listening on port {}", port);
+    *SERVICE.lock().unwrap_or_else(|e| e.into_inner()) = Some(Service {
+        config,
+        ctx,
+        listener: handle,
+        mdns,
+    });
+    Ok(())
+}
+
+fn stop_service() {
+    let service = SERVICE.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(service) = service {
+        service.listener.abort();
+        if let Some(daemon) = service.mdns {
+            let _ = daemon.shutdown();
+        }
+        DISCOVERED.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        log::info!("LAN sync stopped");
+    }
+}
+
+async fn accept_loop(app: AppHandle, ctx: SyncContext, listener: TcpListener) {
+    let slots = std::sync::Arc::new(tokio::sync::Semaphore::new(MAX_PENDING_ACCEPTS));
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::warn!("Sync listener error: {}", e);
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+        let Ok(slot) = slots.clone().try_acquire_owned() else {
+            log::warn!("Too many sync connections, dropping {}", addr);
+            continue;
+        };
+        let app = app.clone();
+        let ctx = ctx.clone();
+        tauri::async_runtime::spawn(async move {
+            let _slot = slot;
+            // Only a peer that knows the pairing key gets to wait for the session
+            let accepted = match lan::accept_handshake(&ctx, stream).await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    log::warn!("Sync from {} failed: {}", addr, e);
+                    return;
+                }
+            };
+            let name = accepted.peer.name.clone();
+            let _session = SESSION.lock().await;
+            match lan::accept_session(&ctx, accepted).await {
+                Ok(summary) => report(&app, &name, &summary),
+                Err(e) => log::warn!("Sync from {} failed: {}", addr, e),
+            }
+        });
+    }
+}
+
+/// Advertise this machine and collect the peers advertised by others.
+fn start_mdns(ctx: &SyncContext) -> Result<ServiceDaemon, String> {
+    let daemon = ServiceDaemon::new().map_err(|e| e.to_string())?;
+    let host = format!("{}.local.", ctx.device_id);
+    let properties = [
+        ("id", ctx.device_id.as_str()),
+        ("name", ctx.device_name.as_str()),
+    ];
+    let info = ServiceInfo::new(
+        SERVICE_TYPE,
+        &ctx.device_id,
+        &host,
+        "",
+        ctx.port,
+        &properties[..],
+    )
+    .map_err(|e| e.to_string())?
+    .enable_addr_auto();
+    daemon.register(info).map_err(|e| e.to_string())?;
+
+    let events = daemon.browse(SERVICE_TYPE).map_err(|e| e.to_string())?;
+    let own_id = ctx.device_id.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Ok(event) = events.recv_async().await {
+            let mut discovered = DISCOVERED.lock().unwrap_or_else(|e| e.into_inner());
+            match event {
+                ServiceEvent::ServiceResolved(info) => {
+                    let Some(device_id) = info.get_property_val_str("id") else {
+                        continue;
+                    };
+                    // IPv4 first: link-local IPv6 addresses need a scope to connect
+                    let ip = info
+                        .get_addresses()
+                        .iter()
+                        .copied()
+                        .min_by_key(|ip| ip.is_ipv6());
+                    let (Some(ip), false) = (ip, device_id == own_id) else {
+                        continue;
+                    };
+                    let fullname = info.get_fullname().to_string();
+                    discovered.retain(|d| d.device_id != device_id && d.fullname != fullname);
+                    discovered.push(Discovered {
+                        device_id: device_id.to_string(),
+                        fullname,
+                        addr: SocketAddr::new(ip, info.get_port()),
+                    });
+                }
+                ServiceEvent::ServiceRemoved(_, fullname) => {
+                    discovered.retain(|d| d.fullname != fullname);
+                }
+                _ => {}
+            }
+        }
+    });
+    Ok(daemon)
+}
+
+async fn pair_with(app: &AppHandle, pool: &SqlitePool, secret: &str) -> Result<(), String> {
+    let key = sync::derive_pairing_key(secret)?;
+    queries::set_setting(pool, "sync_key", &B64.encode(key))
+        .await
+        .map_err(|e| e.to_string())?;
+    // Cursors and peers belong to the previous pairing
+    queries::clear_sync_peers(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    queries::reset_folder_sync(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    refresh_service(app, pool).await
+}
+
+fn report(app: &AppHandle, peer: &str, summary: &SyncSummary) {
+    log::debug!(
+        "Synced with {}: {} new, {} updated, {} sent",
+        peer,
+        summary.inserted,
+        summary.updated,
+        summary.sent
+    );
+    if summary.inserted + summary.updated > 0 {
+        let _ = app.emit("clipboard-changed", serde_json::json!({}));
+    }
+}
+
+async fn load_config(pool: &SqlitePool) -> Option<Config> {
+    if setting(pool, "sync_enabled").await != "true" {
+        return None;
+    }
+    let key: Key = B64
+        .decode(setting(pool, "sync_key").await)
+        .ok()?
+        .try_into()
+        .ok()?;
+    Some(Config {
+        key,
+        device_id: setting(pool, "sync_device_id").await,
+        device_name: setting(pool, "sync_device_name").await,
+        port: setting(pool, "sync_port")
+            .await
+            .parse()
+            .unwrap_or(DEFAULT_PORT),
+    })
+}
+
+/// The shared folder, when sync goes through one.
+async fn sync_folder(pool: &SqlitePool) -> Option<PathBuf> {
+    let folder = setting(pool, "sync_folder").await;
+    if setting(pool, "sync_mode").await != "folder" || folder.is_empty() {
+        return None;
+    }
+    Some(PathBuf::from(folder))
+}
+
+fn current_context() -> Option<SyncContext> {
+    SERVICE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|s| s.ctx.clone())
+}
+
+/// The running service's context, starting the service if the settings allow.
+async fn running_context(app: &AppHandle, pool: &SqlitePool) -> Result<SyncContext, String> {
+    refresh_service(app, pool).await?;
+    current_context().ok_or_else(|| "LAN sync is off or not paired".to_string())
+}
+
+async fn status(pool: &SqlitePool) -> Result<SyncStatus, String> {
+    let mut peers = queries::get_sync_peers(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    {
+        let discovered = DISCOVERED.lock().unwrap_or_else(|e| e.into_inner());
+        for peer in &mut peers {
+            peer.online = discovered.iter().any(|d| d.device_id == peer.device_id);
+        }
+    }
+    let port = match current_context() {
+        Some(ctx) => ctx.port,
+        None => setting(pool, "sync_port")
+            .await
+            .parse()
+            .unwrap_or(DEFAULT_PORT),
+    };
+    Ok(SyncStatus {
+        enabled: setting(pool, "sync_enabled").await == "true",
+        paired: !setting(pool, "sync_key").await.is_empty(),
+        mode: setting(pool, "sync_mode").await,
+        folder: Some(setting(pool, "sync_folder").await).filter(|f| !f.is_empty()),
+        device_id: setting(pool, "sync_device_id").await,
+        device_name: setting(pool, "sync_device_name").await,
+        port,
+        peers,
+    })
+}
+
+async fn setting(pool: &SqlitePool, key: &str) -> String {
+    queries::get_setting(pool, key)
+        .await
+        .unwrap_or(None)
+        .unwrap_or_default()
+}
@@ -0,0 +1,182 @@
+use super::clipboard as clip_cmd;
+use super::lock as lock_cmd;
+use crate::db::{models::TransformShortcut, queries, DbPool};
+use crate::transform::{self, Transform};
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Manager, State};
+
+/// Transform shortcuts currently registered.
+static REGISTERED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Names of the available transforms, for the paste menu and settings.
+#[tauri::command]
+pub fn get_transforms() -> Vec<String> {
+    Transform::ALL
+        .iter()
+        .map(|t| t.as_str().to_string())
+        .collect()
+}
+
+/// Paste a text item through `transforms`, applied in order. The result is
+/// pasted as plain text.
+#[tauri::command]
+pub async fn paste_transformed(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    id: String,
+    transforms: Vec<String>,
+    auto_paste: Option<bool>,
+) -> Result<(), String> {
+    lock_cmd::ensure_unlocked()?;
+    lock_cmd::touch();
+    let pipeline = transform::parse_pipeline(&transforms)?;
+    let (content_type, plain_text, _rich_content, _image_path, _file_path) =
+        queries::get_item_by_id(&db.0, &id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or("Item not found")?;
+    if !matches!(content_type.as_str(), "plain_text" | "rich_text" | "link") {
+        return Err("Only text items can be transformed".to_string());
+    }
+    let text = transform::apply_all(&plain_text, &pipeline)?;
+    paste_text(&app, text, auto_paste.unwrap_or(true)).await
+}
+
+#[tauri::command]
+pub async fn get_transform_shortcuts(
+    db: State<'_, DbPool>,
+) -> Result<Vec<TransformShortcut>, String> {
+    load_shortcuts(&db.0).await
+}
+
+/// Replace the transform shortcuts and register them. Each pastes the text
+/// on the clipboard through its transforms.
+#[tauri::command]
+pub async fn set_transform_shortcuts(
+    app: AppHandle,
+    db: State<'_, DbPool>,
+    shortcuts: Vec<TransformShortcut>,
+) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for binding in &shortcuts {
+        if binding.shortcut.trim().is_empty() {
+            return Err("Shortcut cannot be empty".to_string());
+        }
+        if !seen.insert(binding.shortcut.as_str()) {
+            return Err(format!("{} is used more than once", binding.shortcut));
+        }
+        transform::parse_pipeline(&binding.transforms)?;
+    }
+    let json = serde_json::to_string(&shortcuts).map_err(|e| e.to_string())?;
+    queries::set_setting(&db.0, "transform_shortcuts", &json)
+        .await
+        .map_err(|e| e.to_string())?;
+    refresh_shortcuts(&app).await
+}
+
+// ---- Shortcuts ----
+
+/// Register the transform shortcuts from settings in place of the previous
+/// ones; run at startup and whenever unregister_all has cleared them. Every
+/// binding is tried, and the first failure is returned.
+pub async fn refresh_shortcuts(app: &AppHandle) -> Result<(), String> {
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+    let previous = std::mem::take(&mut *registered());
+    for shortcut in previous {
+        // Already gone if the main shortcut was re-registered in between
+        let _ = app.global_shortcut().unregister(shortcut.as_str());
+    }
+    let Some(pool) = app.try_state::<DbPool>() else {
+        return Ok(());
+    };
+    let shortcuts = load_shortcuts(&pool.0).await?;
+
+    let mut first_error = None;
+    for binding in shortcuts {
+        let pipeline = match transform::parse_pipeline(&binding.transforms) {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                first_error.get_or_insert(format!("{}: {}", binding.shortcut, e));
+                continue;
+            }
+        };
+        let registered_ok = app.global_shortcut().on_shortcut(
+            binding.shortcut.as_str(),
+            move |app, _shortcut, event| {
+                // On release, as with the paste queue: pressing Cmd+V while the
+                // shortcut's keys are still held would paste something else
+                if event.state == ShortcutState::Released {
+                    let app = app.clone();
+                    let pipeline = pipeline.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = paste_clipboard_transformed(&app, &pipeline).await {
+                            log::warn!("Transform shortcut: {}", e);
+                        }
+                    });
+                }
+            },
+        );
+        match registered_ok {
+            Ok(()) => {
+                log::info!("Transform shortcut registered: {}", binding.shortcut);
+                registered().push(binding.shortcut);
+            }
+            Err(e) => {
+                first_error
+                    .get_or_insert(format!("Failed to register {}: {}", binding.shortcut, e));
+            }
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Paste the text on the clipboard through `pipeline`.
+async fn paste_clipboard_transformed(
+    app: &AppHandle,
+    pipeline: &[Transform],
+) -> Result<(), String> {
+    let text = tauri_plugin_clipboard_x::read_text()
+        .await
+        .map_err(|e| format!("Failed to read text: {}", e))?;
+    if text.is_empty() {
+        return Err("The clipboard has no text".to_string());
+    }
+    let text = transform::apply_all(&text, pipeline)?;
+    paste_text(app, text, true).await
+}
+
+// ---- Helpers ----
+
+async fn paste_text(app: &AppHandle, text: String, auto_paste: bool) -> Result<(), String> {
+    // Skip self-monitoring for this clipboard write (clear flag on failure)
+    crate::set_skip_next_clipboard_change();
+    if let Err(e) = clip_cmd::write_to_clipboard(app, "plain_text", &text, &None, &None, &[]).await
+    {
+        crate::clear_skip_next_clipboard_change();
+        return Err(e);
+    }
+
+    if auto_paste {
+        // Resign keyboard focus so the previous app receives the Cmd+V
+        crate::platform::platform_resign_before_paste(app);
+        clip_cmd::simulate_paste();
+        crate::hide_main_window(app);
+    }
+    Ok(())
+}
+
+async fn load_shortcuts(pool: &SqlitePool) -> Result<Vec<TransformShortcut>, String> {
+    let json = queries::get_setting(pool, "transform_shortcuts")
+        .await
+        .map_err(|e| e.to_string())?
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "[]".to_string());
+    serde_json::from_str(&json).map_err(|e| format!("Invalid transform_shortcuts: {}", e))
+}
+
+fn registered() -> MutexGuard<'static, Vec<String>> {
+    REGISTERED.lock().unwrap_or_else(|e| e.into_inner())
+}
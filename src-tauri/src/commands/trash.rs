@@ -0,0 +1,100 @@
+use super::lock as lock_cmd;
+use crate::db::{models::ClipboardItem, queries, DbPool};
+use sqlx::SqlitePool;
+use tauri::State;
+
+/// Get trashed items, most recently deleted first.
+#[tauri::command]
+pub async fn get_trash_items(
+    db: State<'_, DbPool>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<ClipboardItem>, String> {
+    lock_cmd::ensure_unlocked()?;
+    queries::get_trashed_items(&db.0, limit.unwrap_or(50), offset.unwrap_or(0))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Restore a trashed item to the history.
+#[tauri::command]
+pub async fn restore_clipboard_item(db: State<'_, DbPool>, id: String) -> Result<(), String> {
+    lock_cmd::ensure_unlocked()?;
+    let found = queries::restore_item(&db.0, &id)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !found {
+        return Err("Item not in trash".to_string());
+    }
+    Ok(())
+}
+
+/// Permanently delete a single item, removing its original image file if present.
+#[tauri::command]
+pub async fn delete_clipboard_item_permanently(
+    db: State<'_, DbPool>,
+    id: String,
+) -> Result<(), String> {
+    lock_cmd::ensure_unlocked()?;
+    // Capture image_path before deleting the DB row
+    let image_path = queries::get_image_path_by_id(&db.0, &id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    queries::delete_item(&db.0, &id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(path) = image_path {
+        remove_image_files(vec![path]);
+    }
+    Ok(())
+}
+
+/// Permanently delete everything in the trash, removing image files from disk.
+#[tauri::command]
+pub async fn empty_trash(db: State<'_, DbPool>) -> Result<i64, String> {
+    lock_cmd::ensure_unlocked()?;
+    purge_trash(&db.0, None).await
+}
+
+/// Purge items trashed more than `trash_retention_days` ago ("0" keeps them).
+/// Called periodically by the background trash purge.
+pub async fn purge_trash_by_retention(pool: &SqlitePool) -> Result<i64, String> {
+    let days = queries::get_setting(pool, "trash_retention_days")
+        .await
+        .map_err(|e| e.to_string())?
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(30);
+    if days <= 0 {
+        return Ok(0);
+    }
+    purge_trash(pool, Some(days)).await
+}
+
+async fn purge_trash(pool: &SqlitePool, older_than_days: Option<i64>) -> Result<i64, String> {
+    // Collect image paths before deleting rows
+    let image_paths = queries::get_trashed_image_paths(pool, older_than_days)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let deleted = queries::empty_trash(pool, older_than_days)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !image_paths.is_empty() {
+        remove_image_files(image_paths);
+    }
+    Ok(deleted)
+}
+
+/// Async file removal — best-effort, does not fail the command.
+fn remove_image_files(paths: Vec<String>) {
+    tokio::spawn(async move {
+        for path in paths {
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                log::warn!("Failed to delete image file {}: {}", path, e);
+            }
+        }
+    });
+}
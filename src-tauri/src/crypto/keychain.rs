@@ -0,0 +1,42 @@
+//! History key storage in the OS keychain (macOS Keychain, Windows Credential
+//! Manager, Secret Service on Linux).
+
+use super::{Key, KEY_LEN};
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+
+const SERVICE: &str = "com.recopy.app";
+const ACCOUNT: &str = "history-key";
+
+fn entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(SERVICE, ACCOUNT).map_err(|e| format!("Keychain unavailable: {}", e))
+}
+
+/// Load the history key, or None if no key has been stored.
+pub fn load_key() -> Result<Option<Key>, String> {
+    let secret = match entry()?.get_password() {
+        Ok(secret) => secret,
+        Err(keyring::Error::NoEntry) => return Ok(None),
+        Err(e) => return Err(format!("Failed to read keychain: {}", e)),
+    };
+    let bytes = B64
+        .decode(secret)
+        .map_err(|e| format!("Invalid key in keychain: {}", e))?;
+    let key: Key = bytes
+        .try_into()
+        .map_err(|_| format!("Keychain key must be {} bytes", KEY_LEN))?;
+    Ok(Some(key))
+}
+
+pub fn store_key(key: &Key) -> Result<(), String> {
+    entry()?
+        .set_password(&B64.encode(key))
+        .map_err(|e| format!("Failed to write keychain: {}", e))
+}
+
+pub fn delete_key() -> Result<(), String> {
+    match entry()?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete keychain entry: {}", e)),
+    }
+}
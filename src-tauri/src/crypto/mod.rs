@@ -0,0 +1,409 @@
+//! Encryption at rest for clipboard history.
+//!
+//! Sealed values are `MAGIC || nonce || ciphertext` (XChaCha20-Poly1305).
+//! Text columns hold them base64-encoded behind `TEXT_PREFIX`, so rows written
+//! before encryption was enabled are recognized and read back unchanged.
+//! Content hashes are sealed deterministically instead (`HASH_PREFIX`), so
+//! equal content still has equal sealed hashes for dedup.
+
+pub mod keychain;
+
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+pub const KEY_LEN: usize = 32;
+pub type Key = [u8; KEY_LEN];
+
+/// Suffix appended to the file name of encrypted images.
+pub const FILE_SUFFIX: &str = ".enc";
+
+const MAGIC: &[u8] = b"RCE1";
+const NONCE_LEN: usize = 24;
+const TEXT_PREFIX: &str = "rce1:";
+const HASH_PREFIX: &str = "rch1:";
+const SALT_LEN: usize = 16;
+const VERIFIER_PLAINTEXT: &str = "recopy-history-key";
+
+/// Where the history key comes from, stored in the `encryption_mode` setting.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EncryptionMode {
+    Off,
+    Passphrase,
+    Keychain,
+}
+
+impl EncryptionMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            EncryptionMode::Off => "off",
+            EncryptionMode::Passphrase => "passphrase",
+            EncryptionMode::Keychain => "keychain",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "off" => Some(EncryptionMode::Off),
+            "passphrase" => Some(EncryptionMode::Passphrase),
+            "keychain" => Some(EncryptionMode::Keychain),
+            _ => None,
+        }
+    }
+}
+
+// ---- Process-wide key state ----
+
+/// True while the history is encrypted (whether or not it is unlocked).
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The unlocked history key. None while locked or when encryption is off.
+static KEY: RwLock<Option<Key>> = RwLock::new(None);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Install (unlock) or drop (lock) the history key.
+pub fn set_key(key: Option<Key>) {
+    *KEY.write().unwrap_or_else(|e| e.into_inner()) = key;
+}
+
+pub fn current_key() -> Option<Key> {
+    *KEY.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Encryption is on but no key has been supplied yet.
+pub fn is_locked() -> bool {
+    is_enabled() && current_key().is_none()
+}
+
+/// The key new data is sealed with: Some only while encryption is on and unlocked.
+fn active_key() -> Option<Key> {
+    if is_enabled() {
+        current_key()
+    } else {
+        None
+    }
+}
+
+// ---- Keys ----
+
+/// Generate a random history key (keychain mode).
+pub fn generate_key() -> Key {
+    XChaCha20Poly1305::generate_key(&mut OsRng).into()
+}
+
+/// Generate a random base64 salt for passphrase key derivation.
+pub fn generate_salt() -> String {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    B64.encode(salt)
+}
+
+/// Derive the history key from a passphrase with Argon2id.
+pub fn derive_key(passphrase: &str, salt: &str) -> Result<Key, String> {
+    let salt = B64
+        .decode(salt)
+        .map_err(|e| format!("Invalid encryption salt: {}", e))?;
+    let mut key = [0u8; KEY_LEN];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| format!("Failed to derive key: {}", e))?;
+    Ok(key)
+}
+
+/// Known ciphertext stored alongside the data so unlock can reject a wrong key.
+pub fn make_verifier(key: &Key) -> String {
+    encrypt_text(key, VERIFIER_PLAINTEXT)
+}
+
+pub fn check_verifier(key: &Key, verifier: &str) -> bool {
+    decrypt_text(key, verifier).is_ok_and(|v| v == VERIFIER_PLAINTEXT)
+}
+
+// ---- Sealing with an explicit key ----
+
+pub fn encrypt(key: &Key, plaintext: &[u8]) -> Vec<u8> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .expect("XChaCha20-Poly1305 encryption cannot fail for in-memory buffers");
+
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    out
+}
+
+pub fn decrypt(key: &Key, data: &[u8]) -> Result<Vec<u8>, String> {
+    if !is_sealed(data) || data.len() < MAGIC.len() + NONCE_LEN {
+        return Err("Data is not encrypted".to_string());
+    }
+    let (nonce, ciphertext) = data[MAGIC.len()..].split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Decryption failed (wrong key or corrupted data)".to_string())
+}
+
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+pub fn encrypt_text(key: &Key, text: &str) -> String {
+    format!(
+        "{}{}",
+        TEXT_PREFIX,
+        B64.encode(encrypt(key, text.as_bytes()))
+    )
+}
+
+pub fn decrypt_text(key: &Key, text: &str) -> Result<String, String> {
+    let encoded = text
+        .strip_prefix(TEXT_PREFIX)
+        .ok_or("Text is not encrypted")?;
+    let data = B64.decode(encoded).map_err(|e| e.to_string())?;
+    String::from_utf8(decrypt(key, &data)?).map_err(|e| e.to_string())
+}
+
+pub fn is_sealed_text(text: &str) -> bool {
+    text.starts_with(TEXT_PREFIX)
+}
+
+/// Seal a content hash. The nonce is an HMAC of the hash under `key` (a
+/// synthetic IV), so the same hash always seals the same way, yet without the
+/// key it can neither be read back nor matched against guessed content.
+pub fn encrypt_hash(key: &Key, hash: &str) -> String {
+    let mac = hmac_sha256(key, hash.as_bytes());
+    let nonce = XNonce::from_slice(&mac[..NONCE_LEN]);
+    let ciphertext = XChaCha20Poly1305::new(key.into())
+        .encrypt(nonce, hash.as_bytes())
+        .expect("XChaCha20-Poly1305 encryption cannot fail for in-memory buffers");
+
+    let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(nonce);
+    out.extend_from_slice(&ciphertext);
+    format!("{}{}", HASH_PREFIX, B64.encode(out))
+}
+
+pub fn decrypt_hash(key: &Key, sealed: &str) -> Result<String, String> {
+    let encoded = sealed
+        .strip_prefix(HASH_PREFIX)
+        .ok_or("Hash is not encrypted")?;
+    let data = B64.decode(encoded).map_err(|e| e.to_string())?;
+    if data.len() < NONCE_LEN {
+        return Err("Hash is not encrypted".to_string());
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let hash = XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Decryption failed (wrong key or corrupted data)".to_string())?;
+    String::from_utf8(hash).map_err(|e| e.to_string())
+}
+
+pub fn is_sealed_hash(hash: &str) -> bool {
+    hash.starts_with(HASH_PREFIX)
+}
+
+/// HMAC-SHA256 (RFC 2104); keys are shorter than the block size.
+fn hmac_sha256(key: &Key, data: &[u8]) -> [u8; 32] {
+    let mut inner_pad = [0x36u8; 64];
+    let mut outer_pad = [0x5cu8; 64];
+    for (i, b) in key.iter().enumerate() {
+        inner_pad[i] ^= b;
+        outer_pad[i] ^= b;
+    }
+    let inner = Sha256::new()
+        .chain_update(inner_pad)
+        .chain_update(data)
+        .finalize();
+    Sha256::new()
+        .chain_update(outer_pad)
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+// ---- Sealing with the current key ----
+
+/// Seal text for storage; unchanged while encryption is off.
+pub fn seal_text(text: &str) -> String {
+    match active_key() {
+        Some(key) => encrypt_text(&key, text),
+        None => text.to_string(),
+    }
+}
+
+/// Seal a blob for storage; unchanged while encryption is off.
+pub fn seal_bytes(data: &[u8]) -> Vec<u8> {
+    match active_key() {
+        Some(key) => encrypt(&key, data),
+        None => data.to_vec(),
+    }
+}
+
+/// Seal a content hash for storage; unchanged while encryption is off.
+pub fn seal_hash(hash: &str) -> String {
+    match active_key() {
+        Some(key) => encrypt_hash(&key, hash),
+        None => hash.to_string(),
+    }
+}
+
+/// Read back stored text. Plain rows pass through, as does plain text that only
+/// looks sealed; sealed rows come back empty while locked.
+pub fn open_text(text: String) -> String {
+    if !is_sealed_text(&text) {
+        return text;
+    }
+    match current_key() {
+        Some(key) => decrypt_text(&key, &text).unwrap_or(text),
+        None => String::new(),
+    }
+}
+
+/// Read back a stored content hash; sealed hashes stay sealed while locked.
+pub fn open_hash(hash: String) -> String {
+    if !is_sealed_hash(&hash) {
+        return hash;
+    }
+    match current_key() {
+        Some(key) => decrypt_hash(&key, &hash).unwrap_or(hash),
+        None => hash,
+    }
+}
+
+/// Read back a stored blob. Returns None for sealed blobs that can't be opened.
+pub fn open_bytes(data: Vec<u8>) -> Option<Vec<u8>> {
+    if !is_sealed(&data) {
+        return Some(data);
+    }
+    current_key().and_then(|key| {
+        decrypt(&key, &data)
+            .map_err(|e| log::warn!("Failed to decrypt blob: {}", e))
+            .ok()
+    })
+}
+
+/// Read an image file saved by the app, decrypting `.enc` files.
+pub fn read_image_file(path: &str) -> Result<Vec<u8>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("Failed to read image: {}", e))?;
+    if !path.ends_with(FILE_SUFFIX) {
+        return Ok(data);
+    }
+    let key = current_key().ok_or("History is locked")?;
+    decrypt(&key, &data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let key = generate_key();
+        let sealed = encrypt(&key, b"hunter2");
+        assert!(is_sealed(&sealed));
+        assert_ne!(&sealed[MAGIC.len() + NONCE_LEN..], b"hunter2");
+        assert_eq!(decrypt(&key, &sealed).unwrap(), b"hunter2");
+
+        // Fresh nonce every time
+        assert_ne!(encrypt(&key, b"hunter2"), sealed);
+    }
+
+    #[test]
+    fn test_decrypt_rejects_wrong_key_and_tampering() {
+        let key = generate_key();
+        let mut sealed = encrypt(&key, b"secret");
+        assert!(decrypt(&generate_key(), &sealed).is_err());
+
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+        assert!(decrypt(&key, &sealed).is_err());
+
+        assert!(decrypt(&key, b"plain bytes").is_err());
+    }
+
+    #[test]
+    fn test_text_roundtrip() {
+        let key = generate_key();
+        let sealed = encrypt_text(&key, "密码 password");
+        assert!(is_sealed_text(&sealed));
+        assert!(!sealed.contains("password"));
+        assert_eq!(decrypt_text(&key, &sealed).unwrap(), "密码 password");
+
+        assert!(!is_sealed_text("plain text"));
+        assert!(decrypt_text(&key, "plain text").is_err());
+    }
+
+    #[test]
+    fn test_hash_sealing() {
+        let key = generate_key();
+        let hash = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        let sealed = encrypt_hash(&key, hash);
+        assert!(is_sealed_hash(&sealed));
+        assert!(!sealed.contains(hash));
+        assert_eq!(decrypt_hash(&key, &sealed).unwrap(), hash);
+
+        // Deterministic per key, so sealed hashes still dedup
+        assert_eq!(encrypt_hash(&key, hash), sealed);
+        assert_ne!(encrypt_hash(&generate_key(), hash), sealed);
+        assert_ne!(encrypt_hash(&key, "other"), sealed);
+        assert!(decrypt_hash(&generate_key(), &sealed).is_err());
+        assert!(decrypt_hash(&key, hash).is_err());
+
+        // Known answer (Python's hmac module, key of 32 0x0b bytes)
+        let mac: String = hmac_sha256(&[0x0b; KEY_LEN], b"Hi There")
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        assert_eq!(
+            mac,
+            "198a607eb44bfbc69903a0f1cf2bbdc5ba0aa3f3d9ae3c1c7a3b1696a0b68cf7"
+        );
+    }
+
+    #[test]
+    fn test_derive_key() {
+        let salt = generate_salt();
+        let key = derive_key("correct horse", &salt).unwrap();
+        assert_eq!(derive_key("correct horse", &salt).unwrap(), key);
+        assert_ne!(derive_key("wrong horse", &salt).unwrap(), key);
+        assert_ne!(derive_key("correct horse", &generate_salt()).unwrap(), key);
+        assert!(derive_key("correct horse", "not base64!").is_err());
+    }
+
+    #[test]
+    fn test_verifier() {
+        let key = generate_key();
+        let verifier = make_verifier(&key);
+        assert!(check_verifier(&key, &verifier));
+        assert!(!check_verifier(&generate_key(), &verifier));
+        assert!(!check_verifier(&key, ""));
+    }
+
+    #[test]
+    fn test_encryption_mode_roundtrip() {
+        for mode in [
+            EncryptionMode::Off,
+            EncryptionMode::Passphrase,
+            EncryptionMode::Keychain,
+        ] {
+            assert_eq!(EncryptionMode::from_str(mode.as_str()), Some(mode));
+        }
+        assert_eq!(EncryptionMode::from_str("aes"), None);
+    }
+}
@@ -42,12 +42,34 @@ pub async fn init(app: &AppHandle) -> Result<SqlitePool, sqlx::Error> {
     Ok(pool)
 }
 
+/// Open an existing database read-only, without migrating it (for `recopy-cli`
+/// while the app is not running).
+pub async fn open_read_only(path: &std::path::Path) -> Result<SqlitePool, sqlx::Error> {
+    let options = SqliteConnectOptions::new().filename(path).read_only(true);
+
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+}
+
+/// Open a copy of the database read-write, without migrating it (for trimming
+/// backup snapshots before they are kept).
+pub async fn open_copy(path: &std::path::Path) -> Result<SqlitePool, sqlx::Error> {
+    let options = SqliteConnectOptions::new().filename(path);
+
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+}
+
 /// Wrapper around SqlitePool for Tauri state management.
 pub struct DbPool(pub SqlitePool);
 
 /// Helper to create a test pool with in-memory SQLite and run migrations.
 #[cfg(test)]
-async fn test_pool() -> SqlitePool {
+pub(crate) async fn test_pool() -> SqlitePool {
     let options = SqliteConnectOptions::from_str("sqlite::memory:")
         .unwrap()
         .create_if_missing(true);
@@ -84,6 +106,39 @@ mod tests {
         assert!(names.contains(&"settings"));
     }
 
+    #[tokio::test]
+    async fn test_image_dedup_default_only_resets_fresh_values() {
+        let pool = test_pool().await;
+        let distance = || async {
+            sqlx::query_as::<_, (String,)>(
+                "SELECT value FROM settings WHERE key = 'image_dedup_distance'",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .0
+        };
+        // A new database gets the new default
+        assert_eq!(distance().await, "0");
+
+        // A 4 stored by an earlier run may be the user's choice
+        sqlx::query("UPDATE settings SET value = '4' WHERE key = 'image_dedup_distance'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "UPDATE _sqlx_migrations SET installed_on = '2024-01-01 00:00:00' WHERE version = 9",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::raw_sql(include_str!("../../migrations/021_image_dedup_default.sql"))
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(distance().await, "4");
+    }
+
     #[tokio::test]
     async fn test_clipboard_item_crud() {
         let pool = test_pool().await;
@@ -35,6 +35,44 @@ impl ContentType {
     }
 }
 
+/// How an ignore rule is matched against the source application.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IgnoreMatchType {
+    BundleId,
+    Executable,
+    WindowTitle,
+}
+
+impl IgnoreMatchType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            IgnoreMatchType::BundleId => "bundle_id",
+            IgnoreMatchType::Executable => "executable",
+            IgnoreMatchType::WindowTitle => "window_title",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "bundle_id" => Some(IgnoreMatchType::BundleId),
+            "executable" => Some(IgnoreMatchType::Executable),
+            "window_title" => Some(IgnoreMatchType::WindowTitle),
+            _ => None,
+        }
+    }
+}
+
+/// A rule excluding an application's clipboard changes from history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IgnoredApp {
+    pub id: String,
+    pub match_type: String,
+    pub pattern: String,
+    pub created_at: String,
+}
+
 /// A clipboard item stored in the database.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClipboardItem {
@@ -54,6 +92,59 @@ pub struct ClipboardItem {
     pub content_size: i64,
     pub content_hash: String,
     pub is_favorited: bool,
+    /// Flagged by the sensitive-content classifier; `plain_text` is masked.
+    pub sensitive: bool,
+    /// Number of paths in a file item (0 for other types).
+    pub file_count: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_width: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_height: Option<i64>,
+    /// Encoding of the stored original (e.g. "png", "jpeg", "tiff").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_format: Option<String>,
+    /// When the item was moved to the trash (None for live items).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// One path of a file item, in clipboard order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ItemFile {
+    pub path: String,
+    pub name: String,
+    /// File size in bytes; total size of the contents for directories.
+    pub size: i64,
+    pub is_dir: bool,
+}
+
+/// What near-duplicate image matching compares: the perceptual hash, and the
+/// pixel size, which must be equal for two images to merge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageFingerprint {
+    pub phash: i64,
+    pub width: i64,
+    pub height: i64,
+}
+
+/// A user-defined collection of clipboard items.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Group {
+    pub id: String,
+    pub name: String,
+    pub sort_order: i64,
+    pub item_count: i64,
+    pub created_at: String,
+}
+
+/// Reusable text with placeholders, expanded when pasted (see `crate::snippet`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Snippet {
+    pub id: String,
+    pub name: String,
+    pub content: String,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -96,6 +187,176 @@ pub struct FilePreviewData {
     pub total_lines: usize,
 }
 
+/// Raw content columns of an item, as stored (sealed or not).
+/// Used to encrypt or decrypt existing history in place.
+pub struct ItemPayload {
+    pub id: String,
+    pub plain_text: String,
+    pub rich_content: Option<Vec<u8>>,
+    pub thumbnail: Option<Vec<u8>>,
+    pub image_path: Option<String>,
+    pub content_hash: String,
+}
+
+/// Encryption state reported to the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionStatus {
+    /// "off", "passphrase" or "keychain".
+    pub mode: String,
+    pub locked: bool,
+}
+
+/// App lock state reported to the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockStatus {
+    /// A lock passphrase is set (the app's own or the encryption passphrase).
+    pub enabled: bool,
+    pub locked: bool,
+}
+
+/// A snapshot in the backup folder, as listed by `list_backups`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    /// Snapshot folder name, passed back to `restore_backup`.
+    pub id: String,
+    /// UTC, "YYYY-MM-DD HH:MM:SS".
+    pub created_at: String,
+    /// Total size of the snapshot in bytes.
+    pub size: u64,
+}
+
+/// Which items `export_history` writes. Dates are SQLite datetimes
+/// ("YYYY-MM-DD HH:MM:SS", UTC) compared against `updated_at`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportFilter {
+    /// Content types to include (empty = all).
+    #[serde(default)]
+    pub content_types: Vec<String>,
+    #[serde(default)]
+    pub favorites_only: bool,
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+/// Result of `export_history`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportSummary {
+    pub items: usize,
+    pub images: usize,
+}
+
+/// Result of `import_history`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportSummary {
+    /// Items added to the history.
+    pub imported: usize,
+    /// Items whose content_hash already existed; merged instead of duplicated.
+    pub merged: usize,
+    pub groups_created: usize,
+    pub settings: usize,
+}
+
+/// An item read from an export archive, ready to merge into the history.
+pub struct ImportItem {
+    pub item: NewClipboardItem,
+    pub is_favorited: bool,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Local ids of the groups the item belongs to.
+    pub group_ids: Vec<String>,
+}
+
+/// A machine this one syncs with, as reported by `get_sync_status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncPeer {
+    pub device_id: String,
+    pub name: String,
+    /// Last known "host:port", discovered over mDNS or added by hand.
+    pub address: Option<String>,
+    pub last_sync_at: Option<String>,
+    /// Currently advertised on the local network.
+    pub online: bool,
+}
+
+/// Sync state reported to the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncStatus {
+    pub enabled: bool,
+    /// A pairing secret is set.
+    pub paired: bool,
+    /// "lan" or "folder".
+    pub mode: String,
+    /// Folder shared by a file-sync tool, for folder mode.
+    pub folder: Option<String>,
+    pub device_id: String,
+    pub device_name: String,
+    /// Port the sync listener accepts peers on.
+    pub port: u16,
+    pub peers: Vec<SyncPeer>,
+}
+
+/// Local HTTP API state reported to the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpApiStatus {
+    pub enabled: bool,
+    /// The server is listening (false while enabled if the port was taken).
+    pub running: bool,
+    pub port: u16,
+    /// Bearer token clients must send, None until the API is first enabled.
+    pub token: Option<String>,
+}
+
+/// Paste queue state reported to the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasteQueueStatus {
+    /// Captures are being appended to the queue.
+    pub collecting: bool,
+    /// "fifo" or "lifo".
+    pub order: String,
+    /// Pastes the next entry while collecting.
+    pub shortcut: String,
+    /// Queued items in the order they will be pasted.
+    pub items: Vec<ClipboardItem>,
+}
+
+/// A global shortcut that pastes the clipboard's text through transforms.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransformShortcut {
+    pub shortcut: String,
+    /// Transform names, applied in order (see transform::Transform).
+    pub transforms: Vec<String>,
+}
+
+/// Result of a sync round with one or more peers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncSummary {
+    pub peers: usize,
+    /// Items added from peers.
+    pub inserted: usize,
+    /// Existing items that took a peer's newer state.
+    pub updated: usize,
+    /// Items sent to peers.
+    pub sent: usize,
+}
+
+/// Sync bookkeeping of the local item matching a peer's content_hash.
+pub struct ItemSyncState {
+    pub id: String,
+    pub updated_at: String,
+    pub deleted_at: Option<String>,
+    pub changed_at: String,
+}
+
+/// A peer's newer state for an existing item, applied by `update_synced_item`.
+pub struct SyncedState {
+    pub is_favorited: bool,
+    pub updated_at: String,
+    pub deleted_at: Option<String>,
+    pub changed_at: String,
+    /// Local ids of the groups the item belongs to.
+    pub group_ids: Vec<String>,
+}
+
 /// Payload for inserting a new clipboard item.
 pub struct NewClipboardItem {
     pub content_type: ContentType,
@@ -109,6 +370,18 @@ pub struct NewClipboardItem {
     pub source_app_name: String,
     pub content_size: i64,
     pub content_hash: String,
+    /// Flagged by the sensitive-content classifier (excluded from search).
+    pub sensitive: bool,
+    /// All paths of a file item (empty for other types).
+    pub files: Vec<ItemFile>,
+    pub image_width: Option<i64>,
+    pub image_height: Option<i64>,
+    /// Encoding of the saved original image, as sniffed from its bytes.
+    pub image_format: Option<String>,
+    /// 64-bit perceptual hash of an image item, for near-duplicate detection.
+    pub image_phash: Option<i64>,
+    /// SQLite datetime after which the item is purged (None = kept per retention policy).
+    pub expires_at: Option<String>,
 }
 
 #[cfg(test)]
@@ -1,65 +1,249 @@
-use sqlx::SqlitePool;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqliteConnection, SqlitePool};
 use uuid::Uuid;
 
-use super::models::{ClipboardItem, NewClipboardItem};
+use super::models::{
+    ClipboardItem, ExportFilter, Group, IgnoredApp, ImageFingerprint, ImportItem, ItemFile,
+    ItemPayload, ItemSyncState, NewClipboardItem, Snippet, SyncPeer, SyncedState,
+};
+use crate::archive::ArchiveItem;
+use crate::clipboard as clip_util;
+use crate::crypto;
+use crate::sync::SyncItem;
+use std::collections::{HashMap, HashSet};
+
+/// Row shape of the list projection (everything except blobs).
+/// Columns are read by name, so every SELECT feeding it must project all of them.
+struct ItemRow {
+    id: String,
+    content_type: String,
+    plain_text: String,
+    image_path: Option<String>,
+    file_path: Option<String>,
+    file_name: Option<String>,
+    source_app: String,
+    source_app_name: String,
+    content_size: i64,
+    content_hash: String,
+    is_favorited: bool,
+    created_at: String,
+    updated_at: String,
+    sensitive: bool,
+    file_count: i64,
+    image_width: Option<i64>,
+    image_height: Option<i64>,
+    image_format: Option<String>,
+    deleted_at: Option<String>,
+}
+
+impl<'r> sqlx::FromRow<'r, SqliteRow> for ItemRow {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(ItemRow {
+            id: row.try_get("id")?,
+            content_type: row.try_get("content_type")?,
+            plain_text: row.try_get("plain_text")?,
+            image_path: row.try_get("image_path")?,
+            file_path: row.try_get("file_path")?,
+            file_name: row.try_get("file_name")?,
+            source_app: row.try_get("source_app")?,
+            source_app_name: row.try_get("source_app_name")?,
+            content_size: row.try_get("content_size")?,
+            content_hash: row.try_get("content_hash")?,
+            is_favorited: row.try_get("is_favorited")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+            sensitive: row.try_get("sensitive")?,
+            file_count: row.try_get("file_count")?,
+            image_width: row.try_get("image_width")?,
+            image_height: row.try_get("image_height")?,
+            image_format: row.try_get("image_format")?,
+            deleted_at: row.try_get("deleted_at")?,
+        })
+    }
+}
+
+/// Build a list item from an `ItemRow`, decrypting its text and masking sensitive items.
+fn item_from_row(r: ItemRow) -> ClipboardItem {
+    let plain_text = crypto::open_text(r.plain_text);
+    ClipboardItem {
+        id: r.id,
+        content_type: r.content_type,
+        plain_text: if r.sensitive {
+            clip_util::mask_sensitive(&plain_text)
+        } else {
+            plain_text
+        },
+        thumbnail: None,
+        image_path: r.image_path,
+        file_path: r.file_path,
+        file_name: r.file_name,
+        source_app: r.source_app,
+        source_app_name: r.source_app_name,
+        content_size: r.content_size,
+        content_hash: crypto::open_hash(r.content_hash),
+        is_favorited: r.is_favorited,
+        sensitive: r.sensitive,
+        file_count: r.file_count,
+        image_width: r.image_width,
+        image_height: r.image_height,
+        image_format: r.image_format,
+        deleted_at: r.deleted_at,
+        created_at: r.created_at,
+        updated_at: r.updated_at,
+    }
+}
 
 /// Insert a new clipboard item and sync FTS index (transactional).
+/// While encryption is on, content columns are sealed and the item is not indexed.
 pub async fn insert_item(
     pool: &SqlitePool,
     item: &NewClipboardItem,
 ) -> Result<String, sqlx::Error> {
-    let id = Uuid::new_v4().to_string();
-
+    if crypto::is_locked() {
+        return Err(sqlx::Error::Protocol("History is locked".to_string()));
+    }
     let mut tx = pool.begin().await?;
+    let id = insert_item_in(&mut tx, item).await?;
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+/// Insert an item (sealed, with its files and FTS row) on an open connection.
+async fn insert_item_in(
+    conn: &mut SqliteConnection,
+    item: &NewClipboardItem,
+) -> Result<String, sqlx::Error> {
+    let id = Uuid::new_v4().to_string();
 
     sqlx::query(
-        "INSERT INTO clipboard_items (id, content_type, plain_text, rich_content, thumbnail, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO clipboard_items (id, content_type, plain_text, rich_content, thumbnail, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, sensitive, expires_at, file_count, image_width, image_height, image_format, image_phash)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(&id)
     .bind(item.content_type.as_str())
-    .bind(&item.plain_text)
-    .bind(&item.rich_content)
-    .bind(&item.thumbnail)
+    .bind(crypto::seal_text(&item.plain_text))
+    .bind(item.rich_content.as_deref().map(crypto::seal_bytes))
+    .bind(item.thumbnail.as_deref().map(crypto::seal_bytes))
     .bind(&item.image_path)
     .bind(&item.file_path)
     .bind(item.file_name.as_deref().unwrap_or(""))
     .bind(&item.source_app)
     .bind(&item.source_app_name)
     .bind(item.content_size)
-    .bind(&item.content_hash)
-    .execute(&mut *tx)
+    .bind(crypto::seal_hash(&item.content_hash))
+    .bind(item.sensitive)
+    .bind(&item.expires_at)
+    .bind(item.files.len() as i64)
+    .bind(item.image_width)
+    .bind(item.image_height)
+    .bind(&item.image_format)
+    .bind(item.image_phash)
+    .execute(&mut *conn)
     .await?;
 
-    sqlx::query(
-        "INSERT INTO clipboard_fts (item_id, plain_text, file_name, source_app_name) VALUES (?, ?, ?, ?)",
-    )
-    .bind(&id)
-    .bind(&item.plain_text)
-    .bind(item.file_name.as_deref().unwrap_or(""))
-    .bind(&item.source_app_name)
-    .execute(&mut *tx)
-    .await?;
+    for (position, file) in item.files.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO item_files (item_id, position, path, name, size, is_dir) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(position as i64)
+        .bind(&file.path)
+        .bind(&file.name)
+        .bind(file.size)
+        .bind(file.is_dir)
+        .execute(&mut *conn)
+        .await?;
+    }
 
-    tx.commit().await?;
+    // Sensitive items are never indexed for search, nor is anything while encrypted
+    if !item.sensitive && !crypto::is_enabled() {
+        // Multi-file items index every file name, not just the first
+        let fts_file_name = if item.files.is_empty() {
+            item.file_name.clone().unwrap_or_default()
+        } else {
+            item.files
+                .iter()
+                .map(|f| f.name.as_str())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        sqlx::query(
+            "INSERT INTO clipboard_fts (item_id, plain_text, file_name, source_app_name) VALUES (?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(&item.plain_text)
+        .bind(&fts_file_name)
+        .bind(&item.source_app_name)
+        .execute(&mut *conn)
+        .await?;
+    }
 
     Ok(id)
 }
 
+/// How many recent same-size images near-duplicate matching compares against.
+const IMAGE_DEDUP_CANDIDATES: i64 = 500;
+
 /// Check if a clipboard item with the given hash already exists.
-/// If so, bump its updated_at and return its id.
+/// For images, `image` also matches the nearest of the most recent stored
+/// images of the same size whose perceptual hash is within `max_distance`
+/// bits (most recent wins on ties).
+/// If so, bump its updated_at (restoring it from the trash) and return its id.
 pub async fn find_and_bump_by_hash(
     pool: &SqlitePool,
     hash: &str,
+    image: Option<ImageFingerprint>,
+    max_distance: u32,
 ) -> Result<Option<String>, sqlx::Error> {
     let row: Option<(String,)> = sqlx::query_as(
-        "UPDATE clipboard_items SET updated_at = datetime('now') WHERE content_hash = ? RETURNING id",
+        "UPDATE clipboard_items SET updated_at = datetime('now'), deleted_at = NULL
+         WHERE content_hash = ? RETURNING id",
     )
-    .bind(hash)
+    .bind(crypto::seal_hash(hash))
     .fetch_optional(pool)
     .await?;
+    if row.is_some() {
+        return Ok(row.map(|(id,)| id));
+    }
+
+    let Some(image) = image else {
+        return Ok(None);
+    };
+    // SQLite has no popcount, so distances are computed here, over a bounded
+    // number of candidates
+    let candidates: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT id, image_phash FROM clipboard_items
+         WHERE image_phash IS NOT NULL AND image_width = ? AND image_height = ?
+           AND content_type = 'image'
+         ORDER BY updated_at DESC, id DESC LIMIT ?",
+    )
+    .bind(image.width)
+    .bind(image.height)
+    .bind(IMAGE_DEDUP_CANDIDATES)
+    .fetch_all(pool)
+    .await?;
+    let nearest = candidates
+        .into_iter()
+        .map(|(id, other)| {
+            (
+                clip_util::hamming_distance(image.phash as u64, other as u64),
+                id,
+            )
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance);
+    let Some((_, id)) = nearest else {
+        return Ok(None);
+    };
 
-    Ok(row.map(|(id,)| id))
+    sqlx::query(
+        "UPDATE clipboard_items SET updated_at = datetime('now'), deleted_at = NULL WHERE id = ?",
+    )
+    .bind(&id)
+    .execute(pool)
+    .await?;
+    Ok(Some(id))
 }
 
 /// Get clipboard items with optional type filter, ordered by updated_at desc.
@@ -71,9 +255,9 @@ pub async fn get_items(
     offset: i64,
 ) -> Result<Vec<ClipboardItem>, sqlx::Error> {
     let items = if let Some(ct) = content_type {
-        sqlx::query_as::<_, (String, String, String, Option<String>, Option<String>, Option<String>, String, String, i64, String, bool, String, String)>(
-            "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at
-             FROM clipboard_items WHERE content_type = ? ORDER BY updated_at DESC, id DESC LIMIT ? OFFSET ?",
+        sqlx::query_as::<_, ItemRow>(
+            "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at, sensitive, file_count, image_width, image_height, image_format, deleted_at
+             FROM clipboard_items WHERE content_type = ? AND deleted_at IS NULL ORDER BY updated_at DESC, id DESC LIMIT ? OFFSET ?",
         )
         .bind(ct)
         .bind(limit)
@@ -81,9 +265,9 @@ pub async fn get_items(
         .fetch_all(pool)
         .await?
     } else {
-        sqlx::query_as::<_, (String, String, String, Option<String>, Option<String>, Option<String>, String, String, i64, String, bool, String, String)>(
-            "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at
-             FROM clipboard_items ORDER BY updated_at DESC, id DESC LIMIT ? OFFSET ?",
+        sqlx::query_as::<_, ItemRow>(
+            "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at, sensitive, file_count, image_width, image_height, image_format, deleted_at
+             FROM clipboard_items WHERE deleted_at IS NULL ORDER BY updated_at DESC, id DESC LIMIT ? OFFSET ?",
         )
         .bind(limit)
         .bind(offset)
@@ -91,25 +275,36 @@ pub async fn get_items(
         .await?
     };
 
-    Ok(items
+    Ok(items.into_iter().map(item_from_row).collect())
+}
+
+/// Get list items by id, in the order of `ids`. Missing and trashed items are
+/// left out; an id listed twice appears twice.
+pub async fn get_items_by_ids(
+    pool: &SqlitePool,
+    ids: &[String],
+) -> Result<Vec<ClipboardItem>, sqlx::Error> {
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+    let sql = format!(
+        "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at, sensitive, file_count, image_width, image_height, image_format, deleted_at
+         FROM clipboard_items WHERE id IN ({}) AND deleted_at IS NULL",
+        placeholders
+    );
+    let mut q = sqlx::query_as::<_, ItemRow>(&sql);
+    for id in ids {
+        q = q.bind(id);
+    }
+    let items: HashMap<String, ClipboardItem> = q
+        .fetch_all(pool)
+        .await?
         .into_iter()
-        .map(|r| ClipboardItem {
-            id: r.0,
-            content_type: r.1,
-            plain_text: r.2,
-            thumbnail: None,
-            image_path: r.3,
-            file_path: r.4,
-            file_name: r.5,
-            source_app: r.6,
-            source_app_name: r.7,
-            content_size: r.8,
-            content_hash: r.9,
-            is_favorited: r.10,
-            created_at: r.11,
-            updated_at: r.12,
-        })
-        .collect())
+        .map(|r| (r.id.clone(), item_from_row(r)))
+        .collect();
+
+    Ok(ids.iter().filter_map(|id| items.get(id).cloned()).collect())
 }
 
 /// Get a single clipboard item by id.
@@ -126,12 +321,29 @@ pub async fn get_item_by_id(
     )>,
     sqlx::Error,
 > {
-    sqlx::query_as(
+    #[allow(clippy::type_complexity)]
+    let row: Option<(
+        String,
+        String,
+        Option<Vec<u8>>,
+        Option<String>,
+        Option<String>,
+    )> = sqlx::query_as(
         "SELECT content_type, plain_text, rich_content, image_path, file_path FROM clipboard_items WHERE id = ?",
     )
     .bind(id)
     .fetch_optional(pool)
-    .await
+    .await?;
+
+    Ok(row.map(|(ct, pt, rc, ip, fp)| {
+        (
+            ct,
+            crypto::open_text(pt),
+            rc.and_then(crypto::open_bytes),
+            ip,
+            fp,
+        )
+    }))
 }
 
 /// Get full item detail for preview (includes rich_content as UTF-8 string).
@@ -169,8 +381,10 @@ pub async fn get_item_detail(
     .await?;
 
     Ok(row.map(|(ct, pt, rc, ip, fp, fn_, cs)| {
-        let rich_str = rc.map(|bytes| String::from_utf8_lossy(&bytes).to_string());
-        (ct, pt, rich_str, ip, fp, fn_, cs)
+        let rich_str = rc
+            .and_then(crypto::open_bytes)
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string());
+        (ct, crypto::open_text(pt), rich_str, ip, fp, fn_, cs)
     }))
 }
 
@@ -188,55 +402,16 @@ pub async fn get_image_path_by_id(
     Ok(row.and_then(|(p,)| p))
 }
 
-/// Return all non-null image_paths for non-favorited items (used before clear_history).
-pub async fn get_non_favorited_image_paths(pool: &SqlitePool) -> Result<Vec<String>, sqlx::Error> {
+/// Return image_paths of non-favorited items whose expires_at has passed.
+pub async fn get_expired_image_paths(pool: &SqlitePool) -> Result<Vec<String>, sqlx::Error> {
     let rows: Vec<(String,)> = sqlx::query_as(
-        "SELECT image_path FROM clipboard_items WHERE is_favorited = 0 AND image_path IS NOT NULL",
+        "SELECT image_path FROM clipboard_items
+         WHERE is_favorited = 0
+           AND expires_at IS NOT NULL AND expires_at <= datetime('now')
+           AND image_path IS NOT NULL",
     )
     .fetch_all(pool)
     .await?;
-
-    Ok(rows.into_iter().map(|(p,)| p).collect())
-}
-
-/// Return image_paths for items that would be removed by the given retention policy.
-pub async fn get_retention_overflow_image_paths(
-    pool: &SqlitePool,
-    policy: &str,
-    days: i64,
-    count: i64,
-) -> Result<Vec<String>, sqlx::Error> {
-    let rows: Vec<(String,)> = match policy {
-        "days" if days > 0 => {
-            sqlx::query_as(
-                "SELECT image_path FROM clipboard_items
-                 WHERE is_favorited = 0
-                   AND created_at < datetime('now', ? || ' days')
-                   AND image_path IS NOT NULL",
-            )
-            .bind(format!("-{}", days))
-            .fetch_all(pool)
-            .await?
-        }
-        "count" if count > 0 => {
-            sqlx::query_as(
-                "SELECT image_path FROM clipboard_items
-                 WHERE is_favorited = 0
-                   AND id NOT IN (
-                       SELECT id FROM clipboard_items
-                       WHERE is_favorited = 0
-                       ORDER BY updated_at DESC, id DESC
-                       LIMIT ?
-                   )
-                   AND image_path IS NOT NULL",
-            )
-            .bind(count)
-            .fetch_all(pool)
-            .await?
-        }
-        _ => vec![],
-    };
-
     Ok(rows.into_iter().map(|(p,)| p).collect())
 }
 
@@ -250,7 +425,28 @@ pub async fn get_all_image_paths(pool: &SqlitePool) -> Result<Vec<String>, sqlx:
     Ok(rows.into_iter().map(|(p,)| p).collect())
 }
 
-/// Delete a clipboard item and its FTS entry (transactional).
+/// Get all paths of a file item, in clipboard order.
+pub async fn get_item_files(pool: &SqlitePool, id: &str) -> Result<Vec<ItemFile>, sqlx::Error> {
+    let rows: Vec<(String, String, i64, bool)> = sqlx::query_as(
+        "SELECT path, name, size, is_dir FROM item_files WHERE item_id = ? ORDER BY position ASC",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| ItemFile {
+            path: r.0,
+            name: r.1,
+            size: r.2,
+            is_dir: r.3,
+        })
+        .collect())
+}
+
+/// Permanently delete a clipboard item and its FTS entry (transactional).
+/// Regular deletes move items to the trash instead (see `trash_item`).
 pub async fn delete_item(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
     let mut tx = pool.begin().await?;
 
@@ -259,6 +455,16 @@ pub async fn delete_item(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error>
         .execute(&mut *tx)
         .await?;
 
+    sqlx::query("DELETE FROM item_groups WHERE item_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM item_files WHERE item_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
     sqlx::query("DELETE FROM clipboard_items WHERE id = ?")
         .bind(id)
         .execute(&mut *tx)
@@ -278,6 +484,11 @@ pub async fn search_items(
     limit: i64,
     favorites_only: bool,
 ) -> Result<Vec<ClipboardItem>, sqlx::Error> {
+    // Encrypted history has no FTS index and sealed text columns
+    if crypto::is_enabled() {
+        return search_items_decrypted(pool, query, content_type, limit, favorites_only).await;
+    }
+
     // Multi-token query: always use LIKE with AND matching
     let tokens: Vec<&str> = query.split_whitespace().collect();
     if tokens.len() > 1 {
@@ -291,12 +502,14 @@ pub async fn search_items(
 
     let fts_query = format!("\"{}\"", query.replace('"', "\"\""));
 
-    let item_ids: Vec<(String,)> =
-        sqlx::query_as("SELECT item_id FROM clipboard_fts WHERE clipboard_fts MATCH ? LIMIT ?")
-            .bind(&fts_query)
-            .bind(limit)
-            .fetch_all(pool)
-            .await?;
+    let item_ids: Vec<(String,)> = sqlx::query_as(
+        "SELECT item_id FROM clipboard_fts WHERE clipboard_fts MATCH ?
+             AND item_id IN (SELECT id FROM clipboard_items WHERE deleted_at IS NULL) LIMIT ?",
+    )
+    .bind(&fts_query)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
 
     if item_ids.is_empty() {
         return Ok(vec![]);
@@ -312,36 +525,19 @@ pub async fn search_items(
     };
     let sql = if content_type.is_some() {
         format!(
-            "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at
+            "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at, sensitive, file_count, image_width, image_height, image_format, deleted_at
              FROM clipboard_items WHERE id IN ({}) AND content_type = ?{} ORDER BY updated_at DESC, id DESC",
             placeholders, fav_filter
         )
     } else {
         format!(
-            "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at
+            "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at, sensitive, file_count, image_width, image_height, image_format, deleted_at
              FROM clipboard_items WHERE id IN ({}){} ORDER BY updated_at DESC, id DESC",
             placeholders, fav_filter
         )
     };
 
-    let mut q = sqlx::query_as::<
-        _,
-        (
-            String,
-            String,
-            String,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            String,
-            String,
-            i64,
-            String,
-            bool,
-            String,
-            String,
-        ),
-    >(&sql);
+    let mut q = sqlx::query_as::<_, ItemRow>(&sql);
     for id in &ids {
         q = q.bind(id);
     }
@@ -351,25 +547,7 @@ pub async fn search_items(
 
     let items = q.fetch_all(pool).await?;
 
-    Ok(items
-        .into_iter()
-        .map(|r| ClipboardItem {
-            id: r.0,
-            content_type: r.1,
-            plain_text: r.2,
-            thumbnail: None,
-            image_path: r.3,
-            file_path: r.4,
-            file_name: r.5,
-            source_app: r.6,
-            source_app_name: r.7,
-            content_size: r.8,
-            content_hash: r.9,
-            is_favorited: r.10,
-            created_at: r.11,
-            updated_at: r.12,
-        })
-        .collect())
+    Ok(items.into_iter().map(item_from_row).collect())
 }
 
 /// Fallback search using LIKE with multi-token AND matching.
@@ -391,10 +569,13 @@ async fn search_items_like(
     let mut binds = Vec::new();
     for token in &tokens {
         let pattern = format!("%{}%", token);
-        conditions.push("(plain_text LIKE ? OR file_name LIKE ? OR source_app_name LIKE ?)");
+        // Sensitive items only match on metadata, never on their text
+        conditions.push(
+            "((sensitive = 0 AND plain_text LIKE ?) OR file_name LIKE ? OR source_app_name LIKE ?)",
+        );
         binds.push(pattern);
     }
-    let where_clause = conditions.join(" AND ");
+    let where_clause = format!("deleted_at IS NULL AND {}", conditions.join(" AND "));
     let fav_filter = if favorites_only {
         " AND is_favorited = 1"
     } else {
@@ -403,36 +584,19 @@ async fn search_items_like(
 
     let sql = if let Some(_ct) = content_type {
         format!(
-            "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at
+            "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at, sensitive, file_count, image_width, image_height, image_format, deleted_at
              FROM clipboard_items WHERE {} AND content_type = ?{} ORDER BY updated_at DESC, id DESC LIMIT ?",
             where_clause, fav_filter
         )
     } else {
         format!(
-            "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at
+            "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at, sensitive, file_count, image_width, image_height, image_format, deleted_at
              FROM clipboard_items WHERE {}{} ORDER BY updated_at DESC, id DESC LIMIT ?",
             where_clause, fav_filter
         )
     };
 
-    let mut q = sqlx::query_as::<
-        _,
-        (
-            String,
-            String,
-            String,
-            Option<String>,
-            Option<String>,
-            Option<String>,
-            String,
-            String,
-            i64,
-            String,
-            bool,
-            String,
-            String,
-        ),
-    >(&sql);
+    let mut q = sqlx::query_as::<_, ItemRow>(&sql);
 
     // Bind each token's pattern 3 times (for plain_text, file_name, source_app_name)
     for pattern in &binds {
@@ -447,24 +611,65 @@ async fn search_items_like(
 
     let items = q.fetch_all(pool).await?;
 
-    Ok(items
+    Ok(items.into_iter().map(item_from_row).collect())
+}
+
+/// Search used while encryption is on: candidates are decrypted and every
+/// token must match (case-insensitively) text, file name or source app name.
+/// Excludes thumbnail blobs for fast IPC transfer.
+async fn search_items_decrypted(
+    pool: &SqlitePool,
+    query: &str,
+    content_type: Option<&str>,
+    limit: i64,
+    favorites_only: bool,
+) -> Result<Vec<ClipboardItem>, sqlx::Error> {
+    let tokens: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    if tokens.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let ct_filter = if content_type.is_some() {
+        " AND content_type = ?"
+    } else {
+        ""
+    };
+    let fav_filter = if favorites_only {
+        " AND is_favorited = 1"
+    } else {
+        ""
+    };
+    let sql = format!(
+        "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at, sensitive, file_count, image_width, image_height, image_format, deleted_at
+         FROM clipboard_items WHERE deleted_at IS NULL{}{} ORDER BY updated_at DESC, id DESC",
+        ct_filter, fav_filter
+    );
+
+    let mut q = sqlx::query_as::<_, ItemRow>(&sql);
+    if let Some(ct) = content_type {
+        q = q.bind(ct);
+    }
+    let rows = q.fetch_all(pool).await?;
+
+    Ok(rows
         .into_iter()
-        .map(|r| ClipboardItem {
-            id: r.0,
-            content_type: r.1,
-            plain_text: r.2,
-            thumbnail: None,
-            image_path: r.3,
-            file_path: r.4,
-            file_name: r.5,
-            source_app: r.6,
-            source_app_name: r.7,
-            content_size: r.8,
-            content_hash: r.9,
-            is_favorited: r.10,
-            created_at: r.11,
-            updated_at: r.12,
+        .map(item_from_row)
+        .filter(|item| {
+            // Sensitive items only match on metadata, never on their text
+            let text = if item.sensitive {
+                String::new()
+            } else {
+                item.plain_text.to_lowercase()
+            };
+            let file_name = item.file_name.as_deref().unwrap_or("").to_lowercase();
+            let app_name = item.source_app_name.to_lowercase();
+            tokens.iter().all(|t| {
+                text.contains(t.as_str())
+                    || file_name.contains(t.as_str())
+                    || app_name.contains(t.as_str())
+            })
         })
+        .take(limit.max(0) as usize)
         .collect())
 }
 
@@ -475,7 +680,7 @@ pub async fn get_thumbnail(pool: &SqlitePool, id: &str) -> Result<Option<Vec<u8>
             .bind(id)
             .fetch_optional(pool)
             .await?;
-    Ok(row.and_then(|r| r.0))
+    Ok(row.and_then(|r| r.0).and_then(crypto::open_bytes))
 }
 
 /// Update the thumbnail for an existing clipboard item.
@@ -485,7 +690,7 @@ pub async fn update_thumbnail(
     thumbnail: &[u8],
 ) -> Result<(), sqlx::Error> {
     sqlx::query("UPDATE clipboard_items SET thumbnail = ? WHERE id = ?")
-        .bind(thumbnail)
+        .bind(crypto::seal_bytes(thumbnail))
         .bind(id)
         .execute(pool)
         .await?;
@@ -503,468 +708,2477 @@ pub async fn get_favorited_items(
     offset: i64,
 ) -> Result<Vec<super::models::ClipboardItem>, sqlx::Error> {
     let sql = if content_type.is_some() {
-        "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at
-         FROM clipboard_items WHERE is_favorited = 1 AND content_type = ? ORDER BY updated_at DESC, id DESC LIMIT ? OFFSET ?"
+        "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at, sensitive, file_count, image_width, image_height, image_format, deleted_at
+         FROM clipboard_items WHERE is_favorited = 1 AND deleted_at IS NULL AND content_type = ? ORDER BY updated_at DESC, id DESC LIMIT ? OFFSET ?"
     } else {
-        "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at
-         FROM clipboard_items WHERE is_favorited = 1 ORDER BY updated_at DESC, id DESC LIMIT ? OFFSET ?"
+        "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at, sensitive, file_count, image_width, image_height, image_format, deleted_at
+         FROM clipboard_items WHERE is_favorited = 1 AND deleted_at IS NULL ORDER BY updated_at DESC, id DESC LIMIT ? OFFSET ?"
     };
 
     let items = if let Some(ct) = content_type {
-        sqlx::query_as::<
-            _,
-            (
-                String,
-                String,
-                String,
-                Option<String>,
-                Option<String>,
-                Option<String>,
-                String,
-                String,
-                i64,
-                String,
-                bool,
-                String,
-                String,
-            ),
-        >(sql)
-        .bind(ct)
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(pool)
-        .await?
+        sqlx::query_as::<_, ItemRow>(sql)
+            .bind(ct)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(pool)
+            .await?
     } else {
-        sqlx::query_as::<
-            _,
-            (
-                String,
-                String,
-                String,
-                Option<String>,
-                Option<String>,
-                Option<String>,
-                String,
-                String,
-                i64,
-                String,
-                bool,
-                String,
-                String,
-            ),
-        >(sql)
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(pool)
-        .await?
+        sqlx::query_as::<_, ItemRow>(sql)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(pool)
+            .await?
     };
 
-    Ok(items
+    Ok(items.into_iter().map(item_from_row).collect())
+}
+
+// ---- Groups ----
+
+/// Get all groups ordered by sort_order, with the number of items in each.
+/// Trashed items keep their memberships but are not counted.
+pub async fn get_groups(pool: &SqlitePool) -> Result<Vec<Group>, sqlx::Error> {
+    let rows: Vec<(String, String, i64, i64, String)> = sqlx::query_as(
+        "SELECT g.id, g.name, g.sort_order, COUNT(ci.id), g.created_at
+         FROM groups g
+         LEFT JOIN item_groups ig ON ig.group_id = g.id
+         LEFT JOIN clipboard_items ci ON ci.id = ig.item_id AND ci.deleted_at IS NULL
+         GROUP BY g.id ORDER BY g.sort_order ASC, g.created_at ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
         .into_iter()
-        .map(|r| super::models::ClipboardItem {
+        .map(|r| Group {
             id: r.0,
-            content_type: r.1,
-            plain_text: r.2,
-            thumbnail: None,
-            image_path: r.3,
-            file_path: r.4,
-            file_name: r.5,
-            source_app: r.6,
-            source_app_name: r.7,
-            content_size: r.8,
-            content_hash: r.9,
-            is_favorited: r.10,
-            created_at: r.11,
-            updated_at: r.12,
+            name: r.1,
+            sort_order: r.2,
+            item_count: r.3,
+            created_at: r.4,
         })
         .collect())
 }
 
-// ---- Settings ----
+/// Create a new group appended after the existing ones. Returns the new id.
+pub async fn create_group(pool: &SqlitePool, name: &str) -> Result<String, sqlx::Error> {
+    let id = Uuid::new_v4().to_string();
 
-/// Get a setting value by key.
-pub async fn get_setting(pool: &SqlitePool, key: &str) -> Result<Option<String>, sqlx::Error> {
-    let row: Option<(String,)> = sqlx::query_as("SELECT value FROM settings WHERE key = ?")
-        .bind(key)
-        .fetch_optional(pool)
-        .await?;
-    Ok(row.map(|r| r.0))
-}
+    sqlx::query(
+        "INSERT INTO groups (id, name, sort_order)
+         VALUES (?, ?, (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM groups))",
+    )
+    .bind(&id)
+    .bind(name)
+    .execute(pool)
+    .await?;
 
-/// Get all settings as key-value pairs.
-pub async fn get_all_settings(pool: &SqlitePool) -> Result<Vec<(String, String)>, sqlx::Error> {
-    sqlx::query_as("SELECT key, value FROM settings ORDER BY key")
-        .fetch_all(pool)
-        .await
+    Ok(id)
 }
 
-/// Set a setting value (upsert).
-pub async fn set_setting(pool: &SqlitePool, key: &str, value: &str) -> Result<(), sqlx::Error> {
-    sqlx::query("INSERT INTO settings (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value = excluded.value")
-        .bind(key)
-        .bind(value)
+/// Rename a group. Returns false if the group does not exist.
+pub async fn rename_group(pool: &SqlitePool, id: &str, name: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE groups SET name = ? WHERE id = ?")
+        .bind(name)
+        .bind(id)
         .execute(pool)
         .await?;
-    Ok(())
+    Ok(result.rows_affected() > 0)
 }
 
-/// Clear all non-favorited clipboard items and their FTS entries (transactional).
-pub async fn clear_history(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+/// Delete a group and its item associations (transactional). Items themselves are kept.
+pub async fn delete_group(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
     let mut tx = pool.begin().await?;
 
-    sqlx::query(
-        "DELETE FROM clipboard_fts WHERE item_id IN (SELECT id FROM clipboard_items WHERE is_favorited = 0)",
-    )
-    .execute(&mut *tx)
-    .await?;
-
-    sqlx::query(
-        "DELETE FROM item_groups WHERE item_id IN (SELECT id FROM clipboard_items WHERE is_favorited = 0)",
-    )
-    .execute(&mut *tx)
-    .await?;
+    sqlx::query("DELETE FROM item_groups WHERE group_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
 
-    let result = sqlx::query("DELETE FROM clipboard_items WHERE is_favorited = 0")
+    sqlx::query("DELETE FROM groups WHERE id = ?")
+        .bind(id)
         .execute(&mut *tx)
         .await?;
 
     tx.commit().await?;
 
-    Ok(result.rows_affected() as i64)
+    Ok(())
 }
 
-/// Cleanup old items by retention policy (days or count). Preserves favorites. Transactional.
-pub async fn cleanup_by_retention(
-    pool: &SqlitePool,
-    policy: &str,
-    days: i64,
-    count: i64,
-) -> Result<i64, sqlx::Error> {
-    match policy {
-        "days" if days > 0 => {
-            let mut tx = pool.begin().await?;
-            let days_str = format!("-{}", days);
-
-            sqlx::query(
-                "DELETE FROM clipboard_fts WHERE item_id IN (
-                    SELECT id FROM clipboard_items
-                    WHERE is_favorited = 0
-                    AND created_at < datetime('now', ? || ' days')
-                )",
-            )
-            .bind(&days_str)
-            .execute(&mut *tx)
-            .await?;
+/// Rewrite sort_order so groups appear in the order of `ids` (transactional).
+/// Groups not listed keep their current sort_order.
+pub async fn reorder_groups(pool: &SqlitePool, ids: &[String]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
 
-            sqlx::query(
-                "DELETE FROM item_groups WHERE item_id IN (
-                    SELECT id FROM clipboard_items
-                    WHERE is_favorited = 0
-                    AND created_at < datetime('now', ? || ' days')
-                )",
-            )
-            .bind(&days_str)
+    for (index, id) in ids.iter().enumerate() {
+        sqlx::query("UPDATE groups SET sort_order = ? WHERE id = ?")
+            .bind(index as i64)
+            .bind(id)
             .execute(&mut *tx)
             .await?;
+    }
 
-            let result = sqlx::query(
-                "DELETE FROM clipboard_items WHERE is_favorited = 0 AND created_at < datetime('now', ? || ' days')",
-            )
-            .bind(&days_str)
-            .execute(&mut *tx)
-            .await?;
+    tx.commit().await?;
 
-            tx.commit().await?;
+    Ok(())
+}
+
+/// Add an item to a group (no-op if already a member).
+pub async fn add_item_to_group(
+    pool: &SqlitePool,
+    item_id: &str,
+    group_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT OR IGNORE INTO item_groups (item_id, group_id) VALUES (?, ?)")
+        .bind(item_id)
+        .bind(group_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Remove an item from a group.
+pub async fn remove_item_from_group(
+    pool: &SqlitePool,
+    item_id: &str,
+    group_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM item_groups WHERE item_id = ? AND group_id = ?")
+        .bind(item_id)
+        .bind(group_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Get the ids of all groups an item belongs to.
+pub async fn get_item_group_ids(
+    pool: &SqlitePool,
+    item_id: &str,
+) -> Result<Vec<String>, sqlx::Error> {
+    let rows: Vec<(String,)> = sqlx::query_as("SELECT group_id FROM item_groups WHERE item_id = ?")
+        .bind(item_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+/// Get items belonging to a group, optionally filtered by content type.
+/// Excludes thumbnail blobs for fast IPC transfer.
+pub async fn get_group_items(
+    pool: &SqlitePool,
+    group_id: &str,
+    content_type: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<ClipboardItem>, sqlx::Error> {
+    let sql = if content_type.is_some() {
+        "SELECT ci.id, ci.content_type, ci.plain_text, ci.image_path, ci.file_path, ci.file_name, ci.source_app, ci.source_app_name, ci.content_size, ci.content_hash, ci.is_favorited, ci.created_at, ci.updated_at, ci.sensitive, ci.file_count, ci.image_width, ci.image_height, ci.image_format, ci.deleted_at
+         FROM clipboard_items ci JOIN item_groups ig ON ig.item_id = ci.id
+         WHERE ig.group_id = ? AND ci.deleted_at IS NULL AND ci.content_type = ? ORDER BY ci.updated_at DESC, ci.id DESC LIMIT ? OFFSET ?"
+    } else {
+        "SELECT ci.id, ci.content_type, ci.plain_text, ci.image_path, ci.file_path, ci.file_name, ci.source_app, ci.source_app_name, ci.content_size, ci.content_hash, ci.is_favorited, ci.created_at, ci.updated_at, ci.sensitive, ci.file_count, ci.image_width, ci.image_height, ci.image_format, ci.deleted_at
+         FROM clipboard_items ci JOIN item_groups ig ON ig.item_id = ci.id
+         WHERE ig.group_id = ? AND ci.deleted_at IS NULL ORDER BY ci.updated_at DESC, ci.id DESC LIMIT ? OFFSET ?"
+    };
+
+    let mut q = sqlx::query_as::<_, ItemRow>(sql).bind(group_id);
+    if let Some(ct) = content_type {
+        q = q.bind(ct);
+    }
+
+    let items = q.bind(limit).bind(offset).fetch_all(pool).await?;
+
+    Ok(items.into_iter().map(item_from_row).collect())
+}
+
+// ---- Ignored apps ----
+
+/// Get all capture ignore rules, oldest first.
+pub async fn get_ignored_apps(pool: &SqlitePool) -> Result<Vec<IgnoredApp>, sqlx::Error> {
+    let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+        "SELECT id, match_type, pattern, created_at FROM ignored_apps ORDER BY created_at ASC, id ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| IgnoredApp {
+            id: r.0,
+            match_type: r.1,
+            pattern: r.2,
+            created_at: r.3,
+        })
+        .collect())
+}
+
+/// Add a capture ignore rule. Returns the id of the new or already existing rule.
+pub async fn add_ignored_app(
+    pool: &SqlitePool,
+    match_type: &str,
+    pattern: &str,
+) -> Result<String, sqlx::Error> {
+    let id = Uuid::new_v4().to_string();
+
+    let row: (String,) = sqlx::query_as(
+        "INSERT INTO ignored_apps (id, match_type, pattern) VALUES (?, ?, ?)
+         ON CONFLICT(match_type, pattern) DO UPDATE SET pattern = excluded.pattern
+         RETURNING id",
+    )
+    .bind(&id)
+    .bind(match_type)
+    .bind(pattern)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.0)
+}
+
+/// Remove a capture ignore rule.
+pub async fn remove_ignored_app(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM ignored_apps WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// ---- Settings ----
+
+/// Get a setting value by key.
+pub async fn get_setting(pool: &SqlitePool, key: &str) -> Result<Option<String>, sqlx::Error> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|r| r.0))
+}
+
+/// Get all settings as key-value pairs.
+pub async fn get_all_settings(pool: &SqlitePool) -> Result<Vec<(String, String)>, sqlx::Error> {
+    sqlx::query_as("SELECT key, value FROM settings ORDER BY key")
+        .fetch_all(pool)
+        .await
+}
+
+/// Set a setting value (upsert).
+pub async fn set_setting(pool: &SqlitePool, key: &str, value: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO settings (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value = excluded.value")
+        .bind(key)
+        .bind(value)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Move all non-favorited clipboard items to the trash. Returns the number trashed.
+pub async fn clear_history(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE clipboard_items SET deleted_at = datetime('now')
+         WHERE is_favorited = 0 AND deleted_at IS NULL",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() as i64)
+}
+
+/// Move old items to the trash by retention policy (days or count). Preserves favorites.
+/// Items already in the trash don't count towards the retained total.
+pub async fn cleanup_by_retention(
+    pool: &SqlitePool,
+    policy: &str,
+    days: i64,
+    count: i64,
+) -> Result<i64, sqlx::Error> {
+    let result = match policy {
+        "days" if days > 0 => {
+            sqlx::query(
+                "UPDATE clipboard_items SET deleted_at = datetime('now')
+                 WHERE is_favorited = 0 AND deleted_at IS NULL
+                   AND created_at < datetime('now', ? || ' days')",
+            )
+            .bind(format!("-{}", days))
+            .execute(pool)
+            .await?
+        }
+        "count" if count > 0 => {
+            sqlx::query(
+                "UPDATE clipboard_items SET deleted_at = datetime('now')
+                 WHERE is_favorited = 0 AND deleted_at IS NULL AND id NOT IN (
+                     SELECT id FROM clipboard_items
+                     WHERE is_favorited = 0 AND deleted_at IS NULL
+                     ORDER BY updated_at DESC, id DESC
+                     LIMIT ?
+                 )",
+            )
+            .bind(count)
+            .execute(pool)
+            .await?
+        }
+        _ => return Ok(0), // "unlimited" or invalid - no cleanup
+    };
+
+    Ok(result.rows_affected() as i64)
+}
+
+/// Delete non-favorited items whose expires_at has passed. Returns deleted count.
+/// Favoriting an expiring item keeps it.
+pub async fn purge_expired_items(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "DELETE FROM clipboard_fts WHERE item_id IN (
+            SELECT id FROM clipboard_items
+            WHERE is_favorited = 0
+            AND expires_at IS NOT NULL AND expires_at <= datetime('now')
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM item_groups WHERE item_id IN (
+            SELECT id FROM clipboard_items
+            WHERE is_favorited = 0
+            AND expires_at IS NOT NULL AND expires_at <= datetime('now')
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM item_files WHERE item_id IN (
+            SELECT id FROM clipboard_items
+            WHERE is_favorited = 0
+            AND expires_at IS NOT NULL AND expires_at <= datetime('now')
+        )",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let result = sqlx::query(
+        "DELETE FROM clipboard_items
+         WHERE is_favorited = 0 AND expires_at IS NOT NULL AND expires_at <= datetime('now')",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected() as i64)
+}
+
+// ---- Trash ----
+
+/// Move an item to the trash. Returns false if it doesn't exist or is already trashed.
+pub async fn trash_item(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE clipboard_items SET deleted_at = datetime('now') WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Restore a trashed item. Returns false if it isn't in the trash.
+pub async fn restore_item(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE clipboard_items SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Get trashed items, most recently deleted first.
+/// Excludes thumbnail blobs for fast IPC transfer.
+pub async fn get_trashed_items(
+    pool: &SqlitePool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<ClipboardItem>, sqlx::Error> {
+    let items = sqlx::query_as::<_, ItemRow>(
+        "SELECT id, content_type, plain_text, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, created_at, updated_at, sensitive, file_count, image_width, image_height, image_format, deleted_at
+         FROM clipboard_items WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, id DESC LIMIT ? OFFSET ?",
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(items.into_iter().map(item_from_row).collect())
+}
+
+/// Return image_paths of trashed items, optionally only those trashed more than
+/// `older_than_days` days ago (used before empty_trash).
+pub async fn get_trashed_image_paths(
+    pool: &SqlitePool,
+    older_than_days: Option<i64>,
+) -> Result<Vec<String>, sqlx::Error> {
+    let rows: Vec<(String,)> = sqlx::query_as(
+        "SELECT image_path FROM clipboard_items
+         WHERE deleted_at IS NOT NULL AND deleted_at <= datetime('now', ? || ' days')
+           AND image_path IS NOT NULL",
+    )
+    .bind(format!("-{}", older_than_days.unwrap_or(0)))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|(p,)| p).collect())
+}
+
+/// Permanently delete trashed items, optionally only those trashed more than
+/// `older_than_days` days ago. Returns deleted count. Transactional.
+pub async fn empty_trash(
+    pool: &SqlitePool,
+    older_than_days: Option<i64>,
+) -> Result<i64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let days_str = format!("-{}", older_than_days.unwrap_or(0));
+
+    sqlx::query(
+        "DELETE FROM clipboard_fts WHERE item_id IN (
+            SELECT id FROM clipboard_items
+            WHERE deleted_at IS NOT NULL AND deleted_at <= datetime('now', ? || ' days')
+        )",
+    )
+    .bind(&days_str)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM item_groups WHERE item_id IN (
+            SELECT id FROM clipboard_items
+            WHERE deleted_at IS NOT NULL AND deleted_at <= datetime('now', ? || ' days')
+        )",
+    )
+    .bind(&days_str)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM item_files WHERE item_id IN (
+            SELECT id FROM clipboard_items
+            WHERE deleted_at IS NOT NULL AND deleted_at <= datetime('now', ? || ' days')
+        )",
+    )
+    .bind(&days_str)
+    .execute(&mut *tx)
+    .await?;
+
+    let result = sqlx::query(
+        "DELETE FROM clipboard_items
+         WHERE deleted_at IS NOT NULL AND deleted_at <= datetime('now', ? || ' days')",
+    )
+    .bind(&days_str)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected() as i64)
+}
+
+// ---- Encryption ----
+
+/// Get the raw content columns of every item, trashed ones included.
+pub async fn get_item_payloads(pool: &SqlitePool) -> Result<Vec<ItemPayload>, sqlx::Error> {
+    #[allow(clippy::type_complexity)]
+    let rows: Vec<(
+        String,
+        String,
+        Option<Vec<u8>>,
+        Option<Vec<u8>>,
+        Option<String>,
+        String,
+    )> = sqlx::query_as(
+        "SELECT id, plain_text, rich_content, thumbnail, image_path, content_hash FROM clipboard_items",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| ItemPayload {
+            id: r.0,
+            plain_text: r.1,
+            rich_content: r.2,
+            thumbnail: r.3,
+            image_path: r.4,
+            content_hash: r.5,
+        })
+        .collect())
+}
+
+/// Write back re-sealed content columns and the matching settings in one
+/// transaction. The items' FTS rows are dropped, then rebuilt from the (now
+/// plain) text when `reindex` is set. Sensitive items are never indexed.
+pub async fn replace_item_payloads(
+    pool: &SqlitePool,
+    payloads: &[ItemPayload],
+    reindex: bool,
+    settings: &[(&str, &str)],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    for p in payloads {
+        sqlx::query(
+            "UPDATE clipboard_items SET plain_text = ?, rich_content = ?, thumbnail = ?, image_path = ?, content_hash = ? WHERE id = ?",
+        )
+        .bind(&p.plain_text)
+        .bind(&p.rich_content)
+        .bind(&p.thumbnail)
+        .bind(&p.image_path)
+        .bind(&p.content_hash)
+        .bind(&p.id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // Snippets are not encrypted and keep their rows
+    sqlx::query("DELETE FROM clipboard_fts WHERE item_id NOT IN (SELECT id FROM snippets)")
+        .execute(&mut *tx)
+        .await?;
+
+    if reindex {
+        // Multi-file items index every file name, as in insert_item
+        sqlx::query(
+            "INSERT INTO clipboard_fts (item_id, plain_text, file_name, source_app_name)
+             SELECT ci.id, ci.plain_text,
+                    COALESCE(
+                        (SELECT group_concat(name, char(10)) FROM
+                            (SELECT name FROM item_files WHERE item_id = ci.id ORDER BY position)),
+                        ci.file_name, ''),
+                    ci.source_app_name
+             FROM clipboard_items ci WHERE ci.sensitive = 0",
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    for (key, value) in settings {
+        sqlx::query("INSERT INTO settings (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value = excluded.value")
+            .bind(key)
+            .bind(value)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Rebuild the database file and empty the write-ahead log, so pages freed by
+/// rewriting content (the old plain text) no longer remain on disk.
+pub async fn compact_database(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query("VACUUM").execute(pool).await?;
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// ---- Backups ----
+
+/// Write a consistent, compacted copy of the database to a new file at `path`.
+pub async fn vacuum_into(pool: &SqlitePool, path: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("VACUUM INTO ?")
+        .bind(path)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Delete every item due to expire (concealed or sensitive copies, even if
+/// favorited) from a backup copy of the database, then compact it so their
+/// content doesn't linger in free pages. Returns their image paths.
+pub async fn strip_expiring_items(pool: &SqlitePool) -> Result<Vec<String>, sqlx::Error> {
+    let images: Vec<(String,)> = sqlx::query_as(
+        "SELECT image_path FROM clipboard_items
+         WHERE expires_at IS NOT NULL AND image_path IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut tx = pool.begin().await?;
+    for table in ["clipboard_fts", "item_groups", "item_files"] {
+        sqlx::query(&format!(
+            "DELETE FROM {} WHERE item_id IN (
+                SELECT id FROM clipboard_items WHERE expires_at IS NOT NULL
+            )",
+            table
+        ))
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query("DELETE FROM clipboard_items WHERE expires_at IS NOT NULL")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    sqlx::query("VACUUM").execute(pool).await?;
+    Ok(images.into_iter().map(|(p,)| p).collect())
+}
+
+// ---- Export / import ----
+
+/// Get the live items matching `filter` for export, oldest first, with their
+/// content opened and the local path of each original image. Items due to
+/// expire (concealed or sensitive copies) are left out.
+pub async fn get_export_items(
+    pool: &SqlitePool,
+    filter: &ExportFilter,
+) -> Result<Vec<(ArchiveItem, Option<String>)>, sqlx::Error> {
+    let mut sql = String::from(
+        "SELECT id, content_type, plain_text, rich_content, thumbnail, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, sensitive, file_count, image_width, image_height, image_format, image_phash, created_at, updated_at
+         FROM clipboard_items WHERE deleted_at IS NULL AND expires_at IS NULL",
+    );
+    if filter.favorites_only {
+        sql.push_str(" AND is_favorited = 1");
+    }
+    if !filter.content_types.is_empty() {
+        let placeholders = vec!["?"; filter.content_types.len()].join(", ");
+        sql.push_str(&format!(" AND content_type IN ({})", placeholders));
+    }
+    if filter.since.is_some() {
+        sql.push_str(" AND updated_at >= ?");
+    }
+    if filter.until.is_some() {
+        sql.push_str(" AND updated_at <= ?");
+    }
+    sql.push_str(" ORDER BY updated_at ASC, id ASC");
+
+    let mut q = sqlx::query(&sql);
+    for ct in &filter.content_types {
+        q = q.bind(ct);
+    }
+    if let Some(since) = &filter.since {
+        q = q.bind(since);
+    }
+    if let Some(until) = &filter.until {
+        q = q.bind(until);
+    }
+    let rows = q.fetch_all(pool).await?;
+
+    let mut memberships = get_group_names_by_item(pool).await?;
+    let mut items = Vec::with_capacity(rows.len());
+    for row in rows {
+        let id: String = row.try_get("id")?;
+        let groups = memberships.remove(&id).unwrap_or_default();
+        let item = archive_item_from_row(pool, &row, groups).await?;
+        items.push((item, row.try_get("image_path")?));
+    }
+
+    Ok(items)
+}
+
+/// Names of the groups each item belongs to, keyed by item id.
+async fn get_group_names_by_item(
+    pool: &SqlitePool,
+) -> Result<HashMap<String, Vec<String>>, sqlx::Error> {
+    let mut memberships: HashMap<String, Vec<String>> = HashMap::new();
+    let group_rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT ig.item_id, g.name FROM item_groups ig JOIN groups g ON g.id = ig.group_id
+         ORDER BY g.sort_order ASC, g.created_at ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+    for (item_id, name) in group_rows {
+        memberships.entry(item_id).or_default().push(name);
+    }
+    Ok(memberships)
+}
+
+/// Build a portable item (content opened, files loaded) from a row projecting
+/// the columns selected by `get_export_items`.
+async fn archive_item_from_row(
+    pool: &SqlitePool,
+    row: &SqliteRow,
+    groups: Vec<String>,
+) -> Result<ArchiveItem, sqlx::Error> {
+    let id: String = row.try_get("id")?;
+    let file_count: i64 = row.try_get("file_count")?;
+    let files = if file_count > 0 {
+        get_item_files(pool, &id).await?
+    } else {
+        Vec::new()
+    };
+    let file_name: String = row.try_get("file_name")?;
+    let rich_content: Option<Vec<u8>> = row.try_get("rich_content")?;
+    let thumbnail: Option<Vec<u8>> = row.try_get("thumbnail")?;
+
+    Ok(ArchiveItem {
+        content_type: row.try_get("content_type")?,
+        plain_text: crypto::open_text(row.try_get("plain_text")?),
+        rich_content: rich_content.and_then(crypto::open_bytes),
+        thumbnail: thumbnail.and_then(crypto::open_bytes),
+        image_file: None,
+        file_path: row.try_get("file_path")?,
+        file_name: Some(file_name).filter(|n| !n.is_empty()),
+        files,
+        source_app: row.try_get("source_app")?,
+        source_app_name: row.try_get("source_app_name")?,
+        content_size: row.try_get("content_size")?,
+        content_hash: crypto::open_hash(row.try_get("content_hash")?),
+        is_favorited: row.try_get("is_favorited")?,
+        sensitive: row.try_get("sensitive")?,
+        image_width: row.try_get("image_width")?,
+        image_height: row.try_get("image_height")?,
+        image_format: row.try_get("image_format")?,
+        image_phash: row.try_get("image_phash")?,
+        groups,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+/// Get the content hash of every stored item, trashed ones included.
+pub async fn get_content_hashes(pool: &SqlitePool) -> Result<HashSet<String>, sqlx::Error> {
+    let rows: Vec<(String,)> = sqlx::query_as("SELECT content_hash FROM clipboard_items")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(hash,)| crypto::open_hash(hash))
+        .collect())
+}
+
+/// Merge an imported item by `content_hash` (transactional). New content is
+/// inserted with its original timestamps; an existing item keeps its content
+/// but picks up the favorite flag, a later updated_at and the group memberships,
+/// and comes back out of the trash.
+/// Returns true if the item was inserted, false if it was merged.
+pub async fn import_item(pool: &SqlitePool, import: &ImportItem) -> Result<bool, sqlx::Error> {
+    if crypto::is_locked() {
+        return Err(sqlx::Error::Protocol("History is locked".to_string()));
+    }
+    let mut tx = pool.begin().await?;
+
+    let existing: Option<(String,)> = sqlx::query_as(
+        "SELECT id FROM clipboard_items WHERE content_hash = ?
+         ORDER BY deleted_at IS NOT NULL, updated_at DESC LIMIT 1",
+    )
+    .bind(crypto::seal_hash(&import.item.content_hash))
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let (id, inserted) = match existing {
+        Some((id,)) => {
+            sqlx::query(
+                "UPDATE clipboard_items SET is_favorited = MAX(is_favorited, ?), updated_at = MAX(updated_at, ?),
+                     deleted_at = NULL
+                 WHERE id = ?",
+            )
+            .bind(import.is_favorited)
+            .bind(&import.updated_at)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+            (id, false)
+        }
+        None => {
+            let id = insert_item_in(&mut tx, &import.item).await?;
+            sqlx::query(
+                "UPDATE clipboard_items SET is_favorited = ?, created_at = ?, updated_at = ? WHERE id = ?",
+            )
+            .bind(import.is_favorited)
+            .bind(&import.created_at)
+            .bind(&import.updated_at)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+            (id, true)
+        }
+    };
+
+    for group_id in &import.group_ids {
+        sqlx::query("INSERT OR IGNORE INTO item_groups (item_id, group_id) VALUES (?, ?)")
+            .bind(&id)
+            .bind(group_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(inserted)
+}
+
+// ---- Sync ----
+
+/// Get up to `limit` items whose sync_seq is past `since`, in sync_seq order,
+/// with content opened and the local path of each original image.
+/// Items due to expire are never synced.
+pub async fn get_sync_changes(
+    pool: &SqlitePool,
+    since: i64,
+    limit: i64,
+) -> Result<Vec<(i64, SyncItem, Option<String>)>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, content_type, plain_text, rich_content, thumbnail, image_path, file_path, file_name, source_app, source_app_name, content_size, content_hash, is_favorited, sensitive, file_count, image_width, image_height, image_format, image_phash, created_at, updated_at, deleted_at, changed_at, sync_seq
+         FROM clipboard_items WHERE sync_seq > ? AND expires_at IS NULL
+         ORDER BY sync_seq ASC LIMIT ?",
+    )
+    .bind(since)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut memberships = get_group_names_by_item(pool).await?;
+    let mut changes = Vec::with_capacity(rows.len());
+    for row in rows {
+        let id: String = row.try_get("id")?;
+        let groups = memberships.remove(&id).unwrap_or_default();
+        let changed_at: Option<String> = row.try_get("changed_at")?;
+        let item = SyncItem {
+            item: archive_item_from_row(pool, &row, groups).await?,
+            deleted_at: row.try_get("deleted_at")?,
+            changed_at: changed_at.unwrap_or_default(),
+            image: None,
+        };
+        changes.push((row.try_get("sync_seq")?, item, row.try_get("image_path")?));
+    }
+
+    Ok(changes)
+}
+
+/// Get the sync state of the item stored under `hash` (a live one preferred).
+pub async fn get_item_sync_state(
+    pool: &SqlitePool,
+    hash: &str,
+) -> Result<Option<ItemSyncState>, sqlx::Error> {
+    let row: Option<(String, String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT id, updated_at, deleted_at, changed_at FROM clipboard_items WHERE content_hash = ?
+         ORDER BY deleted_at IS NOT NULL, updated_at DESC LIMIT 1",
+    )
+    .bind(crypto::seal_hash(hash))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| ItemSyncState {
+        id: r.0,
+        updated_at: r.1,
+        deleted_at: r.2,
+        changed_at: r.3.unwrap_or_default(),
+    }))
+}
+
+/// Insert an item received from a peer (transactional), keeping the peer's
+/// timestamps and changed_at so the change is not sent back. Returns the new id.
+pub async fn insert_synced_item(
+    pool: &SqlitePool,
+    import: &ImportItem,
+    changed_at: &str,
+) -> Result<String, sqlx::Error> {
+    if crypto::is_locked() {
+        return Err(sqlx::Error::Protocol("History is locked".to_string()));
+    }
+    let mut tx = pool.begin().await?;
+
+    let id = insert_item_in(&mut tx, &import.item).await?;
+    set_item_groups(&mut tx, &id, &import.group_ids).await?;
+    // Last, so the triggers' own changed_at is overwritten
+    bump_sync_counter(&mut tx).await?;
+    sqlx::query(
+        "UPDATE clipboard_items SET is_favorited = ?, created_at = ?, updated_at = ?, changed_at = ?,
+             sync_seq = (SELECT seq FROM sync_counter)
+         WHERE id = ?",
+    )
+    .bind(import.is_favorited)
+    .bind(&import.created_at)
+    .bind(&import.updated_at)
+    .bind(changed_at)
+    .bind(&id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+/// Apply a peer's newer state to an existing item (transactional): favorite
+/// flag, trash state, group memberships and a later updated_at.
+pub async fn update_synced_item(
+    pool: &SqlitePool,
+    id: &str,
+    state: &SyncedState,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    set_item_groups(&mut tx, id, &state.group_ids).await?;
+    bump_sync_counter(&mut tx).await?;
+    sqlx::query(
+        "UPDATE clipboard_items SET is_favorited = ?, updated_at = MAX(updated_at, ?), deleted_at = ?,
+             changed_at = ?, sync_seq = (SELECT seq FROM sync_counter)
+         WHERE id = ?",
+    )
+    .bind(state.is_favorited)
+    .bind(&state.updated_at)
+    .bind(&state.deleted_at)
+    .bind(&state.changed_at)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Take the next sync_seq; the statement that follows reads it from `sync_counter`.
+async fn bump_sync_counter(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE sync_counter SET seq = seq + 1")
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Make `group_ids` the exact group memberships of an item.
+async fn set_item_groups(
+    conn: &mut SqliteConnection,
+    id: &str,
+    group_ids: &[String],
+) -> Result<(), sqlx::Error> {
+    let current: Vec<(String,)> =
+        sqlx::query_as("SELECT group_id FROM item_groups WHERE item_id = ?")
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?;
+    let current: HashSet<String> = current.into_iter().map(|(g,)| g).collect();
+
+    for group_id in current.iter().filter(|g| !group_ids.contains(g)) {
+        sqlx::query("DELETE FROM item_groups WHERE item_id = ? AND group_id = ?")
+            .bind(id)
+            .bind(group_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    for group_id in group_ids.iter().filter(|g| !current.contains(*g)) {
+        sqlx::query("INSERT OR IGNORE INTO item_groups (item_id, group_id) VALUES (?, ?)")
+            .bind(id)
+            .bind(group_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Get the machines this one has synced with, most recently synced first.
+pub async fn get_sync_peers(pool: &SqlitePool) -> Result<Vec<SyncPeer>, sqlx::Error> {
+    let rows: Vec<(String, String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT device_id, name, address, last_sync_at FROM sync_peers
+         ORDER BY last_sync_at IS NULL, last_sync_at DESC, name ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| SyncPeer {
+            device_id: r.0,
+            name: r.1,
+            address: r.2,
+            last_sync_at: r.3,
+            online: false,
+        })
+        .collect())
+}
+
+/// Record a peer that completed a handshake. Its name is refreshed, and its
+/// address when known (connections it opened itself come from a random port).
+pub async fn upsert_sync_peer(
+    pool: &SqlitePool,
+    device_id: &str,
+    name: &str,
+    address: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO sync_peers (device_id, name, address) VALUES (?, ?, ?)
+         ON CONFLICT(device_id) DO UPDATE SET
+             name = excluded.name, address = COALESCE(excluded.address, sync_peers.address)",
+    )
+    .bind(device_id)
+    .bind(name)
+    .bind(address)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// The peer's sync_seq up to which its changes have been pulled (0 if never).
+pub async fn get_sync_cursor(pool: &SqlitePool, device_id: &str) -> Result<i64, sqlx::Error> {
+    let row: Option<(i64,)> = sqlx::query_as("SELECT last_seq FROM sync_peers WHERE device_id = ?")
+        .bind(device_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|(seq,)| seq).unwrap_or(0))
+}
+
+/// Advance a peer's pull cursor and stamp last_sync_at.
+pub async fn set_sync_cursor(
+    pool: &SqlitePool,
+    device_id: &str,
+    last_seq: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE sync_peers SET last_seq = ?, last_sync_at = datetime('now') WHERE device_id = ?",
+    )
+    .bind(last_seq)
+    .bind(device_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Forget a peer. Returns false if it was not known.
+pub async fn remove_sync_peer(pool: &SqlitePool, device_id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM sync_peers WHERE device_id = ?")
+        .bind(device_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Forget all peers (after pairing with a new secret or unpairing).
+pub async fn clear_sync_peers(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM sync_peers").execute(pool).await?;
+    Ok(())
+}
+
+/// Names of the shared-folder entries already ingested or written here.
+pub async fn get_folder_entries(pool: &SqlitePool) -> Result<HashSet<String>, sqlx::Error> {
+    let rows: Vec<(String,)> = sqlx::query_as("SELECT name FROM sync_folder_entries")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|(name,)| name).collect())
+}
+
+/// Record a shared-folder entry as handled.
+pub async fn mark_folder_entry(pool: &SqlitePool, name: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT OR IGNORE INTO sync_folder_entries (name) VALUES (?)")
+        .bind(name)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Start folder sync over: forget handled entries and write every item again.
+pub async fn reset_folder_sync(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM sync_folder_entries")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE settings SET value = '0' WHERE key = 'sync_folder_seq'")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Set an item's favorite flag.
+pub async fn set_item_favorited(
+    pool: &SqlitePool,
+    id: &str,
+    favorited: bool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE clipboard_items SET is_favorited = ? WHERE id = ?")
+        .bind(favorited)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// ---- Snippets ----
+
+const SNIPPET_COLUMNS: &str = "id, name, content, created_at, updated_at";
+
+type SnippetRow = (String, String, String, String, String);
+
+fn snippet_from_row(r: SnippetRow) -> Snippet {
+    Snippet {
+        id: r.0,
+        name: r.1,
+        content: r.2,
+        created_at: r.3,
+        updated_at: r.4,
+    }
+}
+
+/// Get all snippets, most recently edited first.
+pub async fn get_snippets(pool: &SqlitePool) -> Result<Vec<Snippet>, sqlx::Error> {
+    let rows: Vec<SnippetRow> = sqlx::query_as(&format!(
+        "SELECT {} FROM snippets ORDER BY updated_at DESC, name ASC",
+        SNIPPET_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(snippet_from_row).collect())
+}
+
+pub async fn get_snippet(pool: &SqlitePool, id: &str) -> Result<Option<Snippet>, sqlx::Error> {
+    let row: Option<SnippetRow> = sqlx::query_as(&format!(
+        "SELECT {} FROM snippets WHERE id = ?",
+        SNIPPET_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(snippet_from_row))
+}
+
+/// Create a snippet and index it for search. Returns the new id.
+pub async fn create_snippet(
+    pool: &SqlitePool,
+    name: &str,
+    content: &str,
+) -> Result<String, sqlx::Error> {
+    let id = Uuid::new_v4().to_string();
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("INSERT INTO snippets (id, name, content) VALUES (?, ?, ?)")
+        .bind(&id)
+        .bind(name)
+        .bind(content)
+        .execute(&mut *tx)
+        .await?;
+    index_snippet(&mut tx, &id, name, content).await?;
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// Replace a snippet's name and content. Returns false if it does not exist.
+pub async fn update_snippet(
+    pool: &SqlitePool,
+    id: &str,
+    name: &str,
+    content: &str,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "UPDATE snippets SET name = ?, content = ?, updated_at = datetime('now') WHERE id = ?",
+    )
+    .bind(name)
+    .bind(content)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    sqlx::query("DELETE FROM clipboard_fts WHERE item_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    index_snippet(&mut tx, id, name, content).await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Delete a snippet and its search index row (transactional).
+pub async fn delete_snippet(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM clipboard_fts WHERE item_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM snippets WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Index a snippet in clipboard_fts: content as plain_text, name as file_name.
+async fn index_snippet(
+    conn: &mut SqliteConnection,
+    id: &str,
+    name: &str,
+    content: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO clipboard_fts (item_id, plain_text, file_name, source_app_name) VALUES (?, ?, ?, '')",
+    )
+    .bind(id)
+    .bind(content)
+    .bind(name)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Search snippet names and content, like `search_items`: FTS5 trigram for a
+/// single token of 3+ characters, LIKE with every token required otherwise.
+pub async fn search_snippets(
+    pool: &SqlitePool,
+    query: &str,
+    limit: i64,
+) -> Result<Vec<Snippet>, sqlx::Error> {
+    let tokens: Vec<&str> = query.split_whitespace().collect();
+    if tokens.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let rows: Vec<SnippetRow> = if tokens.len() == 1 && query.trim().chars().count() >= 3 {
+        let fts_query = format!("\"{}\"", query.trim().replace('"', "\"\""));
+        sqlx::query_as(&format!(
+            "SELECT {} FROM snippets WHERE id IN (
+                SELECT item_id FROM clipboard_fts WHERE clipboard_fts MATCH ?)
+             ORDER BY updated_at DESC, name ASC LIMIT ?",
+            SNIPPET_COLUMNS
+        ))
+        .bind(&fts_query)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?
+    } else {
+        let conditions = vec!["(name LIKE ? OR content LIKE ?)"; tokens.len()];
+        let sql = format!(
+            "SELECT {} FROM snippets WHERE {} ORDER BY updated_at DESC, name ASC LIMIT ?",
+            SNIPPET_COLUMNS,
+            conditions.join(" AND ")
+        );
+        let mut q = sqlx::query_as(&sql);
+        for token in &tokens {
+            let pattern = format!("%{}%", token);
+            q = q.bind(pattern.clone()).bind(pattern);
+        }
+        q.bind(limit).fetch_all(pool).await?
+    };
+    Ok(rows.into_iter().map(snippet_from_row).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{models::ContentType, test_pool};
+
+    #[tokio::test]
+    async fn test_insert_and_get() {
+        let pool = test_pool().await;
+
+        let item = NewClipboardItem {
+            content_type: ContentType::PlainText,
+            plain_text: "Hello from test".into(),
+            rich_content: None,
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "com.test.app".into(),
+            source_app_name: "TestApp".into(),
+            content_size: 15,
+            content_hash: "hash-test-1".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+
+        let id = insert_item(&pool, &item).await.unwrap();
+        assert!(!id.is_empty());
+
+        let items = get_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].plain_text, "Hello from test");
+        assert_eq!(items[0].source_app_name, "TestApp");
+    }
+
+    #[tokio::test]
+    async fn test_dedup_by_hash() {
+        let pool = test_pool().await;
+
+        let item = NewClipboardItem {
+            content_type: ContentType::PlainText,
+            plain_text: "Duplicate content".into(),
+            rich_content: None,
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "".into(),
+            source_app_name: "".into(),
+            content_size: 17,
+            content_hash: "same-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+
+        let id1 = insert_item(&pool, &item).await.unwrap();
+
+        // Second insert should be caught by dedup
+        let existing = find_and_bump_by_hash(&pool, "same-hash", None, 0)
+            .await
+            .unwrap();
+        assert_eq!(existing, Some(id1));
+    }
+
+    #[tokio::test]
+    async fn test_dedup_bumps_updated_at() {
+        let pool = test_pool().await;
+
+        let item = NewClipboardItem {
+            content_type: ContentType::PlainText,
+            plain_text: "Bump test".into(),
+            rich_content: None,
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "".into(),
+            source_app_name: "".into(),
+            content_size: 9,
+            content_hash: "bump-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+
+        let id = insert_item(&pool, &item).await.unwrap();
+
+        // Force updated_at to a known old date so we can detect the bump
+        sqlx::query("UPDATE clipboard_items SET updated_at = '2000-01-01 00:00:00' WHERE id = ?")
+            .bind(&id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let original_updated_at: (String,) =
+            sqlx::query_as("SELECT updated_at FROM clipboard_items WHERE id = ?")
+                .bind(&id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(original_updated_at.0, "2000-01-01 00:00:00");
+
+        // Bump should update updated_at to current time
+        let bumped = find_and_bump_by_hash(&pool, "bump-hash", None, 0)
+            .await
+            .unwrap();
+        assert_eq!(bumped, Some(id.clone()));
+
+        let new_updated_at: (String,) =
+            sqlx::query_as("SELECT updated_at FROM clipboard_items WHERE id = ?")
+                .bind(&id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert!(
+            new_updated_at.0.as_str() > "2000-01-01 00:00:00",
+            "updated_at should have been bumped from the old date, got: {}",
+            new_updated_at.0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_item() {
+        let pool = test_pool().await;
+
+        let item = NewClipboardItem {
+            content_type: ContentType::PlainText,
+            plain_text: "To be deleted".into(),
+            rich_content: None,
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "".into(),
+            source_app_name: "".into(),
+            content_size: 13,
+            content_hash: "delete-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+
+        let id = insert_item(&pool, &item).await.unwrap();
+        delete_item(&pool, &id).await.unwrap();
+
+        let items = get_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(items.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_search_fts5() {
+        let pool = test_pool().await;
+
+        for (i, text) in [
+            "Hello World example",
+            "支持中文搜索功能测试",
+            "Rust programming",
+        ]
+        .iter()
+        .enumerate()
+        {
+            let item = NewClipboardItem {
+                content_type: ContentType::PlainText,
+                plain_text: text.to_string(),
+                rich_content: None,
+                thumbnail: None,
+                image_path: None,
+                file_path: None,
+                file_name: None,
+                source_app: "".into(),
+                source_app_name: "".into(),
+                content_size: text.len() as i64,
+                content_hash: format!("hash-{}", i),
+                sensitive: false,
+                files: vec![],
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                expires_at: None,
+            };
+            insert_item(&pool, &item).await.unwrap();
+        }
+
+        // FTS search (>= 3 chars)
+        let results = search_items(&pool, "World", None, 10, false).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].plain_text.contains("World"));
+
+        // Chinese search (>= 3 chars for trigram)
+        let results = search_items(&pool, "中文搜", None, 10, false)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].plain_text.contains("中文"));
+
+        // Short query fallback to LIKE
+        let results = search_items(&pool, "Ru", None, 10, false).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].plain_text.contains("Rust"));
+
+        // Type filter
+        let results = search_items(&pool, "World", Some("image"), 10, false)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_items_type_filter() {
+        let pool = test_pool().await;
+
+        let text_item = NewClipboardItem {
+            content_type: ContentType::PlainText,
+            plain_text: "text content".into(),
+            rich_content: None,
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "".into(),
+            source_app_name: "".into(),
+            content_size: 12,
+            content_hash: "text-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+
+        let image_item = NewClipboardItem {
+            content_type: ContentType::Image,
+            plain_text: "".into(),
+            rich_content: None,
+            thumbnail: Some(vec![1, 2, 3]),
+            image_path: Some("/tmp/test.png".into()),
+            file_path: None,
+            file_name: None,
+            source_app: "".into(),
+            source_app_name: "".into(),
+            content_size: 1024,
+            content_hash: "image-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+
+        insert_item(&pool, &text_item).await.unwrap();
+        insert_item(&pool, &image_item).await.unwrap();
+
+        let all = get_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(all.len(), 2);
+
+        let text_only = get_items(&pool, Some("plain_text"), 10, 0).await.unwrap();
+        assert_eq!(text_only.len(), 1);
+        assert_eq!(text_only[0].content_type, "plain_text");
+
+        let image_only = get_items(&pool, Some("image"), 10, 0).await.unwrap();
+        assert_eq!(image_only.len(), 1);
+        assert_eq!(image_only[0].content_type, "image");
+    }
+
+    #[tokio::test]
+    async fn test_favorites() {
+        let pool = test_pool().await;
+
+        let item = NewClipboardItem {
+            content_type: ContentType::PlainText,
+            plain_text: "Favorite me".into(),
+            rich_content: None,
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "".into(),
+            source_app_name: "".into(),
+            content_size: 11,
+            content_hash: "fav-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+        let id = insert_item(&pool, &item).await.unwrap();
+
+        // Not favorited initially
+        let favs = get_favorited_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(favs.len(), 0);
+
+        // Favorite it
+        sqlx::query("UPDATE clipboard_items SET is_favorited = 1 WHERE id = ?")
+            .bind(&id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let favs = get_favorited_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(favs.len(), 1);
+        assert_eq!(favs[0].id, id);
+    }
+
+    #[tokio::test]
+    async fn test_settings_crud() {
+        let pool = test_pool().await;
+
+        // Default settings should exist
+        let shortcut = get_setting(&pool, "shortcut").await.unwrap();
+        assert_eq!(shortcut, Some("CommandOrControl+Shift+V".to_string()));
+
+        let theme = get_setting(&pool, "theme").await.unwrap();
+        assert_eq!(theme, Some("system".to_string()));
+
+        // Update setting
+        set_setting(&pool, "theme", "light").await.unwrap();
+        let theme = get_setting(&pool, "theme").await.unwrap();
+        assert_eq!(theme, Some("light".to_string()));
+
+        // Get all settings
+        let all = get_all_settings(&pool).await.unwrap();
+        assert!(all.len() >= 9); // 9 default settings
+
+        // Non-existent key
+        let none = get_setting(&pool, "nonexistent").await.unwrap();
+        assert_eq!(none, None);
+
+        // Insert new key
+        set_setting(&pool, "custom_key", "custom_value")
+            .await
+            .unwrap();
+        let val = get_setting(&pool, "custom_key").await.unwrap();
+        assert_eq!(val, Some("custom_value".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_clear_history_preserves_favorites() {
+        let pool = test_pool().await;
+
+        // Insert 3 items
+        for i in 0..3 {
+            let item = NewClipboardItem {
+                content_type: ContentType::PlainText,
+                plain_text: format!("Item {}", i),
+                rich_content: None,
+                thumbnail: None,
+                image_path: None,
+                file_path: None,
+                file_name: None,
+                source_app: "".into(),
+                source_app_name: "".into(),
+                content_size: 6,
+                content_hash: format!("clear-hash-{}", i),
+                sensitive: false,
+                files: vec![],
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                expires_at: None,
+            };
+            insert_item(&pool, &item).await.unwrap();
+        }
+
+        // Favorite the first item
+        let items = get_items(&pool, None, 10, 0).await.unwrap();
+        sqlx::query("UPDATE clipboard_items SET is_favorited = 1 WHERE id = ?")
+            .bind(&items[0].id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Clear history
+        let deleted = clear_history(&pool).await.unwrap();
+        assert_eq!(deleted, 2); // 2 non-favorited items deleted
+
+        // Only favorite remains
+        let remaining = get_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert!(remaining[0].is_favorited);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_by_retention_count() {
+        let pool = test_pool().await;
+
+        // Insert 5 items with sequential timestamps
+        for i in 0..5 {
+            let item = NewClipboardItem {
+                content_type: ContentType::PlainText,
+                plain_text: format!("Retention item {}", i),
+                rich_content: None,
+                thumbnail: None,
+                image_path: None,
+                file_path: None,
+                file_name: None,
+                source_app: "".into(),
+                source_app_name: "".into(),
+                content_size: 16,
+                content_hash: format!("ret-hash-{}", i),
+                sensitive: false,
+                files: vec![],
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                expires_at: None,
+            };
+            insert_item(&pool, &item).await.unwrap();
+        }
+
+        // Keep only 2 most recent
+        let deleted = cleanup_by_retention(&pool, "count", 0, 2).await.unwrap();
+        assert_eq!(deleted, 3);
+
+        let remaining = get_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(remaining.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_unlimited_noop() {
+        let pool = test_pool().await;
+
+        let item = NewClipboardItem {
+            content_type: ContentType::PlainText,
+            plain_text: "Keep me".into(),
+            rich_content: None,
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "".into(),
+            source_app_name: "".into(),
+            content_size: 7,
+            content_hash: "noop-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+        insert_item(&pool, &item).await.unwrap();
+
+        let deleted = cleanup_by_retention(&pool, "unlimited", 0, 0)
+            .await
+            .unwrap();
+        assert_eq!(deleted, 0);
+
+        let remaining = get_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_by_retention_days() {
+        let pool = test_pool().await;
+
+        // Insert 3 items
+        for i in 0..3 {
+            let item = NewClipboardItem {
+                content_type: ContentType::PlainText,
+                plain_text: format!("Old item {}", i),
+                rich_content: None,
+                thumbnail: None,
+                image_path: None,
+                file_path: None,
+                file_name: None,
+                source_app: "".into(),
+                source_app_name: "".into(),
+                content_size: 10,
+                content_hash: format!("days-hash-{}", i),
+                sensitive: false,
+                files: vec![],
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                expires_at: None,
+            };
+            insert_item(&pool, &item).await.unwrap();
+        }
+
+        // Backdate created_at of all items to 60 days ago
+        sqlx::query("UPDATE clipboard_items SET created_at = datetime('now', '-60 days')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Insert 1 recent item (created_at defaults to now)
+        let recent = NewClipboardItem {
+            content_type: ContentType::PlainText,
+            plain_text: "Recent item".into(),
+            rich_content: None,
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "".into(),
+            source_app_name: "".into(),
+            content_size: 11,
+            content_hash: "days-hash-recent".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+        insert_item(&pool, &recent).await.unwrap();
+
+        // Retain items from the last 30 days — the 3 old items should be deleted
+        let deleted = cleanup_by_retention(&pool, "days", 30, 0).await.unwrap();
+        assert_eq!(deleted, 3);
+
+        let remaining = get_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].plain_text, "Recent item");
+    }
+
+    #[tokio::test]
+    async fn test_search_empty_query() {
+        let pool = test_pool().await;
+
+        // Insert an item so the DB is not empty
+        let item = NewClipboardItem {
+            content_type: ContentType::PlainText,
+            plain_text: "Some content".into(),
+            rich_content: None,
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "".into(),
+            source_app_name: "".into(),
+            content_size: 12,
+            content_hash: "empty-q-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+        insert_item(&pool, &item).await.unwrap();
+
+        // Empty string query should return empty results (search_items_like returns vec![] for empty tokens)
+        let results = search_items(&pool, "", None, 10, false).await.unwrap();
+        assert!(results.is_empty());
 
-            Ok(result.rows_affected() as i64)
-        }
-        "count" if count > 0 => {
-            let mut tx = pool.begin().await?;
+        // Whitespace-only query should also return empty results
+        let results = search_items(&pool, "   ", None, 10, false).await.unwrap();
+        assert!(results.is_empty());
+    }
 
-            sqlx::query(
-                "DELETE FROM clipboard_fts WHERE item_id IN (
-                    SELECT id FROM clipboard_items
-                    WHERE is_favorited = 0
-                    ORDER BY updated_at DESC, id DESC
-                    LIMIT -1 OFFSET ?
-                )",
-            )
-            .bind(count)
-            .execute(&mut *tx)
-            .await?;
+    #[tokio::test]
+    async fn test_search_special_chars() {
+        let pool = test_pool().await;
 
-            sqlx::query(
-                "DELETE FROM item_groups WHERE item_id IN (
-                    SELECT id FROM clipboard_items
-                    WHERE is_favorited = 0
-                    ORDER BY updated_at DESC, id DESC
-                    LIMIT -1 OFFSET ?
-                )",
-            )
-            .bind(count)
-            .execute(&mut *tx)
-            .await?;
+        let item = NewClipboardItem {
+            content_type: ContentType::PlainText,
+            plain_text: "Normal text content".into(),
+            rich_content: None,
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "".into(),
+            source_app_name: "".into(),
+            content_size: 19,
+            content_hash: "special-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+        insert_item(&pool, &item).await.unwrap();
 
-            let result = sqlx::query(
-                "DELETE FROM clipboard_items WHERE is_favorited = 0 AND id NOT IN (
-                    SELECT id FROM clipboard_items
-                    WHERE is_favorited = 0
-                    ORDER BY updated_at DESC, id DESC
-                    LIMIT ?
-                )",
-            )
-            .bind(count)
-            .execute(&mut *tx)
-            .await?;
+        // FTS5 special characters should not cause a crash
+        // Quotes are escaped by the search_items function (double-quoting)
+        let results = search_items(&pool, "\"quoted\"", None, 10, false).await;
+        assert!(results.is_ok());
 
-            tx.commit().await?;
+        // Asterisks (FTS5 prefix operator) should not crash
+        let results = search_items(&pool, "text*", None, 10, false).await;
+        assert!(results.is_ok());
 
-            Ok(result.rows_affected() as i64)
-        }
-        _ => Ok(0), // "unlimited" or invalid - no cleanup
-    }
-}
+        // Parentheses and boolean operators
+        let results = search_items(&pool, "OR AND NOT", None, 10, false).await;
+        assert!(results.is_ok());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::db::{models::ContentType, test_pool};
+        // Braces and brackets
+        let results = search_items(&pool, "{test}[0]", None, 10, false).await;
+        assert!(results.is_ok());
+    }
 
     #[tokio::test]
-    async fn test_insert_and_get() {
+    async fn test_search_single_char() {
         let pool = test_pool().await;
 
         let item = NewClipboardItem {
             content_type: ContentType::PlainText,
-            plain_text: "Hello from test".into(),
+            plain_text: "X marks the spot".into(),
             rich_content: None,
             thumbnail: None,
             image_path: None,
             file_path: None,
             file_name: None,
-            source_app: "com.test.app".into(),
-            source_app_name: "TestApp".into(),
-            content_size: 15,
-            content_hash: "hash-test-1".into(),
+            source_app: "".into(),
+            source_app_name: "".into(),
+            content_size: 16,
+            content_hash: "single-char-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+        insert_item(&pool, &item).await.unwrap();
+
+        // Single character (< 3 chars) should fall back to LIKE search
+        let results = search_items(&pool, "X", None, 10, false).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].plain_text.contains("X"));
+
+        // Two characters should also use LIKE fallback
+        let results = search_items(&pool, "ma", None, 10, false).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].plain_text.contains("marks"));
+
+        // Single char with no match
+        let results = search_items(&pool, "Z", None, 10, false).await.unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_item_detail_with_rich_content() {
+        let pool = test_pool().await;
+
+        let rich_html = "<p>Hello <strong>World</strong></p>";
+        let item = NewClipboardItem {
+            content_type: ContentType::RichText,
+            plain_text: "Hello World".into(),
+            rich_content: Some(rich_html.as_bytes().to_vec()),
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "com.test.editor".into(),
+            source_app_name: "TestEditor".into(),
+            content_size: rich_html.len() as i64,
+            content_hash: "rich-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
         };
 
         let id = insert_item(&pool, &item).await.unwrap();
-        assert!(!id.is_empty());
 
-        let items = get_items(&pool, None, 10, 0).await.unwrap();
+        let detail = get_item_detail(&pool, &id).await.unwrap();
+        assert!(detail.is_some());
+
+        let (ct, pt, rc, _ip, _fp, _fn, cs) = detail.unwrap();
+        assert_eq!(ct, "rich_text");
+        assert_eq!(pt, "Hello World");
+        assert_eq!(rc, Some(rich_html.to_string()));
+        assert_eq!(cs, rich_html.len() as i64);
+
+        // Test with UTF-8 rich content containing CJK characters
+        let cjk_html = "<p>你好世界 こんにちは 🌍</p>";
+        let cjk_item = NewClipboardItem {
+            content_type: ContentType::RichText,
+            plain_text: "你好世界".into(),
+            rich_content: Some(cjk_html.as_bytes().to_vec()),
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "".into(),
+            source_app_name: "".into(),
+            content_size: cjk_html.len() as i64,
+            content_hash: "cjk-rich-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+
+        let cjk_id = insert_item(&pool, &cjk_item).await.unwrap();
+        let cjk_detail = get_item_detail(&pool, &cjk_id).await.unwrap();
+        assert!(cjk_detail.is_some());
+
+        let (_, _, cjk_rc, _, _, _, _) = cjk_detail.unwrap();
+        assert_eq!(cjk_rc, Some(cjk_html.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_group_crud_and_reorder() {
+        let pool = test_pool().await;
+
+        let a = create_group(&pool, "SQL snippets").await.unwrap();
+        let b = create_group(&pool, "Deploy commands").await.unwrap();
+
+        let groups = get_groups(&pool).await.unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].id, a);
+        assert_eq!(groups[0].sort_order, 0);
+        assert_eq!(groups[1].id, b);
+        assert_eq!(groups[1].sort_order, 1);
+
+        // Rename
+        assert!(rename_group(&pool, &a, "SQL").await.unwrap());
+        assert!(!rename_group(&pool, "missing", "x").await.unwrap());
+        let groups = get_groups(&pool).await.unwrap();
+        assert_eq!(groups[0].name, "SQL");
+
+        // Reorder
+        reorder_groups(&pool, &[b.clone(), a.clone()])
+            .await
+            .unwrap();
+        let groups = get_groups(&pool).await.unwrap();
+        assert_eq!(groups[0].id, b);
+        assert_eq!(groups[1].id, a);
+
+        // Delete
+        delete_group(&pool, &b).await.unwrap();
+        let groups = get_groups(&pool).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].id, a);
+    }
+
+    #[tokio::test]
+    async fn test_group_membership() {
+        let pool = test_pool().await;
+
+        let group_id = create_group(&pool, "Snippets").await.unwrap();
+
+        let mut ids = Vec::new();
+        for (i, ct) in [ContentType::PlainText, ContentType::Link]
+            .into_iter()
+            .enumerate()
+        {
+            let item = NewClipboardItem {
+                content_type: ct,
+                plain_text: format!("Group item {}", i),
+                rich_content: None,
+                thumbnail: None,
+                image_path: None,
+                file_path: None,
+                file_name: None,
+                source_app: "".into(),
+                source_app_name: "".into(),
+                content_size: 12,
+                content_hash: format!("group-hash-{}", i),
+                sensitive: false,
+                files: vec![],
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                expires_at: None,
+            };
+            ids.push(insert_item(&pool, &item).await.unwrap());
+        }
+
+        add_item_to_group(&pool, &ids[0], &group_id).await.unwrap();
+        add_item_to_group(&pool, &ids[1], &group_id).await.unwrap();
+        // Adding twice is a no-op
+        add_item_to_group(&pool, &ids[0], &group_id).await.unwrap();
+
+        let groups = get_groups(&pool).await.unwrap();
+        assert_eq!(groups[0].item_count, 2);
+
+        let items = get_group_items(&pool, &group_id, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 2);
+
+        let links = get_group_items(&pool, &group_id, Some("link"), 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].id, ids[1]);
+
+        assert_eq!(
+            get_item_group_ids(&pool, &ids[0]).await.unwrap(),
+            vec![group_id.clone()]
+        );
+
+        remove_item_from_group(&pool, &ids[0], &group_id)
+            .await
+            .unwrap();
+        let items = get_group_items(&pool, &group_id, None, 10, 0)
+            .await
+            .unwrap();
         assert_eq!(items.len(), 1);
-        assert_eq!(items[0].plain_text, "Hello from test");
-        assert_eq!(items[0].source_app_name, "TestApp");
+
+        // Deleting an item drops its membership
+        delete_item(&pool, &ids[1]).await.unwrap();
+        let groups = get_groups(&pool).await.unwrap();
+        assert_eq!(groups[0].item_count, 0);
+
+        // Deleting a group keeps its items
+        add_item_to_group(&pool, &ids[0], &group_id).await.unwrap();
+        delete_group(&pool, &group_id).await.unwrap();
+        let remaining = get_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ignored_apps_crud() {
+        let pool = test_pool().await;
+
+        let id1 = add_ignored_app(&pool, "bundle_id", "com.1password.1password")
+            .await
+            .unwrap();
+        let id2 = add_ignored_app(&pool, "window_title", "*Private Browsing*")
+            .await
+            .unwrap();
+        assert_ne!(id1, id2);
+
+        // Re-adding an identical rule returns the existing id
+        let dup = add_ignored_app(&pool, "bundle_id", "com.1password.1password")
+            .await
+            .unwrap();
+        assert_eq!(dup, id1);
+
+        let rules = get_ignored_apps(&pool).await.unwrap();
+        assert_eq!(rules.len(), 2);
+        assert!(rules
+            .iter()
+            .any(|r| r.match_type == "window_title" && r.pattern == "*Private Browsing*"));
+
+        // Invalid match types are rejected by the CHECK constraint
+        assert!(add_ignored_app(&pool, "process", "x").await.is_err());
+
+        remove_ignored_app(&pool, &id1).await.unwrap();
+        let rules = get_ignored_apps(&pool).await.unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].id, id2);
+    }
+
+    #[tokio::test]
+    async fn test_purge_expired_items() {
+        let pool = test_pool().await;
+
+        let mut ids = Vec::new();
+        for (i, expires_at) in [
+            None,
+            Some("2000-01-01 00:00:00"),
+            Some("2000-01-01 00:00:00"),
+            Some("2999-01-01 00:00:00"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let item = NewClipboardItem {
+                content_type: ContentType::PlainText,
+                plain_text: format!("secret {}", i),
+                rich_content: None,
+                thumbnail: None,
+                image_path: None,
+                file_path: None,
+                file_name: None,
+                source_app: "com.test".into(),
+                source_app_name: "Test".into(),
+                content_size: 8,
+                content_hash: format!("expire-hash-{}", i),
+                sensitive: false,
+                files: vec![],
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                expires_at: expires_at.map(String::from),
+            };
+            ids.push(insert_item(&pool, &item).await.unwrap());
+        }
+
+        // A favorited item survives its expiry
+        sqlx::query("UPDATE clipboard_items SET is_favorited = 1 WHERE id = ?")
+            .bind(&ids[2])
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let deleted = purge_expired_items(&pool).await.unwrap();
+        assert_eq!(deleted, 1);
+        assert!(get_item_by_id(&pool, &ids[1]).await.unwrap().is_none());
+        assert!(get_item_by_id(&pool, &ids[0]).await.unwrap().is_some());
+        assert!(get_item_by_id(&pool, &ids[2]).await.unwrap().is_some());
+        assert!(get_item_by_id(&pool, &ids[3]).await.unwrap().is_some());
+
+        // Purged items are gone from search as well
+        let results = search_items(&pool, "secret", None, 10, false)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_strip_expiring_items() {
+        let pool = test_pool().await;
+
+        let mut ids = Vec::new();
+        for (i, (expires_at, image_path)) in [
+            (None, Some("/data/images/kept.png")),
+            (
+                Some("2999-01-01 00:00:00"),
+                Some("/data/images/concealed.png"),
+            ),
+            (Some("2999-01-01 00:00:00"), None),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let item = NewClipboardItem {
+                content_type: ContentType::PlainText,
+                plain_text: format!("secret {}", i),
+                rich_content: None,
+                thumbnail: None,
+                image_path: image_path.map(String::from),
+                file_path: None,
+                file_name: None,
+                source_app: "com.test".into(),
+                source_app_name: "Test".into(),
+                content_size: 8,
+                content_hash: format!("strip-hash-{}", i),
+                sensitive: false,
+                files: vec![],
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                expires_at: expires_at.map(String::from),
+            };
+            ids.push(insert_item(&pool, &item).await.unwrap());
+        }
+
+        // Favoriting doesn't keep an expiring item in a backup
+        sqlx::query("UPDATE clipboard_items SET is_favorited = 1 WHERE id = ?")
+            .bind(&ids[2])
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let images = strip_expiring_items(&pool).await.unwrap();
+        assert_eq!(images, vec!["/data/images/concealed.png".to_string()]);
+        assert!(get_item_by_id(&pool, &ids[0]).await.unwrap().is_some());
+        assert!(get_item_by_id(&pool, &ids[1]).await.unwrap().is_none());
+        assert!(get_item_by_id(&pool, &ids[2]).await.unwrap().is_none());
+
+        let results = search_items(&pool, "secret", None, 10, false)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
     }
 
     #[tokio::test]
-    async fn test_dedup_by_hash() {
+    async fn test_sensitive_item_masked_and_unsearchable() {
         let pool = test_pool().await;
 
         let item = NewClipboardItem {
             content_type: ContentType::PlainText,
-            plain_text: "Duplicate content".into(),
+            plain_text: "hT7#qL2$vZ9!mK4xWq".into(),
             rich_content: None,
             thumbnail: None,
             image_path: None,
             file_path: None,
             file_name: None,
-            source_app: "".into(),
-            source_app_name: "".into(),
-            content_size: 17,
-            content_hash: "same-hash".into(),
+            source_app: "com.test".into(),
+            source_app_name: "Vault".into(),
+            content_size: 18,
+            content_hash: "sensitive-hash".into(),
+            sensitive: true,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
         };
+        let id = insert_item(&pool, &item).await.unwrap();
 
-        let id1 = insert_item(&pool, &item).await.unwrap();
+        // Masked in lists
+        let items = get_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert!(items[0].sensitive);
+        assert_eq!(items[0].plain_text, "••••••••");
 
-        // Second insert should be caught by dedup
-        let existing = find_and_bump_by_hash(&pool, "same-hash").await.unwrap();
-        assert_eq!(existing, Some(id1));
+        // Not found by text via FTS or LIKE, still found by source app
+        assert!(search_items(&pool, "vZ9!mK", None, 10, false)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(search_items(&pool, "hT", None, 10, false)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            search_items(&pool, "Va", None, 10, false)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Paste path still reads the original text
+        let row = get_item_by_id(&pool, &id).await.unwrap().unwrap();
+        assert_eq!(row.1, "hT7#qL2$vZ9!mK4xWq");
     }
 
     #[tokio::test]
-    async fn test_dedup_bumps_updated_at() {
+    async fn test_multi_file_item() {
         let pool = test_pool().await;
 
+        let files = vec![
+            ItemFile {
+                path: "/tmp/docs/report.pdf".into(),
+                name: "report.pdf".into(),
+                size: 1000,
+                is_dir: false,
+            },
+            ItemFile {
+                path: "/tmp/docs/assets".into(),
+                name: "assets".into(),
+                size: 500,
+                is_dir: true,
+            },
+        ];
         let item = NewClipboardItem {
-            content_type: ContentType::PlainText,
-            plain_text: "Bump test".into(),
+            content_type: ContentType::File,
+            plain_text: "/tmp/docs/report.pdf\n/tmp/docs/assets".into(),
             rich_content: None,
             thumbnail: None,
             image_path: None,
-            file_path: None,
-            file_name: None,
-            source_app: "".into(),
-            source_app_name: "".into(),
-            content_size: 9,
-            content_hash: "bump-hash".into(),
+            file_path: Some("/tmp/docs/report.pdf".into()),
+            file_name: Some("report.pdf".into()),
+            source_app: "com.apple.finder".into(),
+            source_app_name: "Finder".into(),
+            content_size: 1500,
+            content_hash: "multi-file-hash".into(),
+            sensitive: false,
+            files: files.clone(),
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
         };
-
         let id = insert_item(&pool, &item).await.unwrap();
 
-        // Force updated_at to a known old date so we can detect the bump
-        sqlx::query("UPDATE clipboard_items SET updated_at = '2000-01-01 00:00:00' WHERE id = ?")
-            .bind(&id)
-            .execute(&pool)
+        let items = get_items(&pool, Some("file"), 10, 0).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].file_count, 2);
+        assert_eq!(items[0].content_size, 1500);
+        assert_eq!(get_item_files(&pool, &id).await.unwrap(), files);
+
+        // Every file name is searchable, not only the first
+        let results = search_items(&pool, "assets", None, 10, false)
             .await
             .unwrap();
+        assert_eq!(results.len(), 1);
 
-        let original_updated_at: (String,) =
-            sqlx::query_as("SELECT updated_at FROM clipboard_items WHERE id = ?")
-                .bind(&id)
-                .fetch_one(&pool)
-                .await
-                .unwrap();
-        assert_eq!(original_updated_at.0, "2000-01-01 00:00:00");
-
-        // Bump should update updated_at to current time
-        let bumped = find_and_bump_by_hash(&pool, "bump-hash").await.unwrap();
-        assert_eq!(bumped, Some(id.clone()));
-
-        let new_updated_at: (String,) =
-            sqlx::query_as("SELECT updated_at FROM clipboard_items WHERE id = ?")
-                .bind(&id)
-                .fetch_one(&pool)
-                .await
-                .unwrap();
-        assert!(
-            new_updated_at.0.as_str() > "2000-01-01 00:00:00",
-            "updated_at should have been bumped from the old date, got: {}",
-            new_updated_at.0
-        );
+        delete_item(&pool, &id).await.unwrap();
+        assert!(get_item_files(&pool, &id).await.unwrap().is_empty());
     }
 
     #[tokio::test]
-    async fn test_delete_item() {
+    async fn test_image_metadata_roundtrip() {
         let pool = test_pool().await;
 
         let item = NewClipboardItem {
-            content_type: ContentType::PlainText,
-            plain_text: "To be deleted".into(),
+            content_type: ContentType::Image,
+            plain_text: String::new(),
             rich_content: None,
             thumbnail: None,
-            image_path: None,
+            image_path: Some("/tmp/images/2026-01/shot.jpg".into()),
             file_path: None,
             file_name: None,
-            source_app: "".into(),
-            source_app_name: "".into(),
-            content_size: 13,
-            content_hash: "delete-hash".into(),
+            source_app: "com.apple.Preview".into(),
+            source_app_name: "Preview".into(),
+            content_size: 2048,
+            content_hash: "jpeg-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: Some(1920),
+            image_height: Some(1080),
+            image_format: Some("jpeg".into()),
+            image_phash: None,
+            expires_at: None,
         };
-
         let id = insert_item(&pool, &item).await.unwrap();
-        delete_item(&pool, &id).await.unwrap();
-
-        let items = get_items(&pool, None, 10, 0).await.unwrap();
-        assert_eq!(items.len(), 0);
-    }
-
-    #[tokio::test]
-    async fn test_search_fts5() {
-        let pool = test_pool().await;
-
-        for (i, text) in [
-            "Hello World example",
-            "支持中文搜索功能测试",
-            "Rust programming",
-        ]
-        .iter()
-        .enumerate()
-        {
-            let item = NewClipboardItem {
-                content_type: ContentType::PlainText,
-                plain_text: text.to_string(),
-                rich_content: None,
-                thumbnail: None,
-                image_path: None,
-                file_path: None,
-                file_name: None,
-                source_app: "".into(),
-                source_app_name: "".into(),
-                content_size: text.len() as i64,
-                content_hash: format!("hash-{}", i),
-            };
-            insert_item(&pool, &item).await.unwrap();
-        }
-
-        // FTS search (>= 3 chars)
-        let results = search_items(&pool, "World", None, 10, false).await.unwrap();
-        assert_eq!(results.len(), 1);
-        assert!(results[0].plain_text.contains("World"));
-
-        // Chinese search (>= 3 chars for trigram)
-        let results = search_items(&pool, "中文搜", None, 10, false)
-            .await
-            .unwrap();
-        assert_eq!(results.len(), 1);
-        assert!(results[0].plain_text.contains("中文"));
-
-        // Short query fallback to LIKE
-        let results = search_items(&pool, "Ru", None, 10, false).await.unwrap();
-        assert_eq!(results.len(), 1);
-        assert!(results[0].plain_text.contains("Rust"));
 
-        // Type filter
-        let results = search_items(&pool, "World", Some("image"), 10, false)
+        let items = get_items(&pool, Some("image"), 10, 0).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].image_width, Some(1920));
+        assert_eq!(items[0].image_height, Some(1080));
+        assert_eq!(items[0].image_format.as_deref(), Some("jpeg"));
+
+        // Table-qualified projection (group listing) maps the same columns
+        let group_id = create_group(&pool, "Screenshots").await.unwrap();
+        add_item_to_group(&pool, &id, &group_id).await.unwrap();
+        let items = get_group_items(&pool, &group_id, None, 10, 0)
             .await
             .unwrap();
-        assert_eq!(results.len(), 0);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].image_format.as_deref(), Some("jpeg"));
     }
 
     #[tokio::test]
-    async fn test_get_items_type_filter() {
+    async fn test_dedup_near_duplicate_image() {
         let pool = test_pool().await;
 
-        let text_item = NewClipboardItem {
-            content_type: ContentType::PlainText,
-            plain_text: "text content".into(),
+        let phash: i64 = 0x0F0F_0F0F_0F0F_0F0F;
+        let item = NewClipboardItem {
+            content_type: ContentType::Image,
+            plain_text: String::new(),
             rich_content: None,
             thumbnail: None,
-            image_path: None,
+            image_path: Some("/tmp/images/2026-01/shot.png".into()),
             file_path: None,
             file_name: None,
             source_app: "".into(),
             source_app_name: "".into(),
-            content_size: 12,
-            content_hash: "text-hash".into(),
+            content_size: 4096,
+            content_hash: "png-bytes-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: Some(64),
+            image_height: Some(48),
+            image_format: Some("png".into()),
+            image_phash: Some(phash),
+            expires_at: None,
         };
+        let id = insert_item(&pool, &item).await.unwrap();
 
-        let image_item = NewClipboardItem {
-            content_type: ContentType::Image,
-            plain_text: "".into(),
-            rich_content: None,
-            thumbnail: Some(vec![1, 2, 3]),
-            image_path: Some("/tmp/test.png".into()),
-            file_path: None,
-            file_name: None,
-            source_app: "".into(),
-            source_app_name: "".into(),
-            content_size: 1024,
-            content_hash: "image-hash".into(),
+        // Same picture re-encoded: different bytes, perceptual hash 2 bits away
+        let near = ImageFingerprint {
+            phash: phash ^ 0b11,
+            width: 64,
+            height: 48,
         };
+        let found = find_and_bump_by_hash(&pool, "jpeg-bytes-hash", Some(near), 4)
+            .await
+            .unwrap();
+        assert_eq!(found, Some(id));
 
-        insert_item(&pool, &text_item).await.unwrap();
-        insert_item(&pool, &image_item).await.unwrap();
-
-        let all = get_items(&pool, None, 10, 0).await.unwrap();
-        assert_eq!(all.len(), 2);
-
-        let text_only = get_items(&pool, Some("plain_text"), 10, 0).await.unwrap();
-        assert_eq!(text_only.len(), 1);
-        assert_eq!(text_only[0].content_type, "plain_text");
-
-        let image_only = get_items(&pool, Some("image"), 10, 0).await.unwrap();
-        assert_eq!(image_only.len(), 1);
-        assert_eq!(image_only[0].content_type, "image");
+        // Outside the allowed distance, a different size, or no perceptual hash at all
+        assert_eq!(
+            find_and_bump_by_hash(&pool, "jpeg-bytes-hash", Some(near), 1)
+                .await
+                .unwrap(),
+            None
+        );
+        let resized = ImageFingerprint { width: 32, ..near };
+        assert_eq!(
+            find_and_bump_by_hash(&pool, "jpeg-bytes-hash", Some(resized), 4)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            find_and_bump_by_hash(&pool, "jpeg-bytes-hash", None, 4)
+                .await
+                .unwrap(),
+            None
+        );
     }
 
     #[tokio::test]
-    async fn test_favorites() {
+    async fn test_trash_and_restore() {
         let pool = test_pool().await;
 
         let item = NewClipboardItem {
             content_type: ContentType::PlainText,
-            plain_text: "Favorite me".into(),
+            plain_text: "Undo this delete".into(),
             rich_content: None,
             thumbnail: None,
             image_path: None,
@@ -972,283 +3186,556 @@ mod tests {
             file_name: None,
             source_app: "".into(),
             source_app_name: "".into(),
-            content_size: 11,
-            content_hash: "fav-hash".into(),
+            content_size: 16,
+            content_hash: "trash-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
         };
         let id = insert_item(&pool, &item).await.unwrap();
+        let group_id = create_group(&pool, "Work").await.unwrap();
+        add_item_to_group(&pool, &id, &group_id).await.unwrap();
 
-        // Not favorited initially
-        let favs = get_favorited_items(&pool, None, 10, 0).await.unwrap();
-        assert_eq!(favs.len(), 0);
+        assert!(trash_item(&pool, &id).await.unwrap());
+        assert!(!trash_item(&pool, &id).await.unwrap());
 
-        // Favorite it
-        sqlx::query("UPDATE clipboard_items SET is_favorited = 1 WHERE id = ?")
-            .bind(&id)
-            .execute(&pool)
+        // Hidden from history, search and groups; listed in the trash
+        assert!(get_items(&pool, None, 10, 0).await.unwrap().is_empty());
+        assert!(search_items(&pool, "Undo", None, 10, false)
             .await
-            .unwrap();
+            .unwrap()
+            .is_empty());
+        assert_eq!(get_groups(&pool).await.unwrap()[0].item_count, 0);
+        let trashed = get_trashed_items(&pool, 10, 0).await.unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert!(trashed[0].deleted_at.is_some());
+
+        // Restoring brings back search and group membership
+        assert!(restore_item(&pool, &id).await.unwrap());
+        assert!(!restore_item(&pool, &id).await.unwrap());
+        assert_eq!(
+            search_items(&pool, "Undo", None, 10, false)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(get_groups(&pool).await.unwrap()[0].item_count, 1);
 
-        let favs = get_favorited_items(&pool, None, 10, 0).await.unwrap();
-        assert_eq!(favs.len(), 1);
-        assert_eq!(favs[0].id, id);
+        // Copying a trashed item again restores it instead of inserting a duplicate
+        trash_item(&pool, &id).await.unwrap();
+        let found = find_and_bump_by_hash(&pool, "trash-hash", None, 0)
+            .await
+            .unwrap();
+        assert_eq!(found, Some(id));
+        assert_eq!(get_items(&pool, None, 10, 0).await.unwrap().len(), 1);
     }
 
     #[tokio::test]
-    async fn test_settings_crud() {
+    async fn test_empty_trash() {
         let pool = test_pool().await;
 
-        // Default settings should exist
-        let shortcut = get_setting(&pool, "shortcut").await.unwrap();
-        assert_eq!(shortcut, Some("CommandOrControl+Shift+V".to_string()));
+        let mut ids = Vec::new();
+        for i in 0..3 {
+            let item = NewClipboardItem {
+                content_type: ContentType::Image,
+                plain_text: String::new(),
+                rich_content: None,
+                thumbnail: None,
+                image_path: Some(format!("/tmp/images/2026-01/trash-{}.png", i)),
+                file_path: None,
+                file_name: None,
+                source_app: "".into(),
+                source_app_name: "".into(),
+                content_size: 100,
+                content_hash: format!("empty-trash-hash-{}", i),
+                sensitive: false,
+                files: vec![],
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                expires_at: None,
+            };
+            ids.push(insert_item(&pool, &item).await.unwrap());
+        }
+        trash_item(&pool, &ids[0]).await.unwrap();
+        trash_item(&pool, &ids[1]).await.unwrap();
 
-        let theme = get_setting(&pool, "theme").await.unwrap();
-        assert_eq!(theme, Some("system".to_string()));
+        // Only the first item has been in the trash long enough
+        sqlx::query(
+            "UPDATE clipboard_items SET deleted_at = datetime('now', '-40 days') WHERE id = ?",
+        )
+        .bind(&ids[0])
+        .execute(&pool)
+        .await
+        .unwrap();
+        let old_paths = get_trashed_image_paths(&pool, Some(30)).await.unwrap();
+        assert_eq!(
+            old_paths,
+            vec!["/tmp/images/2026-01/trash-0.png".to_string()]
+        );
+        assert_eq!(empty_trash(&pool, Some(30)).await.unwrap(), 1);
+        assert_eq!(get_trashed_items(&pool, 10, 0).await.unwrap().len(), 1);
 
-        // Update setting
-        set_setting(&pool, "theme", "light").await.unwrap();
-        let theme = get_setting(&pool, "theme").await.unwrap();
-        assert_eq!(theme, Some("light".to_string()));
+        // Emptying everything leaves live items alone
+        assert_eq!(get_trashed_image_paths(&pool, None).await.unwrap().len(), 1);
+        assert_eq!(empty_trash(&pool, None).await.unwrap(), 1);
+        assert!(get_trashed_items(&pool, 10, 0).await.unwrap().is_empty());
+        let remaining = get_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, ids[2]);
+    }
 
-        // Get all settings
-        let all = get_all_settings(&pool).await.unwrap();
-        assert!(all.len() >= 9); // 9 default settings
+    #[tokio::test]
+    async fn test_vacuum_into() {
+        // VACUUM INTO needs a file-backed source (the app database always is)
+        let dir = std::env::temp_dir().join(format!("recopy-vacuum-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("source.db").to_string_lossy().to_string();
+        let pool = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", source))
+            .await
+            .unwrap();
+        super::super::MIGRATOR.run(&pool).await.unwrap();
+        set_setting(&pool, "theme", "dark").await.unwrap();
 
-        // Non-existent key
-        let none = get_setting(&pool, "nonexistent").await.unwrap();
-        assert_eq!(none, None);
+        let path = dir.join("copy.db").to_string_lossy().to_string();
+        vacuum_into(&pool, &path).await.unwrap();
+        pool.close().await;
 
-        // Insert new key
-        set_setting(&pool, "custom_key", "custom_value")
+        let copy = SqlitePool::connect(&format!("sqlite://{}", path))
             .await
             .unwrap();
-        let val = get_setting(&pool, "custom_key").await.unwrap();
-        assert_eq!(val, Some("custom_value".to_string()));
+        assert_eq!(
+            get_setting(&copy, "theme").await.unwrap(),
+            Some("dark".into())
+        );
+        copy.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
     #[tokio::test]
-    async fn test_clear_history_preserves_favorites() {
+    async fn test_get_export_items_filters() {
         let pool = test_pool().await;
 
-        // Insert 3 items
-        for i in 0..3 {
+        let kinds = [
+            (ContentType::PlainText, "export-1"),
+            (ContentType::Link, "export-2"),
+            (ContentType::PlainText, "export-3"),
+        ];
+        let mut ids = Vec::new();
+        for (content_type, hash) in kinds {
             let item = NewClipboardItem {
-                content_type: ContentType::PlainText,
-                plain_text: format!("Item {}", i),
-                rich_content: None,
+                content_type,
+                plain_text: hash.to_string(),
+                rich_content: Some(b"<p>rich</p>".to_vec()),
                 thumbnail: None,
                 image_path: None,
                 file_path: None,
                 file_name: None,
                 source_app: "".into(),
-                source_app_name: "".into(),
-                content_size: 6,
-                content_hash: format!("clear-hash-{}", i),
+                source_app_name: "Test".into(),
+                content_size: 8,
+                content_hash: hash.into(),
+                sensitive: false,
+                files: vec![],
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                expires_at: None,
             };
-            insert_item(&pool, &item).await.unwrap();
+            ids.push(insert_item(&pool, &item).await.unwrap());
+        }
+        for (id, ts) in ids.iter().zip([
+            "2026-01-01 00:00:00",
+            "2026-02-01 00:00:00",
+            "2026-03-01 00:00:00",
+        ]) {
+            sqlx::query("UPDATE clipboard_items SET updated_at = ? WHERE id = ?")
+                .bind(ts)
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
         }
-
-        // Favorite the first item
-        let items = get_items(&pool, None, 10, 0).await.unwrap();
         sqlx::query("UPDATE clipboard_items SET is_favorited = 1 WHERE id = ?")
-            .bind(&items[0].id)
+            .bind(&ids[2])
             .execute(&pool)
             .await
             .unwrap();
+        let group_id = create_group(&pool, "Work").await.unwrap();
+        add_item_to_group(&pool, &ids[0], &group_id).await.unwrap();
 
-        // Clear history
-        let deleted = clear_history(&pool).await.unwrap();
-        assert_eq!(deleted, 2); // 2 non-favorited items deleted
+        // Everything, oldest first, with content and group names
+        let all = get_export_items(&pool, &ExportFilter::default())
+            .await
+            .unwrap();
+        let hashes: Vec<&str> = all.iter().map(|(i, _)| i.content_hash.as_str()).collect();
+        assert_eq!(hashes, ["export-1", "export-2", "export-3"]);
+        assert_eq!(all[0].0.groups, vec!["Work".to_string()]);
+        assert_eq!(all[0].0.rich_content.as_deref(), Some(&b"<p>rich</p>"[..]));
+
+        let filter = ExportFilter {
+            content_types: vec!["plain_text".into()],
+            ..Default::default()
+        };
+        assert_eq!(get_export_items(&pool, &filter).await.unwrap().len(), 2);
 
-        // Only favorite remains
-        let remaining = get_items(&pool, None, 10, 0).await.unwrap();
-        assert_eq!(remaining.len(), 1);
-        assert!(remaining[0].is_favorited);
+        let filter = ExportFilter {
+            favorites_only: true,
+            ..Default::default()
+        };
+        let favorites = get_export_items(&pool, &filter).await.unwrap();
+        assert_eq!(favorites.len(), 1);
+        assert!(favorites[0].0.is_favorited);
+
+        let filter = ExportFilter {
+            since: Some("2026-01-15 00:00:00".into()),
+            until: Some("2026-02-15 00:00:00".into()),
+            ..Default::default()
+        };
+        let ranged = get_export_items(&pool, &filter).await.unwrap();
+        assert_eq!(ranged.len(), 1);
+        assert_eq!(ranged[0].0.content_hash, "export-2");
+
+        // Trashed items are not exported
+        trash_item(&pool, &ids[1]).await.unwrap();
+        assert_eq!(
+            get_export_items(&pool, &ExportFilter::default())
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
     }
 
     #[tokio::test]
-    async fn test_cleanup_by_retention_count() {
+    async fn test_import_item_merges_by_hash() {
         let pool = test_pool().await;
+        let group_id = create_group(&pool, "Imported").await.unwrap();
 
-        // Insert 5 items with sequential timestamps
-        for i in 0..5 {
-            let item = NewClipboardItem {
+        let import = |hash: &str, favorited: bool| ImportItem {
+            item: NewClipboardItem {
                 content_type: ContentType::PlainText,
-                plain_text: format!("Retention item {}", i),
+                plain_text: "imported text".into(),
                 rich_content: None,
                 thumbnail: None,
                 image_path: None,
                 file_path: None,
                 file_name: None,
                 source_app: "".into(),
-                source_app_name: "".into(),
-                content_size: 16,
-                content_hash: format!("ret-hash-{}", i),
-            };
-            insert_item(&pool, &item).await.unwrap();
-        }
+                source_app_name: "Other Mac".into(),
+                content_size: 13,
+                content_hash: hash.into(),
+                sensitive: false,
+                files: vec![],
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                expires_at: None,
+            },
+            is_favorited: favorited,
+            created_at: "2025-06-01 08:00:00".into(),
+            updated_at: "2025-06-02 08:00:00".into(),
+            group_ids: vec![group_id.clone()],
+        };
 
-        // Keep only 2 most recent
-        let deleted = cleanup_by_retention(&pool, "count", 0, 2).await.unwrap();
-        assert_eq!(deleted, 3);
+        // New content keeps its timestamps, favorite flag and groups, and is searchable
+        assert!(import_item(&pool, &import("import-1", false))
+            .await
+            .unwrap());
+        let items = get_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].created_at, "2025-06-01 08:00:00");
+        assert_eq!(items[0].updated_at, "2025-06-02 08:00:00");
+        assert!(!items[0].is_favorited);
+        assert_eq!(
+            get_group_items(&pool, &group_id, None, 10, 0)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            search_items(&pool, "imported", None, 10, false)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
 
-        let remaining = get_items(&pool, None, 10, 0).await.unwrap();
-        assert_eq!(remaining.len(), 2);
+        // Same hash again: merged, not duplicated; favorite flag is picked up
+        assert!(!import_item(&pool, &import("import-1", true)).await.unwrap());
+        let items = get_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_favorited);
+        assert!(get_content_hashes(&pool)
+            .await
+            .unwrap()
+            .contains("import-1"));
+
+        // Importing content that sits in the trash restores it
+        assert!(trash_item(&pool, &items[0].id).await.unwrap());
+        assert!(!import_item(&pool, &import("import-1", false))
+            .await
+            .unwrap());
+        let items = get_items(&pool, None, 10, 0).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].deleted_at, None);
     }
 
     #[tokio::test]
-    async fn test_cleanup_unlimited_noop() {
+    async fn test_replace_item_payloads() {
         let pool = test_pool().await;
 
         let item = NewClipboardItem {
             content_type: ContentType::PlainText,
-            plain_text: "Keep me".into(),
-            rich_content: None,
+            plain_text: "api token abc123".into(),
+            rich_content: Some(b"<b>api token abc123</b>".to_vec()),
             thumbnail: None,
             image_path: None,
             file_path: None,
             file_name: None,
             source_app: "".into(),
-            source_app_name: "".into(),
-            content_size: 7,
-            content_hash: "noop-hash".into(),
+            source_app_name: "Terminal".into(),
+            content_size: 16,
+            content_hash: "payload-hash".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
         };
-        insert_item(&pool, &item).await.unwrap();
+        let id = insert_item(&pool, &item).await.unwrap();
 
-        let deleted = cleanup_by_retention(&pool, "unlimited", 0, 0)
+        // Seal in place: text no longer searchable, settings written in the same transaction
+        let key = crypto::generate_key();
+        let sealed: Vec<ItemPayload> = get_item_payloads(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|p| ItemPayload {
+                plain_text: crypto::encrypt_text(&key, &p.plain_text),
+                rich_content: p.rich_content.map(|rc| crypto::encrypt(&key, &rc)),
+                content_hash: crypto::encrypt_hash(&key, &p.content_hash),
+                ..p
+            })
+            .collect();
+        replace_item_payloads(&pool, &sealed, false, &[("encryption_mode", "keychain")])
             .await
             .unwrap();
-        assert_eq!(deleted, 0);
-
-        let remaining = get_items(&pool, None, 10, 0).await.unwrap();
-        assert_eq!(remaining.len(), 1);
+        assert!(search_items(&pool, "abc123", None, 10, false)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            get_setting(&pool, "encryption_mode").await.unwrap(),
+            Some("keychain".into())
+        );
+        let stored = get_item_payloads(&pool).await.unwrap();
+        assert!(crypto::is_sealed_text(&stored[0].plain_text));
+        assert!(crypto::is_sealed(
+            stored[0].rich_content.as_deref().unwrap()
+        ));
+        assert!(crypto::is_sealed_hash(&stored[0].content_hash));
+
+        // Open again and rebuild the index
+        let opened: Vec<ItemPayload> = stored
+            .into_iter()
+            .map(|p| ItemPayload {
+                plain_text: crypto::decrypt_text(&key, &p.plain_text).unwrap(),
+                rich_content: p.rich_content.map(|rc| crypto::decrypt(&key, &rc).unwrap()),
+                content_hash: crypto::decrypt_hash(&key, &p.content_hash).unwrap(),
+                ..p
+            })
+            .collect();
+        replace_item_payloads(&pool, &opened, true, &[("encryption_mode", "off")])
+            .await
+            .unwrap();
+        let results = search_items(&pool, "abc123", None, 10, false)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, id);
+        assert_eq!(results[0].plain_text, "api token abc123");
+        assert_eq!(results[0].content_hash, "payload-hash");
     }
 
     #[tokio::test]
-    async fn test_cleanup_by_retention_days() {
+    async fn test_search_items_decrypted() {
         let pool = test_pool().await;
 
-        // Insert 3 items
-        for i in 0..3 {
+        let texts = [
+            ("Quarterly Report draft", "Pages", false),
+            ("report password hunter2", "Notes", true),
+            ("grocery list", "Notes", false),
+        ];
+        for (i, (text, app, sensitive)) in texts.iter().enumerate() {
             let item = NewClipboardItem {
                 content_type: ContentType::PlainText,
-                plain_text: format!("Old item {}", i),
+                plain_text: text.to_string(),
                 rich_content: None,
                 thumbnail: None,
                 image_path: None,
                 file_path: None,
                 file_name: None,
                 source_app: "".into(),
-                source_app_name: "".into(),
-                content_size: 10,
-                content_hash: format!("days-hash-{}", i),
+                source_app_name: app.to_string(),
+                content_size: text.len() as i64,
+                content_hash: format!("decrypted-search-{}", i),
+                sensitive: *sensitive,
+                files: vec![],
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                expires_at: None,
             };
             insert_item(&pool, &item).await.unwrap();
         }
 
-        // Backdate created_at of all items to 60 days ago
-        sqlx::query("UPDATE clipboard_items SET created_at = datetime('now', '-60 days')")
-            .execute(&pool)
+        // Case-insensitive, every token must match; sensitive text never matches
+        let results = search_items_decrypted(&pool, "REPORT draft", None, 10, false)
             .await
             .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].plain_text, "Quarterly Report draft");
+        assert!(search_items_decrypted(&pool, "hunter2", None, 10, false)
+            .await
+            .unwrap()
+            .is_empty());
 
-        // Insert 1 recent item (created_at defaults to now)
-        let recent = NewClipboardItem {
-            content_type: ContentType::PlainText,
-            plain_text: "Recent item".into(),
-            rich_content: None,
-            thumbnail: None,
-            image_path: None,
-            file_path: None,
-            file_name: None,
-            source_app: "".into(),
-            source_app_name: "".into(),
-            content_size: 11,
-            content_hash: "days-hash-recent".into(),
-        };
-        insert_item(&pool, &recent).await.unwrap();
-
-        // Retain items from the last 30 days — the 3 old items should be deleted
-        let deleted = cleanup_by_retention(&pool, "days", 30, 0).await.unwrap();
-        assert_eq!(deleted, 3);
-
-        let remaining = get_items(&pool, None, 10, 0).await.unwrap();
-        assert_eq!(remaining.len(), 1);
-        assert_eq!(remaining[0].plain_text, "Recent item");
+        // Source app names match, including for sensitive items
+        let results = search_items_decrypted(&pool, "notes", None, 10, false)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        let results = search_items_decrypted(&pool, "notes", None, 1, false)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(
+            search_items_decrypted(&pool, "notes", Some("image"), 10, false)
+                .await
+                .unwrap()
+                .is_empty()
+        );
     }
 
     #[tokio::test]
-    async fn test_search_empty_query() {
+    async fn test_sync_changes_follow_local_edits() {
         let pool = test_pool().await;
-
-        // Insert an item so the DB is not empty
         let item = NewClipboardItem {
             content_type: ContentType::PlainText,
-            plain_text: "Some content".into(),
+            plain_text: "synced text".into(),
             rich_content: None,
             thumbnail: None,
             image_path: None,
             file_path: None,
             file_name: None,
             source_app: "".into(),
-            source_app_name: "".into(),
-            content_size: 12,
-            content_hash: "empty-q-hash".into(),
+            source_app_name: "Editor".into(),
+            content_size: 11,
+            content_hash: "sync-1".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
         };
-        insert_item(&pool, &item).await.unwrap();
-
-        // Empty string query should return empty results (search_items_like returns vec![] for empty tokens)
-        let results = search_items(&pool, "", None, 10, false).await.unwrap();
-        assert!(results.is_empty());
+        let id = insert_item(&pool, &item).await.unwrap();
 
-        // Whitespace-only query should also return empty results
-        let results = search_items(&pool, "   ", None, 10, false).await.unwrap();
-        assert!(results.is_empty());
+        let changes = get_sync_changes(&pool, 0, 10).await.unwrap();
+        assert_eq!(changes.len(), 1);
+        let (seq, synced, _) = &changes[0];
+        assert_eq!(synced.item.plain_text, "synced text");
+        assert!(!synced.changed_at.is_empty());
+        assert!(get_sync_changes(&pool, *seq, 10).await.unwrap().is_empty());
+
+        // Grouping and trashing each count as a change
+        let group_id = create_group(&pool, "Work").await.unwrap();
+        add_item_to_group(&pool, &id, &group_id).await.unwrap();
+        assert!(trash_item(&pool, &id).await.unwrap());
+        let changes = get_sync_changes(&pool, *seq, 10).await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].0 > *seq);
+        assert_eq!(changes[0].1.item.groups, vec!["Work".to_string()]);
+        assert!(changes[0].1.deleted_at.is_some());
+
+        // A peer's state keeps the peer's changed_at
+        let state = SyncedState {
+            is_favorited: true,
+            updated_at: "2020-01-01 00:00:00".into(),
+            deleted_at: None,
+            changed_at: "2999-01-01 00:00:00.000".into(),
+            group_ids: vec![],
+        };
+        update_synced_item(&pool, &id, &state).await.unwrap();
+        let local = get_item_sync_state(&pool, "sync-1").await.unwrap().unwrap();
+        assert_eq!(local.changed_at, "2999-01-01 00:00:00.000");
+        assert!(local.deleted_at.is_none());
+        assert_ne!(local.updated_at, "2020-01-01 00:00:00");
+        let changes = get_sync_changes(&pool, changes[0].0, 10).await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].1.item.is_favorited);
+        assert!(changes[0].1.item.groups.is_empty());
     }
 
     #[tokio::test]
-    async fn test_search_special_chars() {
+    async fn test_sync_seq_never_goes_back() {
         let pool = test_pool().await;
-
-        let item = NewClipboardItem {
-            content_type: ContentType::PlainText,
-            plain_text: "Normal text content".into(),
-            rich_content: None,
-            thumbnail: None,
-            image_path: None,
-            file_path: None,
-            file_name: None,
-            source_app: "".into(),
-            source_app_name: "".into(),
-            content_size: 19,
-            content_hash: "special-hash".into(),
-        };
-        insert_item(&pool, &item).await.unwrap();
-
-        // FTS5 special characters should not cause a crash
-        // Quotes are escaped by the search_items function (double-quoting)
-        let results = search_items(&pool, "\"quoted\"", None, 10, false).await;
-        assert!(results.is_ok());
-
-        // Asterisks (FTS5 prefix operator) should not crash
-        let results = search_items(&pool, "text*", None, 10, false).await;
-        assert!(results.is_ok());
-
-        // Parentheses and boolean operators
-        let results = search_items(&pool, "OR AND NOT", None, 10, false).await;
-        assert!(results.is_ok());
-
-        // Braces and brackets
-        let results = search_items(&pool, "{test}[0]", None, 10, false).await;
-        assert!(results.is_ok());
+        let mut ids = Vec::new();
+        for i in 0..2 {
+            let item = NewClipboardItem {
+                content_type: ContentType::PlainText,
+                plain_text: format!("synced {}", i),
+                rich_content: None,
+                thumbnail: None,
+                image_path: None,
+                file_path: None,
+                file_name: None,
+                source_app: "".into(),
+                source_app_name: "Editor".into(),
+                content_size: 8,
+                content_hash: format!("seq-{}", i),
+                sensitive: false,
+                files: vec![],
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                expires_at: None,
+            };
+            ids.push(insert_item(&pool, &item).await.unwrap());
+        }
+        let changes = get_sync_changes(&pool, 0, 10).await.unwrap();
+        let seen = changes.last().unwrap().0;
+
+        // Deleting the newest item for good doesn't free its sync_seq
+        delete_item(&pool, &ids[1]).await.unwrap();
+        assert!(trash_item(&pool, &ids[0]).await.unwrap());
+        let changes = get_sync_changes(&pool, seen, 10).await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].1.deleted_at.is_some());
     }
 
     #[tokio::test]
-    async fn test_search_single_char() {
+    async fn test_snippet_crud_and_search() {
         let pool = test_pool().await;
 
         let item = NewClipboardItem {
             content_type: ContentType::PlainText,
-            plain_text: "X marks the spot".into(),
+            plain_text: "Regards, the team".into(),
             rich_content: None,
             thumbnail: None,
             image_path: None,
@@ -1256,77 +3743,119 @@ mod tests {
             file_name: None,
             source_app: "".into(),
             source_app_name: "".into(),
-            content_size: 16,
-            content_hash: "single-char-hash".into(),
+            content_size: 17,
+            content_hash: "snippet-item".into(),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
         };
-        insert_item(&pool, &item).await.unwrap();
+        let item_id = insert_item(&pool, &item).await.unwrap();
 
-        // Single character (< 3 chars) should fall back to LIKE search
-        let results = search_items(&pool, "X", None, 10, false).await.unwrap();
-        assert_eq!(results.len(), 1);
-        assert!(results[0].plain_text.contains("X"));
+        let sig = create_snippet(&pool, "Signature", "Regards, {{prompt:Name}}")
+            .await
+            .unwrap();
+        let date = create_snippet(&pool, "Today", "{{date}}").await.unwrap();
+        assert_eq!(get_snippets(&pool).await.unwrap().len(), 2);
+
+        // FTS on content and name, LIKE for short and multi-token queries
+        let ids = |snippets: Vec<Snippet>| snippets.into_iter().map(|s| s.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(search_snippets(&pool, "Regards", 10).await.unwrap()),
+            vec![sig.clone()]
+        );
+        assert_eq!(
+            ids(search_snippets(&pool, "today", 10).await.unwrap()),
+            vec![date.clone()]
+        );
+        assert_eq!(
+            ids(search_snippets(&pool, "sig name", 10).await.unwrap()),
+            vec![sig.clone()]
+        );
+        assert!(search_snippets(&pool, "", 10).await.unwrap().is_empty());
 
-        // Two characters should also use LIKE fallback
-        let results = search_items(&pool, "ma", None, 10, false).await.unwrap();
-        assert_eq!(results.len(), 1);
-        assert!(results[0].plain_text.contains("marks"));
+        // Items and snippets share the index but not their results
+        let items = search_items(&pool, "Regards", None, 10, false)
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, item_id);
 
-        // Single char with no match
-        let results = search_items(&pool, "Z", None, 10, false).await.unwrap();
-        assert!(results.is_empty());
+        // Updates are reindexed
+        assert!(update_snippet(&pool, &sig, "Sign-off", "Cheers")
+            .await
+            .unwrap());
+        assert!(!update_snippet(&pool, "missing", "x", "y").await.unwrap());
+        assert!(search_snippets(&pool, "Regards", 10)
+            .await
+            .unwrap()
+            .is_empty());
+        let updated = get_snippet(&pool, &sig).await.unwrap().unwrap();
+        assert_eq!(updated.name, "Sign-off");
+        assert_eq!(
+            ids(search_snippets(&pool, "Cheers", 10).await.unwrap()),
+            vec![sig.clone()]
+        );
+
+        // Rebuilding the items' index for encryption leaves snippets searchable
+        let payloads = get_item_payloads(&pool).await.unwrap();
+        replace_item_payloads(&pool, &payloads, true, &[])
+            .await
+            .unwrap();
+        assert_eq!(search_snippets(&pool, "Cheers", 10).await.unwrap().len(), 1);
+
+        delete_snippet(&pool, &sig).await.unwrap();
+        assert!(get_snippet(&pool, &sig).await.unwrap().is_none());
+        assert!(search_snippets(&pool, "Cheers", 10)
+            .await
+            .unwrap()
+            .is_empty());
     }
 
     #[tokio::test]
-    async fn test_get_item_detail_with_rich_content() {
+    async fn test_get_items_by_ids() {
         let pool = test_pool().await;
 
-        let rich_html = "<p>Hello <strong>World</strong></p>";
-        let item = NewClipboardItem {
-            content_type: ContentType::RichText,
-            plain_text: "Hello World".into(),
-            rich_content: Some(rich_html.as_bytes().to_vec()),
-            thumbnail: None,
-            image_path: None,
-            file_path: None,
-            file_name: None,
-            source_app: "com.test.editor".into(),
-            source_app_name: "TestEditor".into(),
-            content_size: rich_html.len() as i64,
-            content_hash: "rich-hash".into(),
-        };
-
-        let id = insert_item(&pool, &item).await.unwrap();
-
-        let detail = get_item_detail(&pool, &id).await.unwrap();
-        assert!(detail.is_some());
-
-        let (ct, pt, rc, _ip, _fp, _fn, cs) = detail.unwrap();
-        assert_eq!(ct, "rich_text");
-        assert_eq!(pt, "Hello World");
-        assert_eq!(rc, Some(rich_html.to_string()));
-        assert_eq!(cs, rich_html.len() as i64);
-
-        // Test with UTF-8 rich content containing CJK characters
-        let cjk_html = "<p>你好世界 こんにちは 🌍</p>";
-        let cjk_item = NewClipboardItem {
-            content_type: ContentType::RichText,
-            plain_text: "你好世界".into(),
-            rich_content: Some(cjk_html.as_bytes().to_vec()),
-            thumbnail: None,
-            image_path: None,
-            file_path: None,
-            file_name: None,
-            source_app: "".into(),
-            source_app_name: "".into(),
-            content_size: cjk_html.len() as i64,
-            content_hash: "cjk-rich-hash".into(),
-        };
-
-        let cjk_id = insert_item(&pool, &cjk_item).await.unwrap();
-        let cjk_detail = get_item_detail(&pool, &cjk_id).await.unwrap();
-        assert!(cjk_detail.is_some());
-
-        let (_, _, cjk_rc, _, _, _, _) = cjk_detail.unwrap();
-        assert_eq!(cjk_rc, Some(cjk_html.to_string()));
+        let mut ids = Vec::new();
+        for i in 0..3 {
+            let item = NewClipboardItem {
+                content_type: ContentType::PlainText,
+                plain_text: format!("value {}", i),
+                rich_content: None,
+                thumbnail: None,
+                image_path: None,
+                file_path: None,
+                file_name: None,
+                source_app: "".into(),
+                source_app_name: "".into(),
+                content_size: 7,
+                content_hash: format!("by-ids-{}", i),
+                sensitive: false,
+                files: vec![],
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                expires_at: None,
+            };
+            ids.push(insert_item(&pool, &item).await.unwrap());
+        }
+        assert!(trash_item(&pool, &ids[1]).await.unwrap());
+
+        // Requested order, duplicates kept, trashed and unknown ids left out
+        let wanted = vec![
+            ids[2].clone(),
+            ids[1].clone(),
+            "missing".to_string(),
+            ids[0].clone(),
+            ids[2].clone(),
+        ];
+        let items = get_items_by_ids(&pool, &wanted).await.unwrap();
+        let texts: Vec<&str> = items.iter().map(|i| i.plain_text.as_str()).collect();
+        assert_eq!(texts, vec!["value 2", "value 0", "value 2"]);
+        assert!(get_items_by_ids(&pool, &[]).await.unwrap().is_empty());
     }
 }
@@ -0,0 +1,272 @@
+//! `org.recopy.Recopy` on the D-Bus session bus (Linux and other desktops
+//! without a platform module), so desktop shortcuts and other programs can
+//! drive the app:
+//!
+//! ```text
+//! gdbus call --session --dest org.recopy.Recopy \
+//!     --object-path /org/recopy/Recopy --method org.recopy.Recopy.Toggle
+//! ```
+//!
+//! The interface only forwards to a [`Handler`]; `server` implements it on top
+//! of the app, tests on top of a private bus.
+
+pub mod server;
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use zbus::object_server::SignalContext;
+use zbus::zvariant::Type;
+use zbus::Connection;
+
+/// Well-known name, also used as the interface name.
+pub const BUS_NAME: &str = "org.recopy.Recopy";
+pub const OBJECT_PATH: &str = "/org/recopy/Recopy";
+
+/// Most results `Search` returns, whatever the caller asks for.
+const MAX_SEARCH_RESULTS: u32 = 200;
+
+/// One `Search` result, sent as `(ssss)`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct SearchHit {
+    pub id: String,
+    pub content_type: String,
+    /// One line of the item's text, its file name or an image description.
+    pub preview: String,
+    pub updated_at: String,
+}
+
+/// What the service asks of the app.
+pub trait Handler: Send + Sync + 'static {
+    fn show(&self);
+    fn hide(&self);
+    fn toggle(&self);
+    fn search(
+        &self,
+        query: String,
+        limit: u32,
+    ) -> impl Future<Output = Result<Vec<SearchHit>, String>> + Send;
+    fn paste(&self, id: String) -> impl Future<Output = Result<(), String>> + Send;
+}
+
+struct Recopy<H> {
+    handler: H,
+}
+
+#[zbus::interface(name = "org.recopy.Recopy")]
+impl<H: Handler> Recopy<H> {
+    /// Show the panel.
+    fn show(&self) {
+        self.handler.show();
+    }
+
+    /// Hide the panel.
+    fn hide(&self) {
+        self.handler.hide();
+    }
+
+    /// Show the panel if hidden, hide it otherwise.
+    fn toggle(&self) {
+        self.handler.toggle();
+    }
+
+    /// Search the history; a limit of 0 means the default of 50.
+    async fn search(&self, query: String, limit: u32) -> zbus::fdo::Result<Vec<SearchHit>> {
+        let limit = if limit == 0 { 50 } else { limit };
+        self.handler
+            .search(query, limit.min(MAX_SEARCH_RESULTS))
+            .await
+            .map_err(zbus::fdo::Error::Failed)
+    }
+
+    /// Paste an item into the frontmost app.
+    async fn paste(&self, id: String) -> zbus::fdo::Result<()> {
+        self.handler
+            .paste(id)
+            .await
+            .map_err(zbus::fdo::Error::Failed)
+    }
+
+    /// An item was copied or changed; the id is empty after deletes and clears.
+    #[zbus(signal)]
+    async fn clipboard_changed(ctxt: &SignalContext<'_>, id: &str) -> zbus::Result<()>;
+}
+
+/// Claim the name on the bus `builder` connects to and serve `handler`.
+/// The service runs until the returned connection is dropped.
+pub async fn serve<H: Handler>(
+    builder: zbus::connection::Builder<'_>,
+    handler: H,
+) -> zbus::Result<Connection> {
+    builder
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, Recopy { handler })?
+        .build()
+        .await
+}
+
+/// Emit `ClipboardChanged` from the service on `connection`.
+pub async fn clipboard_changed(connection: &Connection, id: &str) -> zbus::Result<()> {
+    connection
+        .emit_signal(None::<()>, OBJECT_PATH, BUS_NAME, "ClipboardChanged", &id)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// A bus daemon of our own, so tests neither need nor touch the user's.
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        /// None when `dbus-daemon` is not installed.
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address=1"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?)
+                .read_line(&mut address)
+                .ok()?;
+            Some(PrivateBus {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+
+        fn builder(&self) -> zbus::connection::Builder<'_> {
+            zbus::connection::Builder::address(self.address.as_str()).unwrap()
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct FakeApp {
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl FakeApp {
+        fn record(&self, call: String) {
+            self.calls.lock().unwrap().push(call);
+        }
+    }
+
+    impl Handler for FakeApp {
+        fn show(&self) {
+            self.record("show".to_string());
+        }
+
+        fn hide(&self) {
+            self.record("hide".to_string());
+        }
+
+        fn toggle(&self) {
+            self.record("toggle".to_string());
+        }
+
+        async fn search(&self, query: String, limit: u32) -> Result<Vec<SearchHit>, String> {
+            self.record(format!("search {} {}", query, limit));
+            Ok(vec![SearchHit {
+                id: "item-1".to_string(),
+                content_type: "plain_text".to_string(),
+                preview: query,
+                updated_at: "2026-01-01 00:00:00".to_string(),
+            }])
+        }
+
+        async fn paste(&self, id: String) -> Result<(), String> {
+            if id == "missing" {
+                return Err("Item not found".to_string());
+            }
+            self.record(format!("paste {}", id));
+            Ok(())
+        }
+    }
+
+    async fn proxy(connection: &Connection) -> zbus::Proxy<'static> {
+        zbus::Proxy::new(connection, BUS_NAME, OBJECT_PATH, BUS_NAME)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_methods_reach_the_handler() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon not installed, skipping");
+            return;
+        };
+        let app = FakeApp::default();
+        let _service = serve(bus.builder(), app.clone()).await.unwrap();
+        let client = bus.builder().build().await.unwrap();
+        let recopy = proxy(&client).await;
+
+        let _: () = recopy.call("Show", &()).await.unwrap();
+        let _: () = recopy.call("Toggle", &()).await.unwrap();
+        let hits: Vec<SearchHit> = recopy.call("Search", &("docker", 0u32)).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].preview, "docker");
+        let _: Vec<SearchHit> = recopy.call("Search", &("x", 10_000u32)).await.unwrap();
+        let _: () = recopy.call("Paste", &("item-1",)).await.unwrap();
+
+        let failed = recopy.call::<_, _, ()>("Paste", &("missing",)).await;
+        match failed {
+            Err(zbus::Error::MethodError(name, message, _)) => {
+                assert_eq!(name.as_str(), "org.freedesktop.DBus.Error.Failed");
+                assert_eq!(message.as_deref(), Some("Item not found"));
+            }
+            other => panic!("expected a method error, got {:?}", other),
+        }
+
+        assert_eq!(
+            *app.calls.lock().unwrap(),
+            vec![
+                "show",
+                "toggle",
+                "search docker 50",
+                "search x 200",
+                "paste item-1"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clipboard_changed_signal() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon not installed, skipping");
+            return;
+        };
+        let service = serve(bus.builder(), FakeApp::default()).await.unwrap();
+        let client = bus.builder().build().await.unwrap();
+        let mut signals = proxy(&client)
+            .await
+            .receive_signal("ClipboardChanged")
+            .await
+            .unwrap();
+
+        clipboard_changed(&service, "item-1").await.unwrap();
+
+        let signal = tokio::time::timeout(Duration::from_secs(5), signals.next())
+            .await
+            .expect("no signal within 5s")
+            .unwrap();
+        let id: String = signal.body().deserialize().unwrap();
+        assert_eq!(id, "item-1");
+    }
+}
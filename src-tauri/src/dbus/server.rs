@@ -0,0 +1,112 @@
+//! The app behind the D-Bus interface: window commands go to the panel,
+//! search and paste through the same commands as the webview, and every
+//! `clipboard-changed` event is re-emitted as `ClipboardChanged`.
+
+use super::{Handler, SearchHit};
+use crate::commands::clipboard as clip_cmd;
+use crate::db::models::ClipboardItem;
+use tauri::{AppHandle, Listener, Manager};
+
+/// Characters of text shown in a search result.
+const PREVIEW_CHARS: usize = 200;
+
+impl Handler for AppHandle {
+    fn show(&self) {
+        on_main_thread(self, crate::show_main_window);
+    }
+
+    fn hide(&self) {
+        on_main_thread(self, crate::hide_main_window);
+    }
+
+    fn toggle(&self) {
+        on_main_thread(self, |app| {
+            if crate::platform::platform_is_visible(app) {
+                crate::hide_main_window(app);
+            } else {
+                crate::show_main_window(app);
+            }
+        });
+    }
+
+    async fn search(&self, query: String, limit: u32) -> Result<Vec<SearchHit>, String> {
+        let items = clip_cmd::search_clipboard_items(
+            self.state(),
+            query,
+            None,
+            Some(i64::from(limit)),
+            None,
+        )
+        .await?;
+        Ok(items.iter().map(search_hit).collect())
+    }
+
+    async fn paste(&self, id: String) -> Result<(), String> {
+        clip_cmd::paste_clipboard_item(self.clone(), self.state(), id, Some(true)).await
+    }
+}
+
+/// Register the service on the session bus. Failures (no session bus, name
+/// taken) are logged; the app runs without it.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let builder = match zbus::connection::Builder::session() {
+            Ok(builder) => builder,
+            Err(e) => {
+                log::warn!("D-Bus service unavailable: {}", e);
+                return;
+            }
+        };
+        let connection = match super::serve(builder, app.clone()).await {
+            Ok(connection) => connection,
+            Err(e) => {
+                log::warn!("D-Bus service unavailable: {}", e);
+                return;
+            }
+        };
+        log::info!("D-Bus service registered as {}", super::BUS_NAME);
+
+        // The listener keeps the connection, and with it the service, alive
+        app.listen("clipboard-changed", move |event: tauri::Event| {
+            let id = serde_json::from_str::<serde_json::Value>(event.payload())
+                .ok()
+                .and_then(|payload| payload["id"].as_str().map(String::from))
+                .unwrap_or_default();
+            let connection = connection.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = super::clipboard_changed(&connection, &id).await {
+                    log::debug!("Failed to emit ClipboardChanged: {}", e);
+                }
+            });
+        });
+    });
+}
+
+/// Run a window operation on the main thread: showing the panel reads
+/// settings with `block_on`, which panics on a runtime thread.
+fn on_main_thread(app: &AppHandle, f: fn(&AppHandle)) {
+    let handle = app.clone();
+    if let Err(e) = app.run_on_main_thread(move || f(&handle)) {
+        log::warn!("D-Bus window request failed: {}", e);
+    }
+}
+
+/// One line describing an item, for search results.
+fn search_hit(item: &ClipboardItem) -> SearchHit {
+    let text = match item.content_type.as_str() {
+        "image" => format!(
+            "[image {}x{}]",
+            item.image_width.unwrap_or(0),
+            item.image_height.unwrap_or(0)
+        ),
+        "file" => item.file_name.clone().unwrap_or_default(),
+        _ => item.plain_text.clone(),
+    };
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    SearchHit {
+        id: item.id.clone(),
+        content_type: item.content_type.clone(),
+        preview: line.chars().take(PREVIEW_CHARS).collect(),
+        updated_at: item.updated_at.clone(),
+    }
+}
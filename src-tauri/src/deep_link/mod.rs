@@ -0,0 +1,119 @@
+//! `recopy://` links, opened from browsers, launchers and scripts:
+//!
+//! - `recopy://show` shows the panel
+//! - `recopy://search?q=foo` shows the panel searching for `foo`
+//! - `recopy://paste?id=…` pastes an item into the frontmost app
+//!
+//! There is no link for adding text: any web page can open one, and it would
+//! put text in the history without the user asking. Scripts can add text with
+//! `recopy-cli add`.
+//!
+//! macOS hands links to the running app; on Windows and Linux they arrive as
+//! arguments of a second launch, which the single-instance plugin forwards.
+
+pub mod open;
+
+use url::Url;
+
+pub const SCHEME: &str = "recopy";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Show,
+    Search(String),
+    Paste(String),
+}
+
+impl Action {
+    /// Parse a `recopy://` link. Errors name what is wrong with it.
+    pub fn parse(link: &str) -> Result<Action, String> {
+        let url = Url::parse(link).map_err(|e| format!("Invalid link {}: {}", link, e))?;
+        if url.scheme() != SCHEME {
+            return Err(format!("Not a {}:// link: {}", SCHEME, link));
+        }
+        // Browsers on Windows may append a slash: recopy://show/
+        if !matches!(url.path(), "" | "/") {
+            return Err(format!("Unexpected path in {}", link));
+        }
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+        let required = |name: &str| {
+            param(name)
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| format!("{} needs a {} parameter", link, name))
+        };
+
+        match url.host_str().unwrap_or_default() {
+            "show" => Ok(Action::Show),
+            "search" => Ok(Action::Search(param("q").unwrap_or_default())),
+            "paste" => Ok(Action::Paste(required("id")?)),
+            other => Err(format!("Unknown action {:?} in {}", other, link)),
+        }
+    }
+}
+
+/// The `recopy://` links among a launch's arguments.
+pub fn links_in_args(args: &[String]) -> Vec<&str> {
+    let prefix = format!("{}:", SCHEME);
+    args.iter()
+        .map(String::as_str)
+        .filter(|arg| {
+            arg.get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(&prefix))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_actions() {
+        assert_eq!(Action::parse("recopy://show"), Ok(Action::Show));
+        assert_eq!(Action::parse("recopy://show/"), Ok(Action::Show));
+        assert_eq!(
+            Action::parse("recopy://search?q=docker%20run"),
+            Ok(Action::Search("docker run".to_string()))
+        );
+        assert_eq!(
+            Action::parse("recopy://search"),
+            Ok(Action::Search(String::new()))
+        );
+        assert_eq!(
+            Action::parse("recopy://paste?id=abc-123"),
+            Ok(Action::Paste("abc-123".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_links() {
+        assert!(Action::parse("https://example.com").is_err());
+        assert!(Action::parse("recopy://delete?id=1").is_err());
+        assert!(Action::parse("recopy://paste").is_err());
+        assert!(Action::parse("recopy://add?text=hello").is_err());
+        assert!(Action::parse("recopy://show/extra").is_err());
+        assert!(Action::parse("not a link").is_err());
+    }
+
+    #[test]
+    fn test_links_in_args() {
+        let args: Vec<String> = [
+            "/usr/bin/recopy",
+            "--flag",
+            "RECOPY://show",
+            "recopy://paste?id=x",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(
+            links_in_args(&args),
+            vec!["RECOPY://show", "recopy://paste?id=x"]
+        );
+        assert!(links_in_args(&["/usr/bin/recopy".to_string()]).is_empty());
+    }
+}
@@ -0,0 +1,56 @@
+//! Carry out `recopy://` links through the same commands as the webview.
+
+use super::Action;
+use crate::commands::clipboard as clip_cmd;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Handle a second launch: open its links, or just show the panel when it
+/// has none (the user started the app again).
+pub fn open_args(app: &AppHandle, args: &[String]) {
+    let links = super::links_in_args(args);
+    if links.is_empty() {
+        crate::show_main_window(app);
+        return;
+    }
+    open_links(app, links);
+}
+
+/// Open each link in order. Bad links are logged and skipped.
+pub fn open_links<'a>(app: &AppHandle, links: impl IntoIterator<Item = &'a str>) {
+    for link in links {
+        match Action::parse(link) {
+            Ok(action) => run(app, action),
+            Err(e) => log::warn!("Ignoring link: {}", e),
+        }
+    }
+}
+
+fn run(app: &AppHandle, action: Action) {
+    match action {
+        Action::Show => on_main_thread(app, crate::show_main_window),
+        Action::Search(query) => {
+            // The panel reloads with this query when it shows
+            let _ = app.emit("recopy-search", serde_json::json!({ "query": query }));
+            on_main_thread(app, crate::show_main_window);
+        }
+        Action::Paste(id) => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let pasted =
+                    clip_cmd::paste_clipboard_item(app.clone(), app.state(), id, Some(true)).await;
+                if let Err(e) = pasted {
+                    log::warn!("recopy://paste failed: {}", e);
+                }
+            });
+        }
+    }
+}
+
+/// Links may arrive on any thread; showing the panel reads settings with
+/// `block_on`, so it runs on the main thread.
+fn on_main_thread(app: &AppHandle, f: fn(&AppHandle)) {
+    let handle = app.clone();
+    if let Err(e) = app.run_on_main_thread(move || f(&handle)) {
+        log::warn!("Failed to open link: {}", e);
+    }
+}
@@ -0,0 +1,222 @@
+//! Opt-in HTTP API on 127.0.0.1, for tools that can't use the local socket
+//! (browser extensions, launcher scripts, dashboards).
+//!
+//! Every request carries the token from settings as `Authorization: Bearer
+//! <token>`; `GET /v1/events` also takes it as `?token=`, since `EventSource`
+//! can't set headers. Routes:
+//!
+//! - `GET  /v1/items?content_type=&limit=&offset=`: recent items
+//! - `GET  /v1/search?q=&content_type=&limit=&favorites=`: search
+//! - `GET  /v1/items/{id}`: an item's full content
+//! - `POST /v1/items` with `{"text": …, "source": …}`: add text
+//! - `POST /v1/items/{id}/paste` with optional `{"plain_text": true}`
+//! - `POST /v1/items/{id}/copy`: put an item on the clipboard
+//! - `GET  /v1/events`: Server-Sent Events, one `clipboard-changed` event per
+//!   change with the same payload the window receives
+
+pub mod server;
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use hyper::StatusCode;
+use std::collections::HashMap;
+
+/// Bytes of randomness in a token (hex-encoded, so twice as many characters).
+const TOKEN_BYTES: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Route {
+    List,
+    Search,
+    Get(String),
+    Add,
+    Paste(String),
+    Copy(String),
+    Events,
+}
+
+impl Route {
+    /// The route a request is for, None if there is no such route.
+    pub fn parse(method: &str, path: &str) -> Option<Route> {
+        let rest = path.strip_prefix("/v1/")?.trim_end_matches('/');
+        let segments: Vec<&str> = rest.split('/').collect();
+        let route = match (method, segments.as_slice()) {
+            ("GET", ["items"]) => Route::List,
+            ("POST", ["items"]) => Route::Add,
+            ("GET", ["search"]) => Route::Search,
+            ("GET", ["events"]) => Route::Events,
+            ("GET", ["items", id]) => Route::Get(id.to_string()),
+            ("POST", ["items", id, "paste"]) => Route::Paste(id.to_string()),
+            ("POST", ["items", id, "copy"]) => Route::Copy(id.to_string()),
+            _ => return None,
+        };
+        Some(route)
+    }
+}
+
+/// A new random bearer token.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Whether a request presents `token`, in its `Authorization` header or, where
+/// allowed, as a query parameter. An empty token admits nobody.
+pub fn authorized(token: &str, header: Option<&str>, query_token: Option<&str>) -> bool {
+    let presented = header
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .or(query_token)
+        .map(str::trim);
+    match presented {
+        Some(presented) if !token.is_empty() => constant_time_eq(token, presented),
+        _ => false,
+    }
+}
+
+/// Compare without returning early, so response times don't reveal how much
+/// of a guessed token was right.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// Decode a query string into its parameters (later duplicates win).
+pub fn query_params(query: Option<&str>) -> HashMap<String, String> {
+    url::form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+        .into_owned()
+        .collect()
+}
+
+/// Status for a command's error message: the commands report errors as
+/// text, so the few a client can act on are told apart by their wording.
+pub fn error_status(message: &str) -> StatusCode {
+    if message == "History is locked" {
+        StatusCode::LOCKED
+    } else if message.ends_with("not found") {
+        StatusCode::NOT_FOUND
+    } else if message == "Nothing to add" {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+/// Status and body for `POST /v1/items`: 201 when the text was recorded,
+/// 200 with `recorded: false` when it was skipped (ignored source, too large).
+pub fn added_response(id: Option<String>) -> (StatusCode, serde_json::Value) {
+    let status = if id.is_some() {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    let recorded = id.is_some();
+    (
+        status,
+        serde_json::json!({ "id": id, "recorded": recorded }),
+    )
+}
+
+/// Frame one Server-Sent Event.
+pub fn sse_event(event: &str, data: &str) -> String {
+    let mut frame = format!("event: {}\n", event);
+    for line in data.lines() {
+        frame.push_str("data: ");
+        frame.push_str(line);
+        frame.push('\n');
+    }
+    frame.push('\n');
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes() {
+        assert_eq!(Route::parse("GET", "/v1/items"), Some(Route::List));
+        assert_eq!(Route::parse("GET", "/v1/items/"), Some(Route::List));
+        assert_eq!(Route::parse("POST", "/v1/items"), Some(Route::Add));
+        assert_eq!(Route::parse("GET", "/v1/search"), Some(Route::Search));
+        assert_eq!(Route::parse("GET", "/v1/events"), Some(Route::Events));
+        assert_eq!(
+            Route::parse("GET", "/v1/items/abc"),
+            Some(Route::Get("abc".to_string()))
+        );
+        assert_eq!(
+            Route::parse("POST", "/v1/items/abc/paste"),
+            Some(Route::Paste("abc".to_string()))
+        );
+        assert_eq!(
+            Route::parse("POST", "/v1/items/abc/copy"),
+            Some(Route::Copy("abc".to_string()))
+        );
+
+        assert_eq!(Route::parse("DELETE", "/v1/items/abc"), None);
+        assert_eq!(Route::parse("GET", "/v1/items/abc/paste"), None);
+        assert_eq!(Route::parse("GET", "/items"), None);
+        assert_eq!(Route::parse("GET", "/v1/unknown"), None);
+    }
+
+    #[test]
+    fn test_token_checks() {
+        let token = generate_token();
+        assert_eq!(token.len(), TOKEN_BYTES * 2);
+        assert_ne!(token, generate_token());
+
+        let header = format!("Bearer {}", token);
+        assert!(authorized(&token, Some(&header), None));
+        assert!(authorized(&token, None, Some(&token)));
+        assert!(!authorized(&token, None, None));
+        assert!(!authorized(&token, Some("Bearer wrong"), None));
+        assert!(
+            !authorized(&token, Some(&token), None),
+            "scheme is required"
+        );
+        assert!(!authorized(&token, Some(&header[..header.len() - 1]), None));
+
+        // No token configured: nothing gets in
+        assert!(!authorized("", Some("Bearer "), None));
+        assert!(!authorized("", None, Some("")));
+    }
+
+    #[test]
+    fn test_error_status() {
+        assert_eq!(error_status("History is locked"), StatusCode::LOCKED);
+        assert_eq!(error_status("Item not found"), StatusCode::NOT_FOUND);
+        assert_eq!(error_status("Nothing to add"), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            error_status("Failed to write clipboard"),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    fn test_added_response() {
+        let (status, body) = added_response(Some("a".to_string()));
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body, serde_json::json!({ "id": "a", "recorded": true }));
+
+        let (status, body) = added_response(None);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "id": null, "recorded": false }));
+    }
+
+    #[test]
+    fn test_query_params_and_events() {
+        let params = query_params(Some("q=docker%20run&limit=5&favorites=true"));
+        assert_eq!(params["q"], "docker run");
+        assert_eq!(params["limit"], "5");
+        assert!(query_params(None).is_empty());
+
+        assert_eq!(
+            sse_event("clipboard-changed", r#"{"id":"1"}"#),
+            "event: clipboard-changed\ndata: {\"id\":\"1\"}\n\n"
+        );
+        assert_eq!(sse_event("e", "a\nb"), "event: e\ndata: a\ndata: b\n\n");
+    }
+}
@@ -0,0 +1,255 @@
+//! The app side of the HTTP API: serves each route through the matching
+//! command and streams `clipboard-changed` events.
+
+use super::Route;
+use crate::commands::clipboard as clip_cmd;
+use crate::platform::FrontmostApp;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::{Bytes, Frame, Incoming};
+use hyper::header::{AUTHORIZATION, CACHE_CONTROL, CONTENT_TYPE, WWW_AUTHENTICATE};
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc};
+
+type Body = BoxBody<Bytes, Infallible>;
+
+/// Largest request body accepted (the highest max item size setting).
+const MAX_BODY_BYTES: usize = 100 * 1024 * 1024;
+
+/// Comment lines sent on idle event streams, so proxies and clients can tell
+/// a quiet stream from a dead one.
+const KEEP_ALIVE_SECS: u64 = 30;
+
+#[derive(Deserialize)]
+struct AddBody {
+    text: String,
+    source: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct PasteBody {
+    plain_text: Option<bool>,
+}
+
+/// Shared by every request on the server.
+struct Shared {
+    app: AppHandle,
+    token: String,
+    events: broadcast::Sender<String>,
+}
+
+/// Accept connections until the task is aborted, which also drops every open
+/// connection.
+pub async fn serve(
+    app: AppHandle,
+    listener: TcpListener,
+    token: String,
+    events: broadcast::Sender<String>,
+) {
+    let shared = Arc::new(Shared { app, token, events });
+    let mut connections = tokio::task::JoinSet::new();
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                log::warn!("HTTP API accept failed: {}", e);
+                continue;
+            }
+        };
+        // Reap finished connections so the set doesn't grow without bound
+        while connections.try_join_next().is_some() {}
+
+        let shared = shared.clone();
+        connections.spawn(async move {
+            let service = hyper::service::service_fn(move |request| {
+                let shared = shared.clone();
+                async move { Ok::<_, Infallible>(handle(&shared, request).await) }
+            });
+            if let Err(e) = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                log::debug!("HTTP API connection ended: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle(shared: &Shared, request: Request<Incoming>) -> Response<Body> {
+    let Some(route) = Route::parse(request.method().as_str(), request.uri().path()) else {
+        return error(StatusCode::NOT_FOUND, "Not found");
+    };
+    let params = super::query_params(request.uri().query());
+    let header = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+    let query_token = match route {
+        Route::Events => params.get("token").map(String::as_str),
+        _ => None,
+    };
+    if !super::authorized(&shared.token, header, query_token) {
+        let mut response = error(StatusCode::UNAUTHORIZED, "Missing or wrong token");
+        response
+            .headers_mut()
+            .insert(WWW_AUTHENTICATE, "Bearer".parse().unwrap());
+        return response;
+    }
+
+    let app = &shared.app;
+    let number = |key: &str| params.get(key).and_then(|v| v.parse::<i64>().ok());
+    match route {
+        Route::List => reply(
+            clip_cmd::get_clipboard_items(
+                app.state(),
+                params.get("content_type").cloned(),
+                number("limit"),
+                number("offset"),
+            )
+            .await,
+        ),
+        Route::Search => reply(
+            clip_cmd::search_clipboard_items(
+                app.state(),
+                params.get("q").cloned().unwrap_or_default(),
+                params.get("content_type").cloned(),
+                number("limit"),
+                Some(params.get("favorites").is_some_and(|v| v == "true")),
+            )
+            .await,
+        ),
+        Route::Get(id) => reply(clip_cmd::get_item_detail(app.state(), id).await),
+        Route::Add => {
+            let body: AddBody = match read_json(request).await {
+                Ok(Some(body)) => body,
+                Ok(None) => return error(StatusCode::BAD_REQUEST, "Body must have text"),
+                Err(response) => return response,
+            };
+            let name = body.source.unwrap_or_else(|| "HTTP API".to_string());
+            let source = FrontmostApp {
+                id: name.clone(),
+                name,
+                window_title: String::new(),
+                executable: String::new(),
+            };
+            match clip_cmd::add_text_item(app, body.text, source).await {
+                Ok(id) => {
+                    let (status, body) = super::added_response(id);
+                    json_response(status, &body)
+                }
+                Err(e) => error(super::error_status(&e), &e),
+            }
+        }
+        Route::Paste(id) => {
+            let body: PasteBody = match read_json(request).await {
+                Ok(body) => body.unwrap_or_default(),
+                Err(response) => return response,
+            };
+            let pasted = if body.plain_text.unwrap_or(false) {
+                clip_cmd::paste_as_plain_text(app.clone(), app.state(), id).await
+            } else {
+                clip_cmd::paste_clipboard_item(app.clone(), app.state(), id, Some(true)).await
+            };
+            reply(pasted)
+        }
+        Route::Copy(id) => {
+            reply(clip_cmd::paste_clipboard_item(app.clone(), app.state(), id, Some(false)).await)
+        }
+        Route::Events => event_stream(shared.events.subscribe()),
+    }
+}
+
+/// Read a JSON body; Ok(None) when there is none.
+async fn read_json<T: serde::de::DeserializeOwned>(
+    request: Request<Incoming>,
+) -> Result<Option<T>, Response<Body>> {
+    let bytes = match Limited::new(request.into_body(), MAX_BODY_BYTES)
+        .collect()
+        .await
+    {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) => return Err(error(StatusCode::PAYLOAD_TOO_LARGE, &e.to_string())),
+    };
+    if bytes.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+    serde_json::from_slice(&bytes)
+        .map(Some)
+        .map_err(|e| error(StatusCode::BAD_REQUEST, &e.to_string()))
+}
+
+/// Start an SSE response fed from the app's `clipboard-changed` events.
+fn event_stream(mut events: broadcast::Receiver<String>) -> Response<Body> {
+    let (sender, receiver) = mpsc::channel::<Bytes>(16);
+    tauri::async_runtime::spawn(async move {
+        let mut keep_alive = tokio::time::interval(std::time::Duration::from_secs(KEEP_ALIVE_SECS));
+        loop {
+            let chunk = tokio::select! {
+                event = events.recv() => match event {
+                    Ok(payload) => super::sse_event("clipboard-changed", &payload),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = keep_alive.tick() => ": keep-alive\n\n".to_string(),
+            };
+            // The client went away and its connection dropped the body
+            if sender.send(Bytes::from(chunk)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Response::builder()
+        .header(CONTENT_TYPE, "text/event-stream")
+        .header(CACHE_CONTROL, "no-cache")
+        .body(EventBody(receiver).boxed())
+        .unwrap()
+}
+
+/// Body of an event stream: chunks sent by the forwarding task.
+struct EventBody(mpsc::Receiver<Bytes>);
+
+impl hyper::body::Body for EventBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        self.0
+            .poll_recv(cx)
+            .map(|chunk| chunk.map(|chunk| Ok(Frame::data(chunk))))
+    }
+}
+
+fn reply<T: serde::Serialize>(result: Result<T, String>) -> Response<Body> {
+    match result {
+        Ok(value) => match serde_json::to_value(value) {
+            Ok(value) => json_response(StatusCode::OK, &value),
+            Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+        },
+        Err(e) => error(super::error_status(&e), &e),
+    }
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    json_response(status, &json!({ "error": message }))
+}
+
+fn json_response(status: StatusCode, value: &Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Full::new(Bytes::from(value.to_string())).boxed())
+        .unwrap()
+}
@@ -0,0 +1,398 @@
+//! Local socket API of the running app, for editors, launchers and `recopy-cli`.
+//!
+//! Each connection carries JSON-RPC 2.0 messages, one per line: a Unix-domain
+//! socket on macOS and Linux, a named pipe on Windows. Only the current user
+//! can connect.
+//!
+//! Clients open with `hello`, naming the protocol version they speak; the app
+//! refuses versions newer than its own and answers with its version and
+//! methods. After `subscribe`, every `clipboard-changed` event the window
+//! receives is also sent on the connection as a notification of that name.
+//! The methods and their parameters are described in `docs/ipc.md`.
+
+pub mod server;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Version of the methods and messages below. Bumped on incompatible changes;
+/// new methods and optional parameters don't need a bump.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Notification sent to subscribed connections when the history changes.
+pub const CLIPBOARD_CHANGED: &str = "clipboard-changed";
+
+/// Standard JSON-RPC error codes.
+pub const PARSE_ERROR: i64 = -32700;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// A command failed; the message is the command's error.
+pub const COMMAND_FAILED: i64 = -32000;
+/// The client asked for a protocol version newer than the app's.
+pub const UNSUPPORTED_VERSION: i64 = -32001;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+/// A message without an id, sent by the app to subscribed connections.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl Response {
+    pub fn new(id: Value, outcome: Result<Value, RpcError>) -> Self {
+        let (result, error) = match outcome {
+            Ok(value) => (Some(value), None),
+            Err(e) => (None, Some(e)),
+        };
+        Response {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+/// Where the running app listens: `$XDG_RUNTIME_DIR/recopy.sock` on Linux,
+/// `recopy-<uid>/recopy.sock` in the temp folder elsewhere (or without
+/// `XDG_RUNTIME_DIR`), and a per-user named pipe on Windows.
+pub fn socket_path() -> PathBuf {
+    #[cfg(windows)]
+    {
+        let user = std::env::var("USERNAME").unwrap_or_default();
+        PathBuf::from(format!(r"\\.\pipe\recopy-{}", user))
+    }
+    #[cfg(not(windows))]
+    {
+        if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR").filter(|d| !d.is_empty()) {
+            return PathBuf::from(dir).join("recopy.sock");
+        }
+        std::env::temp_dir()
+            .join(format!("recopy-{}", current_uid()))
+            .join("recopy.sock")
+    }
+}
+
+#[cfg(unix)]
+fn current_uid() -> u32 {
+    extern "C" {
+        fn getuid() -> u32;
+    }
+    // SAFETY: getuid takes no arguments and cannot fail
+    unsafe { getuid() }
+}
+
+/// Fail unless `path` (the socket or its folder) belongs to the current user.
+/// The temp folder is shared, so another user could have created either
+/// first; folders must also be closed to everyone else.
+#[cfg(unix)]
+pub fn check_owner(path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let meta = std::fs::symlink_metadata(path)?;
+    let open_to_others = meta.is_dir() && meta.mode() & 0o077 != 0;
+    if meta.uid() != current_uid() || open_to_others {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} is not private to the current user", path.display()),
+        ));
+    }
+    Ok(())
+}
+
+// ---- Client ----
+
+/// Why a call through the socket failed.
+#[derive(Debug)]
+pub enum CallError {
+    /// Nothing listens on the socket: the app is not running.
+    NotRunning,
+    /// The app answered with an error.
+    Rpc(RpcError),
+    /// The connection broke or the answer was unreadable.
+    Io(String),
+}
+
+impl std::fmt::Display for CallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallError::NotRunning => write!(f, "Recopy is not running"),
+            CallError::Rpc(e) => write!(f, "{}", e.message),
+            CallError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// A connection to the running app.
+pub struct Client {
+    reader: BufReader<tokio::io::ReadHalf<Stream>>,
+    writer: tokio::io::WriteHalf<Stream>,
+    next_id: u64,
+    /// Notifications that arrived while waiting for a response.
+    pending: VecDeque<Notification>,
+}
+
+#[cfg(unix)]
+type Stream = tokio::net::UnixStream;
+#[cfg(windows)]
+type Stream = tokio::net::windows::named_pipe::NamedPipeClient;
+
+impl Client {
+    /// Connect and say `hello`. Fails if the app speaks an older protocol.
+    pub async fn connect(name: &str) -> Result<Self, CallError> {
+        let path = socket_path();
+        #[cfg(unix)]
+        let stream = {
+            if !path.exists() {
+                return Err(CallError::NotRunning);
+            }
+            // Don't send requests to a socket another user put in our place
+            let parent = path.parent().unwrap_or(&path);
+            check_owner(&path)
+                .and_then(|_| check_owner(parent))
+                .map_err(|e| CallError::Io(e.to_string()))?;
+            tokio::net::UnixStream::connect(&path)
+                .await
+                .map_err(|_| CallError::NotRunning)?
+        };
+        #[cfg(windows)]
+        let stream = tokio::net::windows::named_pipe::ClientOptions::new()
+            .open(&path)
+            .map_err(|_| CallError::NotRunning)?;
+        Self::open(stream, name).await
+    }
+
+    /// Say `hello` on an open connection.
+    async fn open(stream: Stream, name: &str) -> Result<Self, CallError> {
+        let (reader, writer) = tokio::io::split(stream);
+        let mut client = Client {
+            reader: BufReader::new(reader),
+            writer,
+            next_id: 1,
+            pending: VecDeque::new(),
+        };
+        client
+            .call(
+                "hello",
+                serde_json::json!({ "protocol": PROTOCOL_VERSION, "client": name }),
+            )
+            .await?;
+        Ok(client)
+    }
+
+    /// Call a method and wait for its result.
+    pub async fn call(&mut self, method: &str, params: Value) -> Result<Value, CallError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = Request {
+            jsonrpc: "2.0".to_string(),
+            id: Some(Value::from(id)),
+            method: method.to_string(),
+            params,
+        };
+        let mut line = serde_json::to_string(&request).map_err(|e| CallError::Io(e.to_string()))?;
+        line.push('\n');
+        self.writer
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| CallError::Io(e.to_string()))?;
+
+        loop {
+            let response = match self.read_message().await? {
+                Message::Response(response) => response,
+                Message::Notification(notification) => {
+                    self.pending.push_back(notification);
+                    continue;
+                }
+            };
+            if response.id != id {
+                continue;
+            }
+            return match (response.result, response.error) {
+                (_, Some(error)) => Err(CallError::Rpc(error)),
+                (result, None) => Ok(result.unwrap_or(Value::Null)),
+            };
+        }
+    }
+
+    /// Wait for the next notification (after calling `subscribe`).
+    pub async fn next_notification(&mut self) -> Result<Notification, CallError> {
+        if let Some(notification) = self.pending.pop_front() {
+            return Ok(notification);
+        }
+        loop {
+            if let Message::Notification(notification) = self.read_message().await? {
+                return Ok(notification);
+            }
+        }
+    }
+
+    async fn read_message(&mut self) -> Result<Message, CallError> {
+        let mut line = String::new();
+        let read = self
+            .reader
+            .read_line(&mut line)
+            .await
+            .map_err(|e| CallError::Io(e.to_string()))?;
+        if read == 0 {
+            return Err(CallError::Io("Recopy closed the connection".to_string()));
+        }
+        let value: Value = serde_json::from_str(&line).map_err(|e| CallError::Io(e.to_string()))?;
+        let message = if value.get("id").is_some() {
+            serde_json::from_value(value).map(Message::Response)
+        } else {
+            serde_json::from_value(value).map(Message::Notification)
+        };
+        message.map_err(|e| CallError::Io(e.to_string()))
+    }
+}
+
+enum Message {
+    Response(Response),
+    Notification(Notification),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_has_result_or_error() {
+        let ok = Response::new(Value::from(1), Ok(Value::Bool(true)));
+        assert_eq!(
+            serde_json::to_value(&ok).unwrap(),
+            serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": true })
+        );
+
+        let failed = Response::new(
+            Value::from("a"),
+            Err(RpcError::new(METHOD_NOT_FOUND, "Unknown method nope")),
+        );
+        assert_eq!(
+            serde_json::to_value(&failed).unwrap(),
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": "a",
+                "error": { "code": METHOD_NOT_FOUND, "message": "Unknown method nope" },
+            })
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_owner_refuses_open_folders() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("recopy-ipc-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let error = check_owner(&dir).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).unwrap();
+        assert!(check_owner(&dir).is_ok());
+        // Only folders must be closed to others; `listen` narrows the socket itself
+        let file = dir.join("recopy.sock");
+        std::fs::write(&file, b"").unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(check_owner(&file).is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_client_round_trip() {
+        let (client_side, app_side) = tokio::net::UnixStream::pair().unwrap();
+
+        // Stands in for the app: answers hello and list, with an event in between
+        let app = tokio::spawn(async move {
+            let (reader, mut writer) = tokio::io::split(app_side);
+            let mut lines = BufReader::new(reader).lines();
+            let mut methods = Vec::new();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let request: Request = serde_json::from_str(&line).unwrap();
+                let outcome = match request.method.as_str() {
+                    "hello" => Ok(serde_json::json!({ "protocol": PROTOCOL_VERSION })),
+                    "list" => {
+                        let event = Notification {
+                            jsonrpc: "2.0".to_string(),
+                            method: CLIPBOARD_CHANGED.to_string(),
+                            params: serde_json::json!({ "id": "x" }),
+                        };
+                        let mut out = serde_json::to_string(&event).unwrap();
+                        out.push('\n');
+                        writer.write_all(out.as_bytes()).await.unwrap();
+                        Ok(serde_json::json!([]))
+                    }
+                    method => Err(RpcError::new(
+                        METHOD_NOT_FOUND,
+                        format!("Unknown method {}", method),
+                    )),
+                };
+                methods.push(request.method);
+                let response = Response::new(request.id.unwrap(), outcome);
+                let mut out = serde_json::to_string(&response).unwrap();
+                out.push('\n');
+                writer.write_all(out.as_bytes()).await.unwrap();
+            }
+            methods
+        });
+
+        let mut client = Client::open(client_side, "test").await.unwrap();
+        let items = client.call("list", Value::Null).await.unwrap();
+        assert_eq!(items, serde_json::json!([]));
+        match client.call("nope", Value::Null).await {
+            Err(CallError::Rpc(e)) => assert_eq!(e.code, METHOD_NOT_FOUND),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+        // The event that came before the list result was kept
+        let notification = client.next_notification().await.unwrap();
+        assert_eq!(notification.method, CLIPBOARD_CHANGED);
+        assert_eq!(notification.params["id"], "x");
+
+        drop(client);
+        assert_eq!(app.await.unwrap(), ["hello", "list", "nope"]);
+    }
+}
@@ -0,0 +1,470 @@
+//! The app side of the socket: accepts connections, runs each request
+//! through the matching command and forwards `clipboard-changed` events to
+//! subscribed connections.
+
+#[cfg(unix)]
+use super::check_owner;
+use super::{
+    socket_path, Notification, Request, Response, RpcError, CLIPBOARD_CHANGED, COMMAND_FAILED,
+    INVALID_PARAMS, METHOD_NOT_FOUND, PARSE_ERROR, PROTOCOL_VERSION, UNSUPPORTED_VERSION,
+};
+use crate::commands::clipboard as clip_cmd;
+use crate::platform::FrontmostApp;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Listener, Manager};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::sync::broadcast;
+
+/// Methods answered by this version, reported by `hello`.
+const METHODS: &[&str] = &[
+    "hello",
+    "list",
+    "search",
+    "get",
+    "copy",
+    "paste",
+    "add",
+    "favorite",
+    "delete",
+    "clear",
+    "subscribe",
+    "unsubscribe",
+];
+
+/// Events buffered per connection before a slow subscriber starts missing some.
+const EVENT_BUFFER: usize = 64;
+
+/// Longest request line read, above the largest text `add` accepts by
+/// default. Longer lines close the connection.
+const MAX_REQUEST_LINE: usize = 32 * 1024 * 1024;
+
+#[derive(Deserialize)]
+struct HelloParams {
+    protocol: Option<u32>,
+    /// Name of the connecting program, for the log.
+    client: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ListParams {
+    content_type: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    query: String,
+    content_type: Option<String>,
+    limit: Option<i64>,
+    favorites_only: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct IdParams {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct PasteParams {
+    id: String,
+    /// Paste the item's text without formatting.
+    plain_text: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct AddParams {
+    text: String,
+    /// Name of the program adding the text, shown as the item's source.
+    source: Option<String>,
+}
+
+/// What a connection has asked for beyond single requests.
+#[derive(Default)]
+struct Session {
+    subscribed: bool,
+}
+
+/// Start listening on the socket. Failures are logged; the app runs without it.
+pub fn start(app: AppHandle) {
+    // Every connection gets its own receiver; payloads are the events' JSON
+    let (events, _) = broadcast::channel::<String>(EVENT_BUFFER);
+    let sender = events.clone();
+    app.listen(CLIPBOARD_CHANGED, move |event: tauri::Event| {
+        let _ = sender.send(event.payload().to_string());
+    });
+
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = listen(app, events).await {
+            log::warn!("Local socket API unavailable: {}", e);
+        }
+    });
+}
+
+#[cfg(unix)]
+async fn listen(app: AppHandle, events: broadcast::Sender<String>) -> std::io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let path = socket_path();
+    // Bind inside a folder only we can enter, so nobody can reach the socket
+    // in the moment before its own permissions are narrowed
+    if let Some(dir) = path.parent() {
+        if !dir.exists() {
+            std::fs::DirBuilder::new().mode(0o700).create(dir)?;
+        }
+        check_owner(dir)?;
+    }
+    // A socket file nobody answers on is left over from a crash
+    if path.exists() && tokio::net::UnixStream::connect(&path).await.is_err() {
+        check_owner(&path)?;
+        std::fs::remove_file(&path)?;
+    }
+    let listener = tokio::net::UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    log::info!("Local socket API listening at {}", path.display());
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tauri::async_runtime::spawn(serve(app.clone(), events.subscribe(), stream));
+    }
+}
+
+#[cfg(windows)]
+async fn listen(app: AppHandle, events: broadcast::Sender<String>) -> std::io::Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let path = socket_path();
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .reject_remote_clients(true)
+        .create(&path)?;
+    log::info!("Local socket API listening at {}", path.display());
+
+    loop {
+        server.connect().await?;
+        let connected = server;
+        server = ServerOptions::new()
+            .reject_remote_clients(true)
+            .create(&path)?;
+        tauri::async_runtime::spawn(serve(app.clone(), events.subscribe(), connected));
+    }
+}
+
+/// Answer requests on one connection until the client hangs up, passing on
+/// events once it has subscribed.
+async fn serve<S: AsyncRead + AsyncWrite + Send + 'static>(
+    app: AppHandle,
+    mut events: broadcast::Receiver<String>,
+    stream: S,
+) {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+    // Kept across iterations: a line cut short by an event is finished next time
+    let mut buf = Vec::new();
+    let mut session = Session::default();
+    loop {
+        tokio::select! {
+            read = read_line(&mut reader, &mut buf) => {
+                match read {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(e) => {
+                        log::debug!("Socket connection dropped: {}", e);
+                        break;
+                    }
+                }
+                let line = String::from_utf8_lossy(&buf).into_owned();
+                buf.clear();
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let Some(response) = handle_line(&app, &mut session, &line).await else {
+                    continue;
+                };
+                if !send(&mut writer, &response).await {
+                    break;
+                }
+            }
+            event = events.recv() => {
+                let payload = match event {
+                    Ok(payload) => payload,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        log::debug!("Socket subscriber missed {} events", missed);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if !session.subscribed {
+                    continue;
+                }
+                let notification = Notification {
+                    jsonrpc: "2.0".to_string(),
+                    method: CLIPBOARD_CHANGED.to_string(),
+                    params: serde_json::from_str(&payload).unwrap_or(Value::Null),
+                };
+                if !send(&mut writer, &notification).await {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Read up to the next newline into `buf`, at most `MAX_REQUEST_LINE` bytes.
+/// False at the end of the stream. Safe to cancel: bytes already read stay
+/// in `buf`.
+async fn read_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+) -> std::io::Result<bool> {
+    let room = (MAX_REQUEST_LINE + 1).saturating_sub(buf.len()) as u64;
+    let read = reader.take(room).read_until(b'\n', buf).await?;
+    if buf.len() > MAX_REQUEST_LINE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "request line too long",
+        ));
+    }
+    Ok(read > 0)
+}
+
+/// Write one message as a line. False once the client is gone.
+async fn send<W: AsyncWrite + Unpin, T: Serialize>(writer: &mut W, message: &T) -> bool {
+    let Ok(mut out) = serde_json::to_string(message) else {
+        return true;
+    };
+    out.push('\n');
+    writer.write_all(out.as_bytes()).await.is_ok()
+}
+
+/// Run one request. Notifications (requests without an id) get no response.
+async fn handle_line(app: &AppHandle, session: &mut Session, line: &str) -> Option<Response> {
+    let request: Request = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(Response::new(
+                Value::Null,
+                Err(RpcError::new(PARSE_ERROR, e.to_string())),
+            ))
+        }
+    };
+    let outcome = dispatch(app, session, &request.method, request.params).await;
+    if let Err(e) = &outcome {
+        log::debug!("Socket request {} failed: {}", request.method, e.message);
+    }
+    request.id.map(|id| Response::new(id, outcome))
+}
+
+async fn dispatch(
+    app: &AppHandle,
+    session: &mut Session,
+    method: &str,
+    params: Value,
+) -> Result<Value, RpcError> {
+    match method {
+        "hello" => hello(params),
+        "list" => {
+            let p: ListParams = parse(params)?;
+            let items =
+                clip_cmd::get_clipboard_items(app.state(), p.content_type, p.limit, p.offset).await;
+            reply(items)
+        }
+        "search" => {
+            let p: SearchParams = parse(params)?;
+            let items = clip_cmd::search_clipboard_items(
+                app.state(),
+                p.query,
+                p.content_type,
+                p.limit,
+                p.favorites_only,
+            )
+            .await;
+            reply(items)
+        }
+        "get" => {
+            let p: IdParams = parse(params)?;
+            reply(clip_cmd::get_item_detail(app.state(), p.id).await)
+        }
+        "copy" => {
+            let p: IdParams = parse(params)?;
+            reply(clip_cmd::paste_clipboard_item(app.clone(), app.state(), p.id, Some(false)).await)
+        }
+        "paste" => {
+            let p: PasteParams = parse(params)?;
+            let pasted = if p.plain_text.unwrap_or(false) {
+                clip_cmd::paste_as_plain_text(app.clone(), app.state(), p.id).await
+            } else {
+                clip_cmd::paste_clipboard_item(app.clone(), app.state(), p.id, Some(true)).await
+            };
+            reply(pasted)
+        }
+        "add" => {
+            let p: AddParams = parse(params)?;
+            let name = p.source.unwrap_or_default();
+            let source = FrontmostApp {
+                id: name.clone(),
+                name,
+                window_title: String::new(),
+                executable: String::new(),
+            };
+            reply(clip_cmd::add_text_item(app, p.text, source).await)
+        }
+        "favorite" => {
+            let p: IdParams = parse(params)?;
+            let favorited = clip_cmd::toggle_favorite(app.state(), p.id.clone()).await;
+            if favorited.is_ok() {
+                changed(app, Some(&p.id));
+            }
+            reply(favorited)
+        }
+        "delete" => {
+            let p: IdParams = parse(params)?;
+            let deleted = clip_cmd::delete_clipboard_item(app.state(), p.id).await;
+            if deleted.is_ok() {
+                changed(app, None);
+            }
+            reply(deleted)
+        }
+        "clear" => {
+            let cleared = clip_cmd::clear_history(app.state()).await;
+            if cleared.is_ok() {
+                changed(app, None);
+            }
+            reply(cleared)
+        }
+        "subscribe" | "unsubscribe" => {
+            session.subscribed = method == "subscribe";
+            Ok(Value::Bool(session.subscribed))
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method {}", method),
+        )),
+    }
+}
+
+/// Answer `hello`, refusing clients that speak a newer protocol.
+fn hello(params: Value) -> Result<Value, RpcError> {
+    let p: HelloParams = parse(params)?;
+    let protocol = p.protocol.unwrap_or(PROTOCOL_VERSION);
+    if protocol > PROTOCOL_VERSION {
+        return Err(RpcError::new(
+            UNSUPPORTED_VERSION,
+            format!(
+                "Protocol version {} is not supported; this Recopy speaks version {}",
+                protocol, PROTOCOL_VERSION
+            ),
+        ));
+    }
+    log::debug!(
+        "Socket client {} connected (protocol {})",
+        p.client.as_deref().unwrap_or("unknown"),
+        protocol
+    );
+    Ok(serde_json::json!({
+        "protocol": PROTOCOL_VERSION,
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "methods": METHODS,
+    }))
+}
+
+/// Let the window refresh after a change made through the socket.
+fn changed(app: &AppHandle, id: Option<&str>) {
+    let payload = match id {
+        Some(id) => serde_json::json!({ "id": id }),
+        None => serde_json::json!({}),
+    };
+    let _ = app.emit(CLIPBOARD_CHANGED, payload);
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    // Methods without parameters may be called with none at all
+    let params = if params.is_null() {
+        Value::Object(Default::default())
+    } else {
+        params
+    };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn reply<T: serde::Serialize>(result: Result<T, String>) -> Result<Value, RpcError> {
+    let value = result.map_err(|e| RpcError::new(COMMAND_FAILED, e))?;
+    serde_json::to_value(value).map_err(|e| RpcError::new(COMMAND_FAILED, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_read_line_caps_length() {
+        let long = vec![b'a'; MAX_REQUEST_LINE + 10];
+        let mut reader = BufReader::new(&long[..]);
+        let mut buf = Vec::new();
+        let error = read_line(&mut reader, &mut buf).await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        // Exactly at the cap is still read
+        let mut line = vec![b'a'; MAX_REQUEST_LINE - 1];
+        line.push(b'\n');
+        let mut reader = BufReader::new(&line[..]);
+        let mut buf = Vec::new();
+        assert!(read_line(&mut reader, &mut buf).await.unwrap());
+        assert_eq!(buf.len(), MAX_REQUEST_LINE);
+    }
+
+    #[tokio::test]
+    async fn test_read_line_resumes_after_cancel() {
+        let (mut client, app_side) = tokio::io::duplex(64);
+        let mut reader = BufReader::new(app_side);
+        let mut buf = Vec::new();
+
+        client.write_all(b"{\"method\":").await.unwrap();
+        // Cut short, as when an event wins the select in `serve`
+        let cut =
+            tokio::time::timeout(Duration::from_millis(50), read_line(&mut reader, &mut buf)).await;
+        assert!(cut.is_err());
+
+        client.write_all(b"\"list\"}\nnext").await.unwrap();
+        assert!(read_line(&mut reader, &mut buf).await.unwrap());
+        assert_eq!(buf, b"{\"method\":\"list\"}\n");
+
+        buf.clear();
+        drop(client);
+        assert!(read_line(&mut reader, &mut buf).await.unwrap());
+        assert_eq!(buf, b"next");
+        buf.clear();
+        assert!(!read_line(&mut reader, &mut buf).await.unwrap());
+    }
+
+    #[test]
+    fn test_parse_null_params() {
+        let p: ListParams = parse(Value::Null).unwrap();
+        assert!(p.content_type.is_none() && p.limit.is_none() && p.offset.is_none());
+
+        let error = parse::<IdParams>(Value::Null).err().unwrap();
+        assert_eq!(error.code, INVALID_PARAMS);
+        let p: IdParams = parse(serde_json::json!({ "id": "a" })).unwrap();
+        assert_eq!(p.id, "a");
+    }
+
+    #[test]
+    fn test_hello_refuses_newer_protocol() {
+        let error = hello(serde_json::json!({ "protocol": PROTOCOL_VERSION + 1 }))
+            .err()
+            .unwrap();
+        assert_eq!(error.code, UNSUPPORTED_VERSION);
+
+        let answer = hello(Value::Null).unwrap();
+        assert_eq!(answer["protocol"], PROTOCOL_VERSION);
+        assert!(answer["methods"]
+            .as_array()
+            .unwrap()
+            .contains(&Value::from("subscribe")));
+    }
+}
@@ -1,9 +1,34 @@
+mod archive;
+mod backup;
 mod clipboard;
 mod commands;
-mod db;
+mod crypto;
+pub mod db;
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod dbus;
+mod deep_link;
+mod http;
+pub mod ipc;
+mod merge;
+mod paste_queue;
 mod platform;
+mod snippet;
+mod sync;
+mod transform;
 
+use commands::archive as archive_cmd;
+use commands::backup as backup_cmd;
 use commands::clipboard as clip_cmd;
+use commands::encryption as crypt_cmd;
+use commands::groups as group_cmd;
+use commands::http_api as http_cmd;
+use commands::ignored_apps as ignore_cmd;
+use commands::lock as lock_cmd;
+use commands::paste_queue as queue_cmd;
+use commands::snippets as snippet_cmd;
+use commands::sync as sync_cmd;
+use commands::transforms as transform_cmd;
+use commands::trash as trash_cmd;
 use db::models::ContentType;
 use std::sync::atomic::{AtomicBool, Ordering};
 
@@ -39,9 +64,10 @@ pub fn run() {
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
             None,
         ))
-        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
-            show_main_window(app);
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            deep_link::open::open_args(app, &args);
         }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_os::init());
 
     // Register updater + process plugins (gated behind self-update feature for App Store compat)
@@ -55,10 +81,13 @@ pub fn run() {
             clip_cmd::get_clipboard_items,
             clip_cmd::search_clipboard_items,
             clip_cmd::get_thumbnail,
+            clip_cmd::get_image_data,
             clip_cmd::get_item_detail,
+            clip_cmd::get_item_files,
             clip_cmd::delete_clipboard_item,
             clip_cmd::paste_clipboard_item,
             clip_cmd::paste_as_plain_text,
+            clip_cmd::paste_merged_items,
             clip_cmd::toggle_favorite,
             clip_cmd::get_favorited_items,
             clip_cmd::get_settings,
@@ -80,6 +109,67 @@ pub fn run() {
             clip_cmd::get_storage_size,
             clip_cmd::set_tray_visible,
             clip_cmd::sync_system_theme,
+            group_cmd::get_groups,
+            group_cmd::create_group,
+            group_cmd::rename_group,
+            group_cmd::delete_group,
+            group_cmd::reorder_groups,
+            group_cmd::add_item_to_group,
+            group_cmd::remove_item_from_group,
+            group_cmd::get_item_groups,
+            group_cmd::get_group_items,
+            ignore_cmd::get_ignored_apps,
+            ignore_cmd::add_ignored_app,
+            ignore_cmd::remove_ignored_app,
+            trash_cmd::get_trash_items,
+            trash_cmd::restore_clipboard_item,
+            trash_cmd::delete_clipboard_item_permanently,
+            trash_cmd::empty_trash,
+            crypt_cmd::get_encryption_status,
+            crypt_cmd::enable_encryption,
+            crypt_cmd::disable_encryption,
+            crypt_cmd::unlock_history,
+            crypt_cmd::lock_history,
+            lock_cmd::get_lock_status,
+            lock_cmd::lock_app,
+            lock_cmd::unlock_app,
+            lock_cmd::set_lock_passphrase,
+            archive_cmd::export_history,
+            archive_cmd::import_history,
+            backup_cmd::list_backups,
+            backup_cmd::create_backup,
+            backup_cmd::restore_backup,
+            sync_cmd::get_sync_status,
+            sync_cmd::set_sync_enabled,
+            sync_cmd::set_sync_folder,
+            sync_cmd::create_sync_secret,
+            sync_cmd::pair_sync,
+            sync_cmd::unpair_sync,
+            sync_cmd::add_sync_peer,
+            sync_cmd::remove_sync_peer,
+            sync_cmd::sync_now,
+            http_cmd::get_http_api_status,
+            http_cmd::set_http_api_enabled,
+            http_cmd::regenerate_http_api_token,
+            snippet_cmd::get_snippets,
+            snippet_cmd::search_snippets,
+            snippet_cmd::create_snippet,
+            snippet_cmd::update_snippet,
+            snippet_cmd::delete_snippet,
+            snippet_cmd::create_snippet_from_item,
+            snippet_cmd::get_snippet_prompts,
+            snippet_cmd::paste_snippet,
+            queue_cmd::get_paste_queue,
+            queue_cmd::set_paste_queue_collecting,
+            queue_cmd::set_paste_queue_order,
+            queue_cmd::reorder_paste_queue,
+            queue_cmd::remove_from_paste_queue,
+            queue_cmd::clear_paste_queue,
+            queue_cmd::paste_next_from_queue,
+            transform_cmd::get_transforms,
+            transform_cmd::paste_transformed,
+            transform_cmd::get_transform_shortcuts,
+            transform_cmd::set_transform_shortcuts,
         ])
         .setup(|app| {
             // Hide dock icon (tao overrides LSUIElement at startup, so must set programmatically)
@@ -89,9 +179,12 @@ pub fn run() {
             // Initialize database
             let app_handle = app.handle().clone();
             tauri::async_runtime::block_on(async {
-                db::init(&app_handle)
+                let pool = db::init(&app_handle)
                     .await
                     .expect("Failed to initialize database");
+                crypt_cmd::init_encryption(&pool).await;
+                lock_cmd::init_lock(&pool).await;
+                sync_cmd::init_sync(&pool).await;
             });
 
             // Initialize preview state for Quick Look feature
@@ -130,6 +223,15 @@ pub fn run() {
             let app_handle = app.handle().clone();
             start_clipboard_monitor(app_handle);
 
+            // Purge expiring items (e.g. concealed password-manager copies)
+            start_expiry_purge(app.handle().clone());
+
+            // Purge items that have sat in the trash past trash_retention_days
+            start_trash_purge(app.handle().clone());
+
+            // Lock the history after idle time or a system sleep
+            start_auto_lock(app.handle().clone());
+
             // Cleanup orphan image files left from previous sessions (best-effort)
             let app_handle_gc = app.handle().clone();
             tauri::async_runtime::spawn(async move {
@@ -166,24 +268,34 @@ pub fn run() {
                     .and_then(|v| v.parse::<i64>().ok())
                     .unwrap_or(1000);
 
-                // Collect image paths before deleting rows
-                let image_paths =
-                    db::queries::get_retention_overflow_image_paths(&pool.0, &policy, days, count)
-                        .await
-                        .unwrap_or_default();
-
                 match db::queries::cleanup_by_retention(&pool.0, &policy, days, count).await {
                     Ok(deleted) if deleted > 0 => {
-                        log::info!("Startup retention cleanup: removed {} items", deleted);
-                        for path in image_paths {
-                            let _ = tokio::fs::remove_file(&path).await;
-                        }
+                        log::info!("Startup retention cleanup: trashed {} items", deleted);
                     }
                     Err(e) => log::warn!("Startup retention cleanup failed: {}", e),
                     _ => {}
                 }
             });
 
+            // Snapshot the database and images to the backup folder on a schedule
+            start_backups(app.handle().clone());
+
+            // Exchange changes with paired machines on the local network
+            start_sync(app.handle().clone());
+
+            // Answer recopy-cli and other local programs on the socket
+            ipc::server::start(app.handle().clone());
+
+            // Let desktop shortcuts and other programs drive the app over D-Bus
+            #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+            dbus::server::start(app.handle().clone());
+
+            // Serve the HTTP API if the user turned it on
+            start_http_api(app.handle().clone());
+
+            // Open recopy:// links given to this launch or to the running app
+            setup_deep_links(app.handle());
+
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -315,6 +427,7 @@ pub fn show_main_window(app: &tauri::AppHandle) {
 
     platform::platform_show_window(app, &panel_position);
     let menu_bar_height = platform::platform_menu_bar_height();
+    lock_cmd::touch();
     let _ = app.emit(
         "recopy-show",
         serde_json::json!({
@@ -324,6 +437,7 @@ pub fn show_main_window(app: &tauri::AppHandle) {
             "panel_position": panel_position,
             "flat_mode_tb": flat_mode_tb,
             "menu_bar_height": menu_bar_height,
+            "locked": lock_cmd::is_locked(),
         }),
     );
 }
@@ -601,6 +715,15 @@ pub fn open_settings_window_impl(app: &tauri::AppHandle, window_theme: Option<ta
                     }
                 },
             );
+            if let Err(e) = tauri::async_runtime::block_on(queue_cmd::refresh_shortcut(&app_clone))
+            {
+                log::warn!("Paste queue shortcut unavailable: {}", e);
+            }
+            if let Err(e) =
+                tauri::async_runtime::block_on(transform_cmd::refresh_shortcuts(&app_clone))
+            {
+                log::warn!("Transform shortcuts unavailable: {}", e);
+            }
             log::info!("Global shortcut re-registered after settings window closed");
         }
     });
@@ -633,9 +756,36 @@ fn setup_global_shortcut(app: &tauri::AppHandle) -> Result<(), Box<dyn std::erro
         })?;
 
     log::info!("Global shortcut registered: {}", shortcut);
+
+    if let Err(e) = tauri::async_runtime::block_on(transform_cmd::refresh_shortcuts(app)) {
+        log::warn!("Transform shortcuts unavailable: {}", e);
+    }
     Ok(())
 }
 
+fn setup_deep_links(app: &tauri::AppHandle) {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    // Installers register the scheme; this covers AppImages and dev builds
+    #[cfg(any(target_os = "linux", all(debug_assertions, target_os = "windows")))]
+    if let Err(e) = app.deep_link().register_all() {
+        log::warn!("Failed to register recopy:// links: {}", e);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        let links: Vec<String> = event.urls().iter().map(|url| url.to_string()).collect();
+        deep_link::open::open_links(&handle, links.iter().map(String::as_str));
+    });
+
+    // Windows and Linux pass the link of the first launch as an argument
+    #[cfg(not(target_os = "macos"))]
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        let links: Vec<String> = urls.iter().map(|url| url.to_string()).collect();
+        deep_link::open::open_links(app, links.iter().map(String::as_str));
+    }
+}
+
 fn setup_blur_hide(app: &tauri::AppHandle) {
     // On macOS with NSPanel, focus events come via tauri://blur emitted
     // by the panel event handler (window_did_resign_key).
@@ -768,14 +918,15 @@ fn setup_blur_hide(app: &tauri::AppHandle) {
 fn start_clipboard_monitor(app: tauri::AppHandle) {
     let app_clone = app.clone();
 
-    // Bounded channel: listener only enqueues, worker processes sequentially
-    let (tx, mut rx) = tokio::sync::mpsc::channel::<()>(32);
+    // Bounded channel: listener only enqueues, worker processes sequentially.
+    // Each event carries the frontmost app probed at the time of the change.
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Option<platform::FrontmostApp>>(32);
 
     // Single worker — processes clipboard events one at a time
     let app_worker = app_clone.clone();
     tauri::async_runtime::spawn(async move {
-        while rx.recv().await.is_some() {
-            handle_clipboard_event(&app_worker).await;
+        while let Some(source) = rx.recv().await {
+            handle_clipboard_event(&app_worker, source).await;
         }
     });
 
@@ -791,28 +942,158 @@ fn start_clipboard_monitor(app: tauri::AppHandle) {
         app_clone.listen(
             "plugin:clipboard-x://clipboard_changed",
             move |_event: tauri::Event| {
+                // Probe immediately: by the time the worker runs the user may have switched apps
+                let source = platform::platform_frontmost_app();
                 // try_send: drops event if channel is full (backpressure)
-                let _ = tx.try_send(());
+                let _ = tx.try_send(source);
             },
         );
     });
 }
 
-async fn handle_clipboard_event(app: &tauri::AppHandle) {
+/// Interval between purges of items whose `expires_at` has passed.
+const EXPIRY_PURGE_INTERVAL_SECS: u64 = 5;
+
+fn start_expiry_purge(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(EXPIRY_PURGE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let Some(pool) = app.try_state::<db::DbPool>() else {
+                continue;
+            };
+
+            // Collect image paths before deleting rows
+            let image_paths = db::queries::get_expired_image_paths(&pool.0)
+                .await
+                .unwrap_or_default();
+
+            match db::queries::purge_expired_items(&pool.0).await {
+                Ok(deleted) if deleted > 0 => {
+                    log::info!("Purged {} expired items", deleted);
+                    for path in image_paths {
+                        let _ = tokio::fs::remove_file(&path).await;
+                    }
+                    // Its own event: clipboard-changed listeners expect an item id
+                    let _ = app.emit("items-purged", serde_json::json!({ "count": deleted }));
+                }
+                Err(e) => log::warn!("Expired item purge failed: {}", e),
+                _ => {}
+            }
+        }
+    });
+}
+
+/// Interval between purges of items trashed longer than `trash_retention_days`.
+const TRASH_PURGE_INTERVAL_SECS: u64 = 60 * 60;
+
+fn start_trash_purge(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(TRASH_PURGE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let Some(pool) = app.try_state::<db::DbPool>() else {
+                continue;
+            };
+            match trash_cmd::purge_trash_by_retention(&pool.0).await {
+                Ok(deleted) if deleted > 0 => log::info!("Purged {} trashed items", deleted),
+                Err(e) => log::warn!("Trash purge failed: {}", e),
+                _ => {}
+            }
+        }
+    });
+}
+
+/// Interval between checks whether a scheduled backup is due.
+const BACKUP_CHECK_INTERVAL_SECS: u64 = 10 * 60;
+
+fn start_backups(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(BACKUP_CHECK_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            backup_cmd::run_scheduled_backup(&app).await;
+        }
+    });
+}
+
+/// Interval between LAN sync rounds.
+const SYNC_INTERVAL_SECS: u64 = 60;
+
+fn start_sync(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(SYNC_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            sync_cmd::run_scheduled_sync(&app).await;
+        }
+    });
+}
+
+fn start_http_api(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        http_cmd::init_http_api(&app).await;
+    });
+}
+
+/// Interval between auto-lock checks.
+const AUTO_LOCK_CHECK_SECS: u64 = 15;
+
+fn start_auto_lock(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let interval = std::time::Duration::from_secs(AUTO_LOCK_CHECK_SECS);
+        let mut last = std::time::SystemTime::now();
+        loop {
+            tokio::time::sleep(interval).await;
+            // The timer doesn't advance while the machine sleeps, so a wall-clock
+            // gap much longer than the interval means we just woke up
+            let now = std::time::SystemTime::now();
+            let slept = now.duration_since(last).is_ok_and(|gap| gap > interval * 4);
+            last = now;
+            lock_cmd::auto_lock_tick(&app, slept).await;
+        }
+    });
+}
+
+async fn handle_clipboard_event(app: &tauri::AppHandle, source: Option<platform::FrontmostApp>) {
     // Skip self-initiated clipboard writes to avoid redundant processing
     if SKIP_NEXT_CLIPBOARD_CHANGE.swap(false, Ordering::SeqCst) {
         log::info!("Skipping self-initiated clipboard change");
         return;
     }
 
+    // Drop changes from applications on the ignore list before reading the contents
+    let source = source.unwrap_or_default();
+    if let Some(pool) = app.try_state::<db::DbPool>() {
+        if ignore_cmd::is_source_ignored(&pool.0, &source).await {
+            log::info!("Skipping clipboard change from ignored app: {}", source.id);
+            return;
+        }
+    }
+
+    // Honor password-manager markers before reading the contents
+    let decision = capture_decision(app).await;
+    if decision == clipboard::CaptureDecision::Skip {
+        log::info!("Skipping clipboard change marked concealed/transient");
+        return;
+    }
+
     // Determine content type and read clipboard
-    let (content_type, content, plain_text, rich_content, file_path, file_name) =
+    let (content_type, content, plain_text, rich_content, files) =
         match extract_clipboard_content(app).await {
             Some(data) => data,
             None => return,
         };
 
-    log::info!("Clipboard changed: type={}", content_type.as_str());
+    log::info!(
+        "Clipboard changed: type={} source={}",
+        content_type.as_str(),
+        source.id
+    );
 
     // Process and store
     let result = clip_cmd::process_clipboard_change(
@@ -821,22 +1102,52 @@ async fn handle_clipboard_event(app: &tauri::AppHandle) {
         content,
         plain_text,
         rich_content,
-        file_path,
-        file_name,
-        String::new(), // source_app - TODO: M1-8
-        String::new(), // source_app_name - TODO: M1-8
+        files,
+        source,
+        decision,
     )
     .await;
 
     match result {
         Ok(Some(id)) => {
             let _ = app.emit("clipboard-changed", serde_json::json!({ "id": id }));
+            queue_cmd::on_item_captured(app, &id);
         }
         Ok(None) => {}
         Err(e) => log::error!("Failed to process clipboard: {}", e),
     }
 }
 
+/// Probe the clipboard for password-manager markers and apply the `concealed_policy` setting.
+async fn capture_decision(app: &tauri::AppHandle) -> clipboard::CaptureDecision {
+    let markers = tokio::task::spawn_blocking(platform::platform_clipboard_markers)
+        .await
+        .unwrap_or_default();
+    if markers == clipboard::ClipboardMarkers::default() {
+        return clipboard::CaptureDecision::Record;
+    }
+
+    let (policy, expire_secs) = match app.try_state::<db::DbPool>() {
+        Some(pool) => {
+            let policy = db::queries::get_setting(&pool.0, "concealed_policy")
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| "skip".to_string());
+            let secs = db::queries::get_setting(&pool.0, "concealed_expire_seconds")
+                .await
+                .ok()
+                .flatten()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(clipboard::DEFAULT_CONCEALED_EXPIRE_SECS);
+            (policy, secs)
+        }
+        None => ("skip".to_string(), 0),
+    };
+
+    clipboard::decide_capture(markers, &policy, expire_secs)
+}
+
 async fn extract_clipboard_content(
     app: &tauri::AppHandle,
 ) -> Option<(
@@ -844,8 +1155,7 @@ async fn extract_clipboard_content(
     Vec<u8>,
     Option<String>,
     Option<Vec<u8>>,
-    Option<String>,
-    Option<String>,
+    Vec<db::models::ItemFile>,
 )> {
     // Read max item size from DB settings
     let max_size_mb = if let Some(pool) = app.try_state::<db::DbPool>() {
@@ -858,51 +1168,76 @@ async fn extract_clipboard_content(
         clipboard::DEFAULT_MAX_ITEM_SIZE_MB
     };
 
-    // Try files first
+    // Try files first: keep every copied path (files and folders)
     if let Ok(true) = tauri_plugin_clipboard_x::has_files().await {
         if let Ok(file_result) = tauri_plugin_clipboard_x::read_files().await {
-            if let Some(first) = file_result.paths.first() {
-                let path = std::path::Path::new(first);
-
-                // Skip directories
-                if path.is_dir() {
-                    log::info!("Skipping directory: {}", first);
+            if !file_result.paths.is_empty() {
+                let paths = file_result.paths.clone();
+                let files = tokio::task::spawn_blocking(move || {
+                    paths
+                        .iter()
+                        .map(|p| {
+                            let path = std::path::Path::new(p);
+                            let is_dir = path.is_dir();
+                            let size = if is_dir {
+                                clipboard::dir_size(path)
+                            } else {
+                                std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+                            };
+                            db::models::ItemFile {
+                                path: p.clone(),
+                                name: path
+                                    .file_name()
+                                    .map(|n| n.to_string_lossy().to_string())
+                                    .unwrap_or_else(|| p.clone()),
+                                size: size as i64,
+                                is_dir,
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .await
+                .ok()?;
+
+                // Only regular files count towards the size limit; only paths are stored
+                let file_bytes: i64 = files.iter().filter(|f| !f.is_dir).map(|f| f.size).sum();
+                if clipboard::exceeds_size_limit(file_bytes as usize, max_size_mb) {
+                    log::info!(
+                        "Skipping large files: {} paths ({}B)",
+                        files.len(),
+                        file_bytes
+                    );
                     return None;
                 }
 
-                // Skip files larger than size limit
-                if let Ok(meta) = tokio::fs::metadata(path).await {
-                    if clipboard::exceeds_size_limit(meta.len() as usize, max_size_mb) {
-                        log::info!("Skipping large file: {} ({}B)", first, meta.len());
-                        return None;
-                    }
-                }
-
-                let file_name = path.file_name().map(|n| n.to_string_lossy().to_string());
-                let content = first.as_bytes().to_vec();
-                return Some((
-                    ContentType::File,
-                    content,
-                    Some(first.clone()),
-                    None,
-                    Some(first.clone()),
-                    file_name,
-                ));
+                // One path per line: doubles as the paste-as-text form and the dedup key
+                let joined = file_result.paths.join("\n");
+                let content = joined.as_bytes().to_vec();
+                return Some((ContentType::File, content, Some(joined), None, files));
             }
         }
     }
 
-    // Try image
+    // Try image: prefer the encoded bytes the source app put on the clipboard
+    // (JPEG, TIFF, WebP, ...); the plugin re-encodes everything to PNG
     if let Ok(true) = tauri_plugin_clipboard_x::has_image().await {
-        if let Ok(img_result) = tauri_plugin_clipboard_x::read_image(app.clone(), None).await {
-            // Read the saved image file
-            if let Ok(img_data) = tokio::fs::read(&img_result.path).await {
-                if clipboard::exceeds_size_limit(img_data.len(), max_size_mb) {
-                    log::info!("Skipping large image: {}B", img_data.len());
-                    return None;
-                }
-                return Some((ContentType::Image, img_data, None, None, None, None));
+        let original = tokio::task::spawn_blocking(platform::platform_read_image_data)
+            .await
+            .ok()
+            .flatten();
+        let img_data = match original {
+            Some(data) => Some(data),
+            None => match tauri_plugin_clipboard_x::read_image(app.clone(), None).await {
+                Ok(img_result) => tokio::fs::read(&img_result.path).await.ok(),
+                Err(_) => None,
+            },
+        };
+        if let Some(img_data) = img_data {
+            if clipboard::exceeds_size_limit(img_data.len(), max_size_mb) {
+                log::info!("Skipping large image: {}B", img_data.len());
+                return None;
             }
+            return Some((ContentType::Image, img_data, None, None, Vec::new()));
         }
     }
 
@@ -919,8 +1254,7 @@ async fn extract_clipboard_content(
                 content_bytes,
                 Some(plain),
                 Some(html_bytes),
-                None,
-                None,
+                Vec::new(),
             ));
         }
     }
@@ -939,8 +1273,7 @@ async fn extract_clipboard_content(
                             content,
                             Some(trimmed.to_string()),
                             None,
-                            None,
-                            None,
+                            Vec::new(),
                         ));
                     }
                 }
@@ -953,8 +1286,7 @@ async fn extract_clipboard_content(
                     content,
                     Some(text),
                     None,
-                    None,
-                    None,
+                    Vec::new(),
                 ));
             }
         }
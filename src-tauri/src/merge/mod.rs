@@ -0,0 +1,203 @@
+//! Merging several history items into one paste: their text joined with a
+//! separator, as a Markdown list, or as a JSON array. When any of the items
+//! is rich text an HTML version is built too, so formatting survives in apps
+//! that accept it.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    Newline,
+    Comma,
+    Tab,
+    MarkdownList,
+    JsonArray,
+}
+
+impl Format {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Format::Newline => "newline",
+            Format::Comma => "comma",
+            Format::Tab => "tab",
+            Format::MarkdownList => "markdown_list",
+            Format::JsonArray => "json_array",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "newline" => Some(Format::Newline),
+            "comma" => Some(Format::Comma),
+            "tab" => Some(Format::Tab),
+            "markdown_list" => Some(Format::MarkdownList),
+            "json_array" => Some(Format::JsonArray),
+            _ => None,
+        }
+    }
+}
+
+/// One item to merge: its plain text and, for rich text, its HTML.
+pub struct Part<'a> {
+    pub text: &'a str,
+    pub html: Option<&'a str>,
+}
+
+/// The merged content, ready to write to the clipboard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merged {
+    pub text: String,
+    /// None unless a part had HTML. JSON arrays are data, so never have it.
+    pub html: Option<String>,
+}
+
+pub fn merge(parts: &[Part], format: Format) -> Merged {
+    let texts: Vec<&str> = parts.iter().map(|p| p.text).collect();
+    let text = match format {
+        Format::Newline => texts.join("\n"),
+        Format::Comma => texts.join(", "),
+        Format::Tab => texts.join("\t"),
+        Format::MarkdownList => texts
+            .iter()
+            .map(|t| markdown_item(t))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Format::JsonArray => serde_json::to_string_pretty(&texts).unwrap_or_default(),
+    };
+
+    let has_html = parts.iter().any(|p| p.html.is_some());
+    let html = (has_html && format != Format::JsonArray).then(|| {
+        let fragments: Vec<String> = parts
+            .iter()
+            .map(|p| match p.html {
+                Some(html) => body_of(html).to_string(),
+                None => escape_html(p.text).replace('\n', "<br>"),
+            })
+            .collect();
+        match format {
+            Format::Comma => fragments.join(", "),
+            // A table row, so spreadsheets paste one item per cell
+            Format::Tab => format!(
+                "<table><tr>{}</tr></table>",
+                fragments
+                    .iter()
+                    .map(|f| format!("<td>{}</td>", f))
+                    .collect::<String>()
+            ),
+            Format::MarkdownList => format!(
+                "<ul>{}</ul>",
+                fragments
+                    .iter()
+                    .map(|f| format!("<li>{}</li>", f))
+                    .collect::<String>()
+            ),
+            _ => fragments
+                .iter()
+                .map(|f| format!("<div>{}</div>", f))
+                .collect(),
+        }
+    });
+
+    Merged { text, html }
+}
+
+/// A Markdown list entry; continuation lines are indented to stay in it.
+fn markdown_item(text: &str) -> String {
+    let text = text.trim_end();
+    let mut lines = text.lines();
+    let mut item = format!("- {}", lines.next().unwrap_or_default());
+    for line in lines {
+        item.push('\n');
+        if !line.is_empty() {
+            item.push_str("  ");
+            item.push_str(line);
+        }
+    }
+    item
+}
+
+/// The contents of an HTML document's body, or the whole string if it is
+/// already a fragment.
+fn body_of(html: &str) -> &str {
+    let lower = html.to_ascii_lowercase();
+    let Some(open) = lower.find("<body") else {
+        return html;
+    };
+    let Some(start) = lower[open..].find('>').map(|i| open + i + 1) else {
+        return html;
+    };
+    let end = lower[start..]
+        .find("</body")
+        .map_or(html.len(), |i| start + i);
+    &html[start..end]
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain<'a>(texts: &[&'a str]) -> Vec<Part<'a>> {
+        texts.iter().map(|text| Part { text, html: None }).collect()
+    }
+
+    #[test]
+    fn test_merge_text() {
+        let parts = plain(&["one", "two words", "three"]);
+        assert_eq!(merge(&parts, Format::Newline).text, "one\ntwo words\nthree");
+        assert_eq!(merge(&parts, Format::Comma).text, "one, two words, three");
+        assert_eq!(merge(&parts, Format::Tab).text, "one\ttwo words\tthree");
+        assert_eq!(merge(&parts, Format::Newline).html, None);
+
+        let parts = plain(&["first\nsecond\n\nlast\n", "b"]);
+        assert_eq!(
+            merge(&parts, Format::MarkdownList).text,
+            "- first\n  second\n\n  last\n- b"
+        );
+
+        let parts = plain(&["say \"hi\"", "a\\b\n"]);
+        let json = merge(&parts, Format::JsonArray).text;
+        let back: Vec<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, vec!["say \"hi\"".to_string(), "a\\b\n".to_string()]);
+
+        for name in ["newline", "comma", "tab", "markdown_list", "json_array"] {
+            assert_eq!(Format::from_str(name).unwrap().as_str(), name);
+        }
+        assert_eq!(Format::from_str("csv"), None);
+    }
+
+    #[test]
+    fn test_merge_html() {
+        let parts = vec![
+            Part {
+                text: "Bold",
+                html: Some("<html><BODY class=\"x\"><b>Bold</b></BODY></html>"),
+            },
+            Part {
+                text: "a < b\nc",
+                html: None,
+            },
+        ];
+        assert_eq!(
+            merge(&parts, Format::Newline).html.as_deref(),
+            Some("<div><b>Bold</b></div><div>a &lt; b<br>c</div>")
+        );
+        assert_eq!(
+            merge(&parts, Format::MarkdownList).html.as_deref(),
+            Some("<ul><li><b>Bold</b></li><li>a &lt; b<br>c</li></ul>")
+        );
+        assert_eq!(
+            merge(&parts, Format::Tab).html.as_deref(),
+            Some("<table><tr><td><b>Bold</b></td><td>a &lt; b<br>c</td></tr></table>")
+        );
+        assert_eq!(merge(&parts, Format::JsonArray).html, None);
+
+        assert_eq!(body_of("<i>x</i>"), "<i>x</i>");
+        assert_eq!(body_of("<body><i>x</i>"), "<i>x</i>");
+    }
+}
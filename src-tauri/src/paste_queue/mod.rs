@@ -0,0 +1,207 @@
+//! The paste queue: while collecting, every captured item is appended, and the
+//! paste-next shortcut pastes entries one at a time, first-copied first
+//! (FIFO) or last-copied first (LIFO). Handy for filling forms: copy five
+//! values, then paste them in order.
+
+use std::collections::VecDeque;
+
+/// Entries kept at most; older captures are dropped first.
+pub const MAX_ENTRIES: usize = 200;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Order {
+    #[default]
+    Fifo,
+    Lifo,
+}
+
+impl Order {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Order::Fifo => "fifo",
+            Order::Lifo => "lifo",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "fifo" => Some(Order::Fifo),
+            "lifo" => Some(Order::Lifo),
+            _ => None,
+        }
+    }
+}
+
+/// Item ids in the order they were captured. The same item may be queued
+/// more than once, e.g. when a value is copied twice for two fields.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PasteQueue {
+    entries: VecDeque<String>,
+}
+
+impl PasteQueue {
+    pub const fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Append a captured item.
+    pub fn push(&mut self, id: String) {
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(id);
+    }
+
+    /// Take the entry to paste next.
+    pub fn next(&mut self, order: Order) -> Option<String> {
+        match order {
+            Order::Fifo => self.entries.pop_front(),
+            Order::Lifo => self.entries.pop_back(),
+        }
+    }
+
+    /// Put an entry back so it is pasted next, e.g. after a failed paste.
+    pub fn push_next(&mut self, order: Order, id: String) {
+        match order {
+            Order::Fifo => self.entries.push_front(id),
+            Order::Lifo => self.entries.push_back(id),
+        }
+    }
+
+    /// The entries in the order they will be pasted.
+    pub fn upcoming(&self, order: Order) -> Vec<String> {
+        match order {
+            Order::Fifo => self.entries.iter().cloned().collect(),
+            Order::Lifo => self.entries.iter().rev().cloned().collect(),
+        }
+    }
+
+    /// Rearrange the queue so it pastes `ids` in the given order. `ids` must
+    /// hold exactly the queued entries, so a stale list can't drop or add any.
+    pub fn reorder(&mut self, order: Order, ids: &[String]) -> Result<(), String> {
+        let mut current: Vec<&String> = self.entries.iter().collect();
+        let mut wanted: Vec<&String> = ids.iter().collect();
+        current.sort();
+        wanted.sort();
+        if current != wanted {
+            return Err("The paste queue has changed; reload it and try again".to_string());
+        }
+        self.entries = ids.iter().cloned().collect();
+        if order == Order::Lifo {
+            self.entries.make_contiguous().reverse();
+        }
+        Ok(())
+    }
+
+    /// Remove the first upcoming entry for `id`. Returns false if not queued.
+    pub fn remove(&mut self, order: Order, id: &str) -> bool {
+        let position = match order {
+            Order::Fifo => self.entries.iter().position(|e| e == id),
+            Order::Lifo => self.entries.iter().rposition(|e| e == id),
+        };
+        position
+            .and_then(|index| self.entries.remove(index))
+            .is_some()
+    }
+
+    /// Drop entries whose item is gone (deleted or trashed).
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        self.entries.retain(|id| keep(id));
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(ids: &[&str]) -> PasteQueue {
+        let mut queue = PasteQueue::new();
+        for id in ids {
+            queue.push(id.to_string());
+        }
+        queue
+    }
+
+    fn strings(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_next_in_order() {
+        let mut fifo = queue(&["a", "b", "c"]);
+        assert_eq!(fifo.upcoming(Order::Fifo), strings(&["a", "b", "c"]));
+        assert_eq!(fifo.next(Order::Fifo).as_deref(), Some("a"));
+        assert_eq!(fifo.next(Order::Fifo).as_deref(), Some("b"));
+
+        let mut lifo = queue(&["a", "b", "c"]);
+        assert_eq!(lifo.upcoming(Order::Lifo), strings(&["c", "b", "a"]));
+        assert_eq!(lifo.next(Order::Lifo).as_deref(), Some("c"));
+        assert_eq!(lifo.len(), 2);
+
+        // A failed paste puts the entry back at the head
+        lifo.push_next(Order::Lifo, "c".to_string());
+        assert_eq!(lifo.upcoming(Order::Lifo), strings(&["c", "b", "a"]));
+        fifo.push_next(Order::Fifo, "b".to_string());
+        assert_eq!(fifo.upcoming(Order::Fifo), strings(&["b", "c"]));
+
+        let mut empty = PasteQueue::new();
+        assert_eq!(empty.next(Order::Fifo), None);
+
+        assert_eq!(Order::from_str("lifo"), Some(Order::Lifo));
+        assert_eq!(Order::from_str(Order::Fifo.as_str()), Some(Order::Fifo));
+        assert_eq!(Order::from_str("stack"), None);
+    }
+
+    #[test]
+    fn test_reorder_and_remove() {
+        let mut q = queue(&["a", "b", "a", "c"]);
+        q.reorder(Order::Fifo, &strings(&["c", "a", "b", "a"]))
+            .unwrap();
+        assert_eq!(q.upcoming(Order::Fifo), strings(&["c", "a", "b", "a"]));
+
+        // LIFO lists are in paste order too
+        q.reorder(Order::Lifo, &strings(&["a", "b", "c", "a"]))
+            .unwrap();
+        assert_eq!(q.upcoming(Order::Lifo), strings(&["a", "b", "c", "a"]));
+        assert_eq!(q.next(Order::Lifo).as_deref(), Some("a"));
+
+        // Stale lists are rejected
+        assert!(q.reorder(Order::Fifo, &strings(&["a", "b"])).is_err());
+        assert!(q.reorder(Order::Fifo, &strings(&["a", "b", "x"])).is_err());
+
+        assert!(q.remove(Order::Lifo, "b"));
+        assert!(!q.remove(Order::Lifo, "b"));
+        assert_eq!(q.upcoming(Order::Lifo), strings(&["c", "a"]));
+
+        q.retain(|id| id != "a");
+        assert_eq!(q.upcoming(Order::Fifo), strings(&["c"]));
+
+        q.clear();
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    fn test_oldest_dropped_when_full() {
+        let mut q = PasteQueue::new();
+        for i in 0..=MAX_ENTRIES {
+            q.push(i.to_string());
+        }
+        assert_eq!(q.len(), MAX_ENTRIES);
+        assert_eq!(q.next(Order::Fifo).as_deref(), Some("1"));
+    }
+}
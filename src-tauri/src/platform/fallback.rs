@@ -114,3 +114,381 @@ pub fn platform_hide_hud(app: &tauri::AppHandle) {
         let _ = window.hide();
     }
 }
+
+/// Identify the application owning the active X11 window (`_NET_ACTIVE_WINDOW`).
+/// `id` is the process executable (via `_NET_WM_PID`), falling back to the
+/// WM_CLASS class; `name` is the WM_CLASS class (e.g. `firefox`, `Code`);
+/// `window_title` is `_NET_WM_NAME`.
+/// Returns None on Wayland sessions without XWayland or when no window is active.
+pub fn platform_frontmost_app() -> Option<super::FrontmostApp> {
+    with_x11_session(frontmost_app)
+}
+
+/// The connection used for source-app detection and clipboard reads, opened
+/// on first use and kept so every clipboard event doesn't pay for a new
+/// connection, window and atom lookups.
+struct X11Session {
+    conn: x11rb::rust_connection::RustConnection,
+    root: u32,
+    active_atom: u32,
+    pid_atom: u32,
+    name_atom: u32,
+    utf8_atom: u32,
+    /// Unmapped window that receives selection conversions.
+    requestor: u32,
+    clipboard_atom: u32,
+    targets_atom: u32,
+    incr_atom: u32,
+    hint_atom: u32,
+    selection_atom: u32,
+    /// `IMAGE_TARGETS`, interned.
+    image_atoms: Vec<u32>,
+}
+
+static X11_SESSION: std::sync::Mutex<Option<X11Session>> = std::sync::Mutex::new(None);
+
+/// Run `f` on the shared session, opening it if needed. `f` returns Err when
+/// the connection is broken (e.g. the X server restarted); the session is
+/// then dropped and reopened next time.
+fn with_x11_session<T>(f: impl FnOnce(&X11Session) -> Result<Option<T>, ()>) -> Option<T> {
+    let mut session = X11_SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    if session.is_none() {
+        *session = X11Session::open();
+    }
+    match f(session.as_ref()?) {
+        Ok(value) => value,
+        Err(()) => {
+            *session = None;
+            None
+        }
+    }
+}
+
+impl X11Session {
+    fn open() -> Option<Self> {
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::{ConnectionExt, EventMask};
+
+        let (conn, screen_num) = x11rb::connect(None).ok()?;
+        let root = conn.setup().roots.get(screen_num)?.root;
+        let requestor = create_requestor_window(&conn, screen_num, EventMask::PROPERTY_CHANGE)?;
+        let intern = |name: &[u8]| -> Option<u32> {
+            Some(conn.intern_atom(false, name).ok()?.reply().ok()?.atom)
+        };
+        Some(Self {
+            active_atom: intern(b"_NET_ACTIVE_WINDOW")?,
+            pid_atom: intern(b"_NET_WM_PID")?,
+            name_atom: intern(b"_NET_WM_NAME")?,
+            utf8_atom: intern(b"UTF8_STRING")?,
+            clipboard_atom: intern(b"CLIPBOARD")?,
+            targets_atom: intern(b"TARGETS")?,
+            incr_atom: intern(b"INCR")?,
+            hint_atom: intern(b"x-kde-passwordManagerHint")?,
+            selection_atom: intern(b"RECOPY_SELECTION")?,
+            image_atoms: IMAGE_TARGETS
+                .iter()
+                .map(|name| intern(name))
+                .collect::<Option<_>>()?,
+            requestor,
+            root,
+            conn,
+        })
+    }
+
+    /// Ask the CLIPBOARD owner to convert to `target` and wait for its answer.
+    /// Ok(false) if it refused or didn't answer in time.
+    fn convert(&self, target: u32) -> Result<bool, ()> {
+        use std::time::{Duration, Instant};
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::ConnectionExt;
+        use x11rb::protocol::Event;
+
+        // Drop events left over from earlier reads, e.g. a late answer to one
+        // that timed out
+        while self.conn.poll_for_event().map_err(|_| ())?.is_some() {}
+        self.conn
+            .convert_selection(
+                self.requestor,
+                self.clipboard_atom,
+                target,
+                self.selection_atom,
+                x11rb::CURRENT_TIME,
+            )
+            .map_err(|_| ())?;
+        self.conn.flush().map_err(|_| ())?;
+
+        let deadline = Instant::now() + Duration::from_millis(200);
+        loop {
+            match self.conn.poll_for_event().map_err(|_| ())? {
+                Some(Event::SelectionNotify(ev))
+                    if ev.requestor == self.requestor && ev.target == target =>
+                {
+                    return Ok(ev.property != x11rb::NONE);
+                }
+                Some(_) => {}
+                None if Instant::now() >= deadline => return Ok(false),
+                None => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+    }
+
+    /// Read and delete the converted value. Deleting it also asks the owner
+    /// for the next chunk of an INCR transfer.
+    fn take_property(
+        &self,
+        max_words: u32,
+    ) -> Result<Option<x11rb::protocol::xproto::GetPropertyReply>, ()> {
+        use x11rb::errors::ReplyError;
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+        let reply = self
+            .conn
+            .get_property(
+                true,
+                self.requestor,
+                self.selection_atom,
+                AtomEnum::ANY,
+                0,
+                max_words,
+            )
+            .map_err(|_| ())?
+            .reply();
+        match reply {
+            Ok(reply) => Ok(Some(reply)),
+            Err(ReplyError::ConnectionError(_)) => Err(()),
+            Err(ReplyError::X11Error(_)) => Ok(None),
+        }
+    }
+}
+
+/// Err if the connection is broken and must be reopened.
+fn frontmost_app(session: &X11Session) -> Result<Option<super::FrontmostApp>, ()> {
+    use x11rb::errors::ReplyError;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+    let X11Session {
+        conn,
+        root,
+        active_atom,
+        pid_atom,
+        name_atom,
+        utf8_atom,
+        ..
+    } = session;
+    let active = conn
+        .get_property(false, *root, *active_atom, AtomEnum::WINDOW, 0, 1)
+        .map_err(|_| ())?
+        .reply();
+    let reply = match active {
+        Ok(reply) => reply,
+        Err(ReplyError::ConnectionError(_)) => return Err(()),
+        Err(ReplyError::X11Error(_)) => return Ok(None),
+    };
+    Ok(window_app(conn, reply, *pid_atom, *name_atom, *utf8_atom))
+}
+
+fn window_app(
+    conn: &x11rb::rust_connection::RustConnection,
+    active: x11rb::protocol::xproto::GetPropertyReply,
+    pid_atom: u32,
+    name_atom: u32,
+    utf8_atom: u32,
+) -> Option<super::FrontmostApp> {
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+    let window = active.value32()?.next()?;
+    if window == 0 {
+        return None;
+    }
+
+    // WM_CLASS is two NUL-terminated strings: instance name, then class name
+    let class = conn
+        .get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)
+        .ok()
+        .and_then(|c| c.reply().ok())
+        .map(|reply| {
+            let parts: Vec<String> = reply
+                .value
+                .split(|b| *b == 0)
+                .filter(|p| !p.is_empty())
+                .map(|p| String::from_utf8_lossy(p).to_string())
+                .collect();
+            parts.last().cloned().unwrap_or_default()
+        })
+        .unwrap_or_default();
+
+    let exe_path = conn
+        .get_property(false, window, pid_atom, AtomEnum::CARDINAL, 0, 1)
+        .ok()
+        .and_then(|c| c.reply().ok())
+        .and_then(|reply| reply.value32().and_then(|mut v| v.next()))
+        .and_then(|pid| std::fs::read_link(format!("/proc/{}/exe", pid)).ok())
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let name = if class.is_empty() {
+        std::path::Path::new(&exe_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    } else {
+        class.clone()
+    };
+    let window_title = conn
+        .get_property(false, window, name_atom, utf8_atom, 0, 1024)
+        .ok()
+        .and_then(|c| c.reply().ok())
+        .map(|reply| String::from_utf8_lossy(&reply.value).to_string())
+        .unwrap_or_default();
+
+    let id = if exe_path.is_empty() {
+        class
+    } else {
+        exe_path.clone()
+    };
+    if id.is_empty() && name.is_empty() {
+        return None;
+    }
+    Some(super::FrontmostApp {
+        id,
+        name,
+        window_title,
+        executable: exe_path,
+    })
+}
+
+/// Read password-manager markers from the X11 CLIPBOARD selection.
+/// KDE/KeePassXC advertise `x-kde-passwordManagerHint` with the value `secret`.
+pub fn platform_clipboard_markers() -> crate::clipboard::ClipboardMarkers {
+    crate::clipboard::ClipboardMarkers {
+        concealed: read_kde_password_hint().unwrap_or(false),
+        transient: false,
+    }
+}
+
+/// Request the `x-kde-passwordManagerHint` target from the clipboard owner.
+/// Returns Some(false) when the owner doesn't offer it or doesn't answer in
+/// time, None if X11 is unavailable.
+fn read_kde_password_hint() -> Option<bool> {
+    with_x11_session(|session| {
+        if !session.convert(session.hint_atom)? {
+            return Ok(Some(false));
+        }
+        let Some(reply) = session.take_property(64)? else {
+            return Ok(Some(false));
+        };
+        // The value may end in a newline (<[u8]>::trim_ascii is above the MSRV)
+        let value = String::from_utf8_lossy(&reply.value);
+        Ok(Some(
+            value.trim_matches(|c: char| c.is_ascii_whitespace()) == "secret",
+        ))
+    })
+}
+
+/// Create an unmapped window to receive selection conversions.
+/// It goes away with the connection.
+fn create_requestor_window(
+    conn: &x11rb::rust_connection::RustConnection,
+    screen_num: usize,
+    event_mask: x11rb::protocol::xproto::EventMask,
+) -> Option<u32> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{ConnectionExt, CreateWindowAux, WindowClass};
+
+    let screen = conn.setup().roots.get(screen_num)?;
+    let window = conn.generate_id().ok()?;
+    conn.create_window(
+        x11rb::COPY_DEPTH_FROM_PARENT,
+        window,
+        screen.root,
+        0,
+        0,
+        1,
+        1,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        screen.root_visual,
+        &CreateWindowAux::new().event_mask(event_mask),
+    )
+    .ok()?;
+    Some(window)
+}
+
+/// Image targets requested from the clipboard owner, in preference order.
+/// GTK and Qt advertise every format they can encode for any image, so the
+/// offered list says nothing about the original; PNG comes first to avoid a
+/// lossy re-encode, and the others are used when the owner offers only one.
+const IMAGE_TARGETS: [&[u8]; 6] = [
+    b"image/png",
+    b"image/jpeg",
+    b"image/webp",
+    b"image/gif",
+    b"image/tiff",
+    b"image/bmp",
+];
+
+/// Read the clipboard image in the encoding its owner offers, following the
+/// INCR protocol for large transfers.
+/// Returns None when X11 is unavailable or no image target is offered.
+pub fn platform_read_image_data() -> Option<Vec<u8>> {
+    use std::time::{Duration, Instant};
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::Property;
+    use x11rb::protocol::Event;
+
+    with_x11_session(|session| {
+        let whole = u32::MAX / 4;
+        if !session.convert(session.targets_atom)? {
+            return Ok(None);
+        }
+        let Some(offered) = session.take_property(whole)? else {
+            return Ok(None);
+        };
+        let Some(offered) = offered.value32().map(|atoms| atoms.collect::<Vec<u32>>()) else {
+            return Ok(None);
+        };
+        let Some(&target) = session
+            .image_atoms
+            .iter()
+            .find(|atom| offered.contains(atom))
+        else {
+            return Ok(None);
+        };
+
+        if !session.convert(target)? {
+            return Ok(None);
+        }
+        let Some(reply) = session.take_property(whole)? else {
+            return Ok(None);
+        };
+        if reply.type_ != session.incr_atom {
+            return Ok((!reply.value.is_empty()).then_some(reply.value));
+        }
+
+        // INCR: deleting the property (done by take_property) asks for the next
+        // chunk; a zero-length chunk ends the transfer
+        let mut data = Vec::new();
+        let mut deadline = Instant::now() + Duration::from_secs(1);
+        loop {
+            match session.conn.poll_for_event().map_err(|_| ())? {
+                Some(Event::PropertyNotify(ev))
+                    if ev.window == session.requestor
+                        && ev.atom == session.selection_atom
+                        && ev.state == Property::NEW_VALUE =>
+                {
+                    let Some(chunk) = session.take_property(whole)? else {
+                        return Ok(None);
+                    };
+                    if chunk.value.is_empty() {
+                        return Ok((!data.is_empty()).then_some(data));
+                    }
+                    data.extend_from_slice(&chunk.value);
+                    deadline = Instant::now() + Duration::from_secs(1);
+                }
+                Some(_) => {}
+                None if Instant::now() >= deadline => return Ok(None),
+                None => std::thread::sleep(Duration::from_millis(1)),
+            }
+        }
+    })
+}
@@ -235,25 +235,75 @@ pub fn platform_hide_preview(app: &tauri::AppHandle) {
     });
 }
 
+/// Pasteboard types of encoded images and the format each carries, in capture
+/// preference order: encoded originals first, TIFF last since AppKit
+/// synthesizes it for almost every image.
+const IMAGE_TYPES: [(&str, image::ImageFormat); 6] = [
+    ("public.jpeg", image::ImageFormat::Jpeg),
+    ("org.webmproject.webp", image::ImageFormat::WebP),
+    ("com.compuserve.gif", image::ImageFormat::Gif),
+    ("public.png", image::ImageFormat::Png),
+    ("com.microsoft.bmp", image::ImageFormat::Bmp),
+    ("public.tiff", image::ImageFormat::Tiff),
+];
+
+/// Read the pasteboard image in its original encoding.
+/// Returns None when the pasteboard holds no image type listed in IMAGE_TYPES.
+pub fn platform_read_image_data() -> Option<Vec<u8>> {
+    use objc2_app_kit::NSPasteboard;
+    use objc2_foundation::NSString;
+
+    let pasteboard = NSPasteboard::generalPasteboard();
+    IMAGE_TYPES.iter().find_map(|(uti, _)| {
+        let data = pasteboard.dataForType(&NSString::from_str(uti))?;
+        let bytes = data.to_vec();
+        (!bytes.is_empty()).then_some(bytes)
+    })
+}
+
 /// Write raw image bytes directly to NSPasteboard, bypassing decode→encode cycle.
-/// Reads the PNG file from disk and writes it directly as NSPasteboardTypePNG.
+/// The file is written under the pasteboard type matching its encoding; formats
+/// other than PNG/TIFF also get a PNG rendition for apps that only read those.
 pub fn platform_write_image_to_pasteboard(path: &str) -> Result<(), String> {
     let bytes = std::fs::read(path).map_err(|e| format!("Failed to read image file: {}", e))?;
+    let format = image::guess_format(&bytes).ok();
+    let uti = IMAGE_TYPES
+        .iter()
+        .find(|(_, f)| Some(*f) == format)
+        .map_or("public.png", |(uti, _)| *uti);
+    let png_rendition = if matches!(
+        format,
+        Some(image::ImageFormat::Png) | Some(image::ImageFormat::Tiff) | None
+    ) {
+        None
+    } else {
+        let img = image::load_from_memory(&bytes)
+            .map_err(|e| format!("Failed to decode image: {}", e))?;
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+        Some(png)
+    };
 
     use objc2::runtime::ProtocolObject;
     use objc2_app_kit::{NSPasteboard, NSPasteboardItem, NSPasteboardTypePNG, NSPasteboardWriting};
-    use objc2_foundation::{NSArray, NSData};
+    use objc2_foundation::{NSArray, NSData, NSString};
 
     unsafe {
         let pasteboard = NSPasteboard::generalPasteboard();
         pasteboard.clearContents();
 
-        let ns_data = NSData::with_bytes(&bytes);
         let item = NSPasteboardItem::new();
-        let set_ok = item.setData_forType(&ns_data, NSPasteboardTypePNG);
-        if !set_ok {
+        let ns_data = NSData::with_bytes(&bytes);
+        if !item.setData_forType(&ns_data, &NSString::from_str(uti)) {
             return Err("Failed to set pasteboard item data".to_string());
         }
+        if let Some(png) = png_rendition {
+            let png_data = NSData::with_bytes(&png);
+            if !item.setData_forType(&png_data, NSPasteboardTypePNG) {
+                return Err("Failed to set pasteboard PNG data".to_string());
+            }
+        }
 
         let proto_item: objc2::rc::Retained<ProtocolObject<dyn NSPasteboardWriting>> =
             ProtocolObject::from_retained(item);
@@ -266,6 +316,154 @@ pub fn platform_write_image_to_pasteboard(path: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Read password-manager markers from the general pasteboard (nspasteboard.org conventions).
+pub fn platform_clipboard_markers() -> crate::clipboard::ClipboardMarkers {
+    use objc2_app_kit::NSPasteboard;
+
+    let mut markers = crate::clipboard::ClipboardMarkers::default();
+    let Some(types) = NSPasteboard::generalPasteboard().types() else {
+        return markers;
+    };
+    for ty in types.iter() {
+        match ty.to_string().as_str() {
+            "org.nspasteboard.ConcealedType" => markers.concealed = true,
+            "org.nspasteboard.TransientType" => markers.transient = true,
+            _ => {}
+        }
+    }
+    markers
+}
+
+/// Identify the frontmost application via NSWorkspace.
+/// The NSPanel never activates Recopy, so this is the app the user copied from.
+pub fn platform_frontmost_app() -> Option<super::FrontmostApp> {
+    use objc2_app_kit::NSWorkspace;
+
+    let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+    let id = app
+        .bundleIdentifier()
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+    let name = app
+        .localizedName()
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+    if id.is_empty() && name.is_empty() {
+        return None;
+    }
+    let executable = app
+        .executableURL()
+        .and_then(|url| url.path())
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+    let window_title = focused_window_title(app.processIdentifier()).unwrap_or_default();
+    Some(super::FrontmostApp {
+        id,
+        name,
+        window_title,
+        executable,
+    })
+}
+
+/// Read the focused window title of a process via the Accessibility API.
+/// Returns None without prompting when Accessibility permission is not granted.
+fn focused_window_title(pid: i32) -> Option<String> {
+    use std::ffi::{c_char, c_void};
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+        fn AXUIElementCreateApplication(pid: i32) -> *mut c_void;
+        fn AXUIElementCopyAttributeValue(
+            element: *const c_void,
+            attribute: *const c_void,
+            value: *mut *mut c_void,
+        ) -> i32;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithCString(
+            alloc: *const c_void,
+            c_str: *const c_char,
+            encoding: u32,
+        ) -> *mut c_void;
+        fn CFStringGetCString(
+            string: *const c_void,
+            buffer: *mut c_char,
+            buffer_size: isize,
+            encoding: u32,
+        ) -> bool;
+        fn CFGetTypeID(cf: *const c_void) -> usize;
+        fn CFStringGetTypeID() -> usize;
+        fn CFRelease(cf: *const c_void);
+    }
+
+    const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+    const K_AX_ERROR_SUCCESS: i32 = 0;
+
+    if pid <= 0 {
+        return None;
+    }
+
+    unsafe {
+        if !AXIsProcessTrusted() {
+            return None;
+        }
+
+        let app_element = AXUIElementCreateApplication(pid);
+        if app_element.is_null() {
+            return None;
+        }
+
+        let focused_attr = CFStringCreateWithCString(
+            std::ptr::null(),
+            b"AXFocusedWindow\0".as_ptr() as _,
+            K_CF_STRING_ENCODING_UTF8,
+        );
+        let title_attr = CFStringCreateWithCString(
+            std::ptr::null(),
+            b"AXTitle\0".as_ptr() as _,
+            K_CF_STRING_ENCODING_UTF8,
+        );
+
+        let mut title = None;
+        let mut window: *mut c_void = std::ptr::null_mut();
+        if AXUIElementCopyAttributeValue(app_element, focused_attr, &mut window)
+            == K_AX_ERROR_SUCCESS
+            && !window.is_null()
+        {
+            let mut value: *mut c_void = std::ptr::null_mut();
+            if AXUIElementCopyAttributeValue(window, title_attr, &mut value) == K_AX_ERROR_SUCCESS
+                && !value.is_null()
+            {
+                if CFGetTypeID(value) == CFStringGetTypeID() {
+                    let mut buf = vec![0 as c_char; 1024];
+                    if CFStringGetCString(
+                        value,
+                        buf.as_mut_ptr(),
+                        buf.len() as isize,
+                        K_CF_STRING_ENCODING_UTF8,
+                    ) {
+                        title = Some(
+                            std::ffi::CStr::from_ptr(buf.as_ptr())
+                                .to_string_lossy()
+                                .to_string(),
+                        );
+                    }
+                }
+                CFRelease(value);
+            }
+            CFRelease(window);
+        }
+
+        CFRelease(title_attr);
+        CFRelease(focused_attr);
+        CFRelease(app_element);
+        title
+    }
+}
+
 // No-ops on macOS: NSPanel is non-activating, so preview never steals focus.
 // Called via `platform::*` re-export in lib.rs; compiler cannot trace glob re-exports.
 #[allow(dead_code)]
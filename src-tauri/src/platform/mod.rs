@@ -16,6 +16,19 @@ mod fallback;
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
 pub use fallback::*;
 
+/// Application that owned the foreground window when the clipboard changed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrontmostApp {
+    /// Stable identifier: bundle id on macOS, executable path on Windows/Linux.
+    pub id: String,
+    /// Human-readable application name for display and search.
+    pub name: String,
+    /// Title of the focused window (empty if unavailable, e.g. no Accessibility permission).
+    pub window_title: String,
+    /// Full path of the app's executable (empty if unavailable).
+    pub executable: String,
+}
+
 pub(crate) fn preview_top_inset_for_target(menu_h: f64, reserve_safe_top: bool) -> f64 {
     if reserve_safe_top {
         if menu_h > 0.0 {
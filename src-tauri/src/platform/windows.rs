@@ -22,6 +22,9 @@ mod win32 {
     pub const SW_HIDE: i32 = 0;
     pub const SW_SHOWNOACTIVATE: i32 = 4;
 
+    // OpenProcess access rights
+    pub const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+
     // SetWindowPos constants
     pub const HWND_TOPMOST: isize = -1;
     pub const SWP_NOMOVE: u32 = 0x0002;
@@ -77,6 +80,7 @@ mod win32 {
     pub const VK_C: u32 = 0x43;
     pub const VK_F: u32 = 0x46;
     pub const VK_V: u32 = 0x56;
+    pub const VK_LEFT: u32 = 0x25;
     pub const VK_OEM_COMMA: u32 = 0xBC;
 
     // WM_NCHITTEST constants
@@ -94,6 +98,7 @@ mod win32 {
 
     // SendInput constants
     pub const INPUT_KEYBOARD: u32 = 1;
+    pub const KEYEVENTF_EXTENDEDKEY: u32 = 0x0001;
     pub const KEYEVENTF_KEYUP: u32 = 0x0002;
 
     // Monitor detection types
@@ -230,6 +235,27 @@ mod win32 {
         pub fn LoadLibraryW(name: *const u16) -> isize;
         pub fn GetProcAddress(hmodule: isize, name: *const u8) -> usize;
         pub fn FreeLibrary(hmodule: isize) -> i32;
+        pub fn OpenProcess(desired_access: u32, inherit_handle: i32, pid: u32) -> isize;
+        pub fn QueryFullProcessImageNameW(
+            process: isize,
+            flags: u32,
+            exe_name: *mut u16,
+            size: *mut u32,
+        ) -> i32;
+        pub fn CloseHandle(handle: isize) -> i32;
+        pub fn GetWindowTextW(hwnd: HWND, text: *mut u16, max_count: i32) -> i32;
+        pub fn RegisterClipboardFormatW(name: *const u16) -> u32;
+        pub fn IsClipboardFormatAvailable(format: u32) -> i32;
+        pub fn OpenClipboard(hwnd_owner: HWND) -> i32;
+        pub fn CloseClipboard() -> i32;
+        pub fn EmptyClipboard() -> i32;
+        pub fn GetClipboardData(format: u32) -> isize;
+        pub fn SetClipboardData(format: u32, hmem: isize) -> isize;
+        pub fn GlobalAlloc(flags: u32, bytes: usize) -> isize;
+        pub fn GlobalFree(hmem: isize) -> isize;
+        pub fn GlobalSize(hmem: isize) -> usize;
+        pub fn GlobalLock(hmem: isize) -> *const u8;
+        pub fn GlobalUnlock(hmem: isize) -> i32;
     }
 }
 
@@ -323,6 +349,241 @@ pub fn is_recopy_foreground() -> bool {
 // Platform API (same interface as macos.rs / fallback.rs)
 // ---------------------------------------------------------------------------
 
+/// Identify the application owning the foreground window.
+/// `id` is the full executable path, `name` its file stem (e.g. `chrome`),
+/// `window_title` the foreground window's caption.
+pub fn platform_frontmost_app() -> Option<super::FrontmostApp> {
+    unsafe {
+        let fg = win32::GetForegroundWindow();
+        if fg == 0 {
+            return None;
+        }
+        let mut pid: u32 = 0;
+        win32::GetWindowThreadProcessId(fg, &mut pid);
+        if pid == 0 {
+            return None;
+        }
+
+        let process = win32::OpenProcess(win32::PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process == 0 {
+            return None;
+        }
+        let mut buf = [0u16; 1024];
+        let mut len = buf.len() as u32;
+        let ok = win32::QueryFullProcessImageNameW(process, 0, buf.as_mut_ptr(), &mut len);
+        win32::CloseHandle(process);
+        if ok == 0 {
+            return None;
+        }
+
+        let exe_path = String::from_utf16_lossy(&buf[..len as usize]);
+        let name = std::path::Path::new(&exe_path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut title_buf = [0u16; 512];
+        let title_len = win32::GetWindowTextW(fg, title_buf.as_mut_ptr(), title_buf.len() as i32);
+        let window_title = String::from_utf16_lossy(&title_buf[..title_len.max(0) as usize]);
+
+        Some(super::FrontmostApp {
+            id: exe_path.clone(),
+            name,
+            window_title,
+            executable: exe_path,
+        })
+    }
+}
+
+/// Read password-manager clipboard formats (see "Cloud Clipboard and Clipboard
+/// History Formats" in the Win32 docs).
+/// - `ExcludeClipboardContentFromMonitorProcessing` present → transient
+/// - `CanIncludeInClipboardHistory` DWORD 0 → concealed
+pub fn platform_clipboard_markers() -> crate::clipboard::ClipboardMarkers {
+    let mut markers = crate::clipboard::ClipboardMarkers::default();
+
+    unsafe {
+        let exclude = register_clipboard_format("ExcludeClipboardContentFromMonitorProcessing");
+        markers.transient = exclude != 0 && win32::IsClipboardFormatAvailable(exclude) != 0;
+
+        let can_include = register_clipboard_format("CanIncludeInClipboardHistory");
+        if can_include == 0 || win32::IsClipboardFormatAvailable(can_include) == 0 {
+            return markers;
+        }
+
+        if !open_clipboard() {
+            return markers;
+        }
+
+        let handle = win32::GetClipboardData(can_include);
+        if handle != 0 {
+            let data = win32::GlobalLock(handle);
+            if !data.is_null() {
+                let value = std::ptr::read_unaligned(data as *const u32);
+                markers.concealed = value == 0;
+                win32::GlobalUnlock(handle);
+            }
+        }
+        win32::CloseClipboard();
+    }
+
+    markers
+}
+
+/// Registered clipboard formats carrying encoded image bytes, originals before
+/// "PNG" (which browsers and Office add next to CF_DIB for any image).
+const IMAGE_FORMATS: [(&str, image::ImageFormat); 4] = [
+    ("JFIF", image::ImageFormat::Jpeg),
+    ("GIF", image::ImageFormat::Gif),
+    ("image/webp", image::ImageFormat::WebP),
+    ("PNG", image::ImageFormat::Png),
+];
+
+fn register_clipboard_format(name: &str) -> u32 {
+    let wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe { win32::RegisterClipboardFormatW(wide.as_ptr()) }
+}
+
+/// Open the clipboard, retrying briefly: the owner may still hold it open
+/// right after a change.
+fn open_clipboard() -> bool {
+    for _ in 0..5 {
+        if unsafe { win32::OpenClipboard(0) } != 0 {
+            return true;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    false
+}
+
+/// Read the clipboard image in its original encoding from the registered
+/// image formats. Returns None when only a bitmap (CF_DIB) is offered.
+pub fn platform_read_image_data() -> Option<Vec<u8>> {
+    let format = IMAGE_FORMATS
+        .iter()
+        .map(|(name, _)| register_clipboard_format(name))
+        .find(|&f| f != 0 && unsafe { win32::IsClipboardFormatAvailable(f) } != 0)?;
+
+    unsafe {
+        if !open_clipboard() {
+            return None;
+        }
+        let mut bytes = None;
+        let handle = win32::GetClipboardData(format);
+        if handle != 0 {
+            let size = win32::GlobalSize(handle);
+            let data = win32::GlobalLock(handle);
+            if !data.is_null() && size > 0 {
+                bytes = Some(std::slice::from_raw_parts(data, size).to_vec());
+                win32::GlobalUnlock(handle);
+            }
+        }
+        win32::CloseClipboard();
+        bytes
+    }
+}
+
+/// Write an image file to the clipboard as a bitmap (CF_DIB) plus, when it has
+/// a registered clipboard name, its original encoding, so apps that understand
+/// it paste the original bytes. Both go in one clipboard session: each session
+/// is one clipboard update, and the monitor skips only one for our own pastes.
+/// Formats without a registered name (TIFF, BMP) are written as bitmap only.
+pub fn platform_write_image(path: &str) -> Result<(), String> {
+    const CF_DIB: u32 = 8;
+
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read image file: {}", e))?;
+    let dib = encode_dib(&bytes)?;
+    let encoded = image::guess_format(&bytes)
+        .ok()
+        .and_then(|format| IMAGE_FORMATS.iter().find(|(_, f)| *f == format))
+        .map(|(name, _)| (*name, register_clipboard_format(name)))
+        .filter(|(_, clipboard_format)| *clipboard_format != 0);
+
+    let dib_mem = global_copy(&dib)?;
+    let encoded_mem = match encoded {
+        Some((name, clipboard_format)) => match global_copy(&bytes) {
+            Ok(hmem) => Some((name, clipboard_format, hmem)),
+            Err(e) => {
+                unsafe { win32::GlobalFree(dib_mem) };
+                return Err(e);
+            }
+        },
+        None => None,
+    };
+
+    unsafe {
+        if !open_clipboard() {
+            win32::GlobalFree(dib_mem);
+            if let Some((_, _, hmem)) = encoded_mem {
+                win32::GlobalFree(hmem);
+            }
+            return Err("Failed to open clipboard".to_string());
+        }
+        win32::EmptyClipboard();
+        // On success the clipboard owns the memory
+        let mut result = Ok(());
+        if win32::SetClipboardData(CF_DIB, dib_mem) == 0 {
+            win32::GlobalFree(dib_mem);
+            result = Err("SetClipboardData failed for CF_DIB".to_string());
+        }
+        if let Some((name, clipboard_format, hmem)) = encoded_mem {
+            if win32::SetClipboardData(clipboard_format, hmem) == 0 {
+                win32::GlobalFree(hmem);
+                // The bitmap alone still pastes
+                log::warn!("SetClipboardData failed for {}", name);
+            }
+        }
+        win32::CloseClipboard();
+        result
+    }
+}
+
+/// Decode an image into a 32-bit bottom-up DIB: a BITMAPINFOHEADER followed by
+/// BGRA rows, last row first.
+fn encode_dib(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let rgba = image::load_from_memory(bytes)
+        .map_err(|e| format!("Failed to decode image: {}", e))?
+        .to_rgba8();
+    let (width, height) = rgba.dimensions();
+    let row = width as usize * 4;
+    let mut dib = Vec::with_capacity(40 + row * height as usize);
+    dib.extend_from_slice(&40u32.to_le_bytes()); // biSize
+    dib.extend_from_slice(&(width as i32).to_le_bytes());
+    dib.extend_from_slice(&(height as i32).to_le_bytes()); // positive: bottom-up
+    dib.extend_from_slice(&1u16.to_le_bytes()); // biPlanes
+    dib.extend_from_slice(&32u16.to_le_bytes()); // biBitCount
+    dib.extend_from_slice(&0u32.to_le_bytes()); // biCompression = BI_RGB
+    dib.extend_from_slice(&((row * height as usize) as u32).to_le_bytes());
+    dib.extend_from_slice(&[0u8; 16]); // resolution and palette: unused
+    for y in (0..height).rev() {
+        for x in 0..width {
+            let [r, g, b, a] = rgba.get_pixel(x, y).0;
+            dib.extend_from_slice(&[b, g, r, a]);
+        }
+    }
+    Ok(dib)
+}
+
+/// Copy bytes into movable global memory for SetClipboardData.
+fn global_copy(bytes: &[u8]) -> Result<isize, String> {
+    const GMEM_MOVEABLE: u32 = 0x0002;
+
+    unsafe {
+        let hmem = win32::GlobalAlloc(GMEM_MOVEABLE, bytes.len());
+        if hmem == 0 {
+            return Err("GlobalAlloc failed".to_string());
+        }
+        let dest = win32::GlobalLock(hmem) as *mut u8;
+        if dest.is_null() {
+            win32::GlobalFree(hmem);
+            return Err("GlobalLock failed".to_string());
+        }
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), dest, bytes.len());
+        win32::GlobalUnlock(hmem);
+        Ok(hmem)
+    }
+}
+
 pub fn init_platform(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     APP_HANDLE.set(app.handle().clone()).ok();
     if let Some(window) = app.get_webview_window("main") {
@@ -884,6 +1145,30 @@ pub fn simulate_paste_keys() {
     }
 }
 
+/// Press Left `count` times, e.g. to put the caret where a pasted snippet's
+/// `{{cursor}}` was. Arrow keys are extended keys.
+pub fn simulate_left_keys(count: usize) {
+    let left = win32::VK_LEFT as u16;
+    let inputs: Vec<win32::KeyboardInputRaw> = (0..count)
+        .flat_map(|_| {
+            [
+                win32::KeyboardInputRaw::new(left, win32::KEYEVENTF_EXTENDEDKEY),
+                win32::KeyboardInputRaw::new(
+                    left,
+                    win32::KEYEVENTF_EXTENDEDKEY | win32::KEYEVENTF_KEYUP,
+                ),
+            ]
+        })
+        .collect();
+    unsafe {
+        win32::SendInput(
+            inputs.len() as u32,
+            inputs.as_ptr(),
+            std::mem::size_of::<win32::KeyboardInputRaw>() as i32,
+        );
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Foreground restore
 // ---------------------------------------------------------------------------
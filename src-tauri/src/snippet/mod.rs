@@ -0,0 +1,230 @@
+//! Snippet templates: text the user writes once and pastes many times, with
+//! placeholders filled in at paste time:
+//!
+//! - `{{date}}` today's date; `{{date:%d/%m %H:%M}}` any strftime format
+//! - `{{clipboard}}` the text on the clipboard before the paste
+//! - `{{uuid}}` a new random UUID
+//! - `{{cursor}}` where the caret ends up after pasting (at most once)
+//! - `{{prompt:Name}}` a value the user is asked for before pasting; every
+//!   occurrence of the same name gets the same value
+//!
+//! Anything else in double braces is kept as written, so text from template
+//! languages that use them (Handlebars, Jinja, …) pastes unchanged.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const OPEN: &str = "{{";
+const CLOSE: &str = "}}";
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Part {
+    Text(String),
+    Date(String),
+    Clipboard,
+    Uuid,
+    Cursor,
+    Prompt(String),
+}
+
+/// What a placeholder is filled with.
+pub struct Context<'a> {
+    pub now: DateTime<Local>,
+    pub clipboard: &'a str,
+    /// Values for `{{prompt:…}}`, by name.
+    pub values: &'a HashMap<String, String>,
+}
+
+/// An expanded snippet, ready to paste.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expansion {
+    pub text: String,
+    /// Characters between `{{cursor}}` and the end of the text, i.e. how far
+    /// to move the caret back after pasting. None without a cursor.
+    pub cursor_back: Option<usize>,
+}
+
+/// Split a template into text and placeholders. Errors name the first
+/// placeholder that is malformed.
+pub fn parse(template: &str) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+    let mut cursors = 0;
+
+    while let Some(start) = rest.find(OPEN) {
+        text.push_str(&rest[..start]);
+        let after = &rest[start + OPEN.len()..];
+        let Some(end) = after.find(CLOSE) else {
+            rest = &rest[start..];
+            break;
+        };
+        let Some(part) = placeholder(after[..end].trim())? else {
+            text.push_str(&rest[start..start + OPEN.len() + end + CLOSE.len()]);
+            rest = &after[end + CLOSE.len()..];
+            continue;
+        };
+        if part == Part::Cursor {
+            cursors += 1;
+            if cursors > 1 {
+                return Err("A snippet can have only one {{cursor}}".to_string());
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(std::mem::take(&mut text)));
+        }
+        parts.push(part);
+        rest = &after[end + CLOSE.len()..];
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    Ok(parts)
+}
+
+/// The placeholder in `{{body}}`, None if it isn't one.
+fn placeholder(body: &str) -> Result<Option<Part>, String> {
+    let (name, arg) = match body.split_once(':') {
+        Some((name, arg)) => (name.trim(), Some(arg)),
+        None => (body, None),
+    };
+    let part = match (name, arg) {
+        ("date", None) => Part::Date(DEFAULT_DATE_FORMAT.to_string()),
+        ("date", Some(format)) => {
+            if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                return Err(format!("Invalid date format in {{{{{}}}}}", body));
+            }
+            Part::Date(format.to_string())
+        }
+        ("clipboard", None) => Part::Clipboard,
+        ("uuid", None) => Part::Uuid,
+        ("cursor", None) => Part::Cursor,
+        ("prompt", Some(prompt)) if !prompt.trim().is_empty() => {
+            Part::Prompt(prompt.trim().to_string())
+        }
+        ("clipboard" | "uuid" | "cursor" | "prompt", _) => {
+            return Err(format!("Malformed placeholder {{{{{}}}}}", body))
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(part))
+}
+
+/// Names of the prompts in a template, each once, in order of appearance.
+pub fn prompts(template: &str) -> Result<Vec<String>, String> {
+    let mut names: Vec<String> = Vec::new();
+    for part in parse(template)? {
+        if let Part::Prompt(name) = part {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Fill in a template's placeholders. Every prompt needs a value.
+pub fn expand(template: &str, context: &Context) -> Result<Expansion, String> {
+    let mut text = String::new();
+    let mut cursor = None;
+    for part in parse(template)? {
+        match part {
+            Part::Text(s) => text.push_str(&s),
+            Part::Date(format) => write!(text, "{}", context.now.format(&format))
+                .map_err(|_| format!("Invalid date format {}", format))?,
+            Part::Clipboard => text.push_str(context.clipboard),
+            Part::Uuid => text.push_str(&uuid::Uuid::new_v4().to_string()),
+            Part::Cursor => cursor = Some(text.len()),
+            Part::Prompt(name) => match context.values.get(&name) {
+                Some(value) => text.push_str(value),
+                None => return Err(format!("Missing value for {}", name)),
+            },
+        }
+    }
+    // Left-arrow presses: a line break counts once, even as \r\n
+    let cursor_back = cursor.map(|at| text[at..].chars().filter(|c| *c != '\r').count());
+    Ok(Expansion { text, cursor_back })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn context<'a>(values: &'a HashMap<String, String>) -> Context<'a> {
+        Context {
+            now: Local.with_ymd_and_hms(2026, 3, 7, 9, 5, 0).unwrap(),
+            clipboard: "copied",
+            values,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("Hi {{ prompt: Name }}, {{date}}{{cursor}}").unwrap(),
+            vec![
+                Part::Text("Hi ".to_string()),
+                Part::Prompt("Name".to_string()),
+                Part::Text(", ".to_string()),
+                Part::Date(DEFAULT_DATE_FORMAT.to_string()),
+                Part::Cursor,
+            ]
+        );
+        assert_eq!(parse("").unwrap(), vec![]);
+        assert_eq!(
+            parse("a } b").unwrap(),
+            vec![Part::Text("a } b".to_string())]
+        );
+
+        // Other braces are text
+        assert_eq!(
+            parse("{{#each items}}{{uuid}} {{date").unwrap(),
+            vec![
+                Part::Text("{{#each items}}".to_string()),
+                Part::Uuid,
+                Part::Text(" {{date".to_string()),
+            ]
+        );
+
+        assert!(parse("{{prompt:}}").is_err());
+        assert!(parse("{{uuid:x}}").is_err());
+        assert!(parse("{{date:%Q}}").is_err());
+        assert!(parse("{{cursor}} and {{cursor}}").is_err());
+
+        assert_eq!(
+            prompts("{{prompt:To}} {{prompt:Topic}} {{prompt:To}}").unwrap(),
+            vec!["To".to_string(), "Topic".to_string()]
+        );
+        assert!(prompts("{{clipboard}}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_expand() {
+        let mut values = HashMap::new();
+        values.insert("Name".to_string(), "Ada".to_string());
+        let context = context(&values);
+
+        let expansion = expand(
+            "{{date}} {{date:%H:%M}} Dear {{prompt:Name}}, re: {{clipboard}}",
+            &context,
+        )
+        .unwrap();
+        assert_eq!(expansion.text, "2026-03-07 09:05 Dear Ada, re: copied");
+        assert_eq!(expansion.cursor_back, None);
+
+        let expansion = expand("({{cursor}})\r\n{{prompt:Name}}", &context).unwrap();
+        assert_eq!(expansion.text, "()\r\nAda");
+        assert_eq!(expansion.cursor_back, Some(5));
+
+        let id = expand("{{uuid}}", &context).unwrap().text;
+        assert!(uuid::Uuid::parse_str(&id).is_ok());
+        assert_ne!(id, expand("{{uuid}}", &context).unwrap().text);
+
+        assert!(expand("{{prompt:Other}}", &context).is_err());
+    }
+}
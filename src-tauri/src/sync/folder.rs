@@ -0,0 +1,397 @@
+//! Folder transport: sync through a folder kept in step by a file-sync tool
+//! (Syncthing, Dropbox, ...).
+//!
+//! Every item is written once into the folder as `<name>.item` (its
+//! [`SyncItem`] as JSON), with an image item's original next to it as
+//! `<name>.image`, both sealed with the pairing key. `<name>` is derived from
+//! the pairing key and the item's `content_hash`, so machines that copied the
+//! same content write the same file, files are never rewritten or removed,
+//! and names reveal nothing to whoever hosts the folder.
+//!
+//! Ingesting a file another machine wrote counts as a fresh copy: known
+//! content is bumped, new content is inserted with its favorite flag and
+//! groups. Later changes to an item (favorite, groups, trash) are not
+//! propagated this way.
+
+use super::{save_image, GroupIds, SyncContext, SyncItem, PAGE_ITEMS};
+use crate::archive;
+use crate::clipboard as clip_util;
+use crate::crypto::{self, Key};
+use crate::db::{
+    models::{ContentType, ImageFingerprint, SyncSummary},
+    queries,
+};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Subfolder of the chosen folder the entries are kept in.
+pub const DIR_NAME: &str = "recopy-sync";
+
+const ITEM_EXT: &str = "item";
+const IMAGE_EXT: &str = "image";
+
+/// File name (without extension) every machine paired with `key` uses for
+/// the item with `content_hash`.
+pub fn entry_name(key: &Key, content_hash: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"recopy-sync-folder");
+    hasher.update(key);
+    hasher.update(content_hash.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Write the live items changed past `since` that the folder doesn't hold
+/// yet, adding them to `summary.sent`. Returns the seq to continue from.
+pub async fn export_items(
+    ctx: &SyncContext,
+    dir: &Path,
+    since: i64,
+    summary: &mut SyncSummary,
+) -> Result<i64, String> {
+    // Sealed content would be written as empty text
+    if crypto::is_locked() {
+        return Err("History is locked".to_string());
+    }
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create sync folder: {}", e))?;
+
+    let mut next = since;
+    loop {
+        let changes = queries::get_sync_changes(&ctx.pool, next, PAGE_ITEMS)
+            .await
+            .map_err(|e| e.to_string())?;
+        let done = (changes.len() as i64) < PAGE_ITEMS;
+
+        for (seq, mut item, image_path) in changes {
+            next = seq;
+            if item.deleted_at.is_some() {
+                continue;
+            }
+            let name = entry_name(&ctx.key, &item.item.content_hash);
+            let item_file = dir.join(format!("{}.{}", name, ITEM_EXT));
+            if !item_file.exists() {
+                if let Some(path) = image_path {
+                    let data = match crypto::read_image_file(&path) {
+                        Ok(data) => data,
+                        Err(e) => {
+                            log::warn!("Not writing image item without its image {}: {}", path, e);
+                            continue;
+                        }
+                    };
+                    item.item.image_file = archive::image_entry_name(&path);
+                    let image_file = dir.join(format!("{}.{}", name, IMAGE_EXT));
+                    write_entry(&image_file, &crypto::encrypt(&ctx.key, &data))?;
+                }
+                let json = serde_json::to_vec(&item).map_err(|e| e.to_string())?;
+                // Last, so readers never see an item before its image
+                write_entry(&item_file, &crypto::encrypt(&ctx.key, &json))?;
+                summary.sent += 1;
+            }
+            // Present here already, so not to be ingested as someone else's copy
+            queries::mark_folder_entry(&ctx.pool, &name)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        if done {
+            return Ok(next);
+        }
+    }
+}
+
+/// Ingest the items other machines wrote since the last look, adding the
+/// outcome to `summary`. Entries whose image hasn't arrived yet are left for
+/// the next look.
+pub async fn ingest_items(
+    ctx: &SyncContext,
+    dir: &Path,
+    summary: &mut SyncSummary,
+) -> Result<(), String> {
+    if crypto::is_locked() {
+        return Err("History is locked".to_string());
+    }
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Failed to read sync folder: {}", e)),
+    };
+    let seen = queries::get_folder_entries(&ctx.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some(ITEM_EXT))
+        .filter_map(|p| p.file_stem()?.to_str().map(String::from))
+        .filter(|name| !seen.contains(name))
+        .collect();
+    if names.is_empty() {
+        return Ok(());
+    }
+    names.sort();
+
+    let dedup_distance = queries::get_setting(&ctx.pool, "image_dedup_distance")
+        .await
+        .unwrap_or(None)
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(clip_util::DEFAULT_IMAGE_DEDUP_DISTANCE)
+        .min(64);
+    let mut groups = GroupIds::load(&ctx.pool).await?;
+
+    for name in names {
+        let Some((item, image)) = read_entry(ctx, dir, &name) else {
+            continue;
+        };
+        ingest_item(ctx, item, image, dedup_distance, &mut groups, summary).await?;
+        queries::mark_folder_entry(&ctx.pool, &name)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Add one entry like a fresh copy of its content.
+async fn ingest_item(
+    ctx: &SyncContext,
+    item: SyncItem,
+    image: Option<Vec<u8>>,
+    dedup_distance: u32,
+    groups: &mut GroupIds,
+    summary: &mut SyncSummary,
+) -> Result<(), String> {
+    let Some(content_type) = ContentType::from_str(&item.item.content_type) else {
+        log::warn!(
+            "Skipping synced item of unknown type {}",
+            item.item.content_type
+        );
+        return Ok(());
+    };
+
+    if queries::find_and_bump_by_hash(
+        &ctx.pool,
+        &item.item.content_hash,
+        match (
+            item.item.image_phash,
+            item.item.image_width,
+            item.item.image_height,
+        ) {
+            (Some(phash), Some(width), Some(height)) if dedup_distance > 0 => {
+                Some(ImageFingerprint {
+                    phash,
+                    width,
+                    height,
+                })
+            }
+            _ => None,
+        },
+        dedup_distance,
+    )
+    .await
+    .map_err(|e| e.to_string())?
+    .is_some()
+    {
+        summary.updated += 1;
+        return Ok(());
+    }
+
+    let image_path = match &image {
+        Some(data) => Some(save_image(
+            &ctx.app_data,
+            item.item.image_file.as_deref(),
+            data,
+        )?),
+        None => None,
+    };
+    let group_ids = groups.resolve(&ctx.pool, &item.item.groups).await?;
+    let import = item
+        .item
+        .into_import(content_type, image_path.clone(), group_ids);
+    let id = match queries::insert_item(&ctx.pool, &import.item).await {
+        Ok(id) => id,
+        Err(e) => {
+            if let Some(path) = image_path {
+                let _ = std::fs::remove_file(path);
+            }
+            return Err(e.to_string());
+        }
+    };
+
+    if import.is_favorited {
+        queries::set_item_favorited(&ctx.pool, &id, true)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    for group_id in &import.group_ids {
+        queries::add_item_to_group(&ctx.pool, &id, group_id)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    summary.inserted += 1;
+    Ok(())
+}
+
+/// Read and open an entry with its image. None if it can't be used yet: not
+/// sealed with this pairing key, cut short, or its image still on its way.
+fn read_entry(ctx: &SyncContext, dir: &Path, name: &str) -> Option<(SyncItem, Option<Vec<u8>>)> {
+    let opened = std::fs::read(dir.join(format!("{}.{}", name, ITEM_EXT)))
+        .map_err(|e| e.to_string())
+        .and_then(|data| crypto::decrypt(&ctx.key, &data))
+        .and_then(|json| serde_json::from_slice::<SyncItem>(&json).map_err(|e| e.to_string()));
+    let item = match opened {
+        Ok(item) => item,
+        Err(e) => {
+            log::debug!("Skipping sync folder entry {}: {}", name, e);
+            return None;
+        }
+    };
+    if item.item.image_file.is_none() {
+        return Some((item, None));
+    }
+
+    let image = std::fs::read(dir.join(format!("{}.{}", name, IMAGE_EXT)))
+        .map_err(|e| e.to_string())
+        .and_then(|data| crypto::decrypt(&ctx.key, &data));
+    match image {
+        Ok(data) => Some((item, Some(data))),
+        Err(e) => {
+            log::debug!("Image of sync folder entry {} not ready: {}", name, e);
+            None
+        }
+    }
+}
+
+/// Write through a temp file and rename, so sync tools never pick up half a file.
+fn write_entry(path: &Path, data: &[u8]) -> Result<(), String> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, data)
+        .and_then(|_| std::fs::rename(&tmp, path))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::NewClipboardItem;
+    use crate::db::test_pool;
+    use std::path::PathBuf;
+
+    async fn node(name: &str) -> SyncContext {
+        SyncContext {
+            pool: test_pool().await,
+            key: [9u8; crypto::KEY_LEN],
+            device_id: uuid::Uuid::new_v4().to_string(),
+            device_name: name.to_string(),
+            port: 0,
+            app_data: temp_dir(),
+        }
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("recopy-folder-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn text_item(text: &str) -> NewClipboardItem {
+        NewClipboardItem {
+            content_type: ContentType::PlainText,
+            plain_text: text.into(),
+            rich_content: None,
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "".into(),
+            source_app_name: "".into(),
+            content_size: text.len() as i64,
+            content_hash: format!("hash-{}", text),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_items_travel_through_folder() {
+        let shared = temp_dir();
+        let laptop = node("Laptop").await;
+        let desktop = node("Desktop").await;
+
+        let alpha = queries::insert_item(&laptop.pool, &text_item("alpha"))
+            .await
+            .unwrap();
+        queries::set_item_favorited(&laptop.pool, &alpha, true)
+            .await
+            .unwrap();
+        let image_path =
+            clip_util::save_original_image(&laptop.app_data, b"png-bytes", "png").unwrap();
+        let mut photo = text_item("");
+        photo.content_type = ContentType::Image;
+        photo.content_hash = "hash-photo".into();
+        photo.image_path = Some(image_path);
+        queries::insert_item(&laptop.pool, &photo).await.unwrap();
+        queries::insert_item(&desktop.pool, &text_item("beta"))
+            .await
+            .unwrap();
+
+        let mut summary = SyncSummary::default();
+        export_items(&laptop, &shared, 0, &mut summary)
+            .await
+            .unwrap();
+        assert_eq!(summary.sent, 2);
+        // Nothing of its own is ingested back
+        ingest_items(&laptop, &shared, &mut summary).await.unwrap();
+        assert_eq!((summary.inserted, summary.updated), (0, 0));
+
+        let mut summary = SyncSummary::default();
+        ingest_items(&desktop, &shared, &mut summary).await.unwrap();
+        assert_eq!(summary.inserted, 2);
+        let items = queries::get_items(&desktop.pool, None, 10, 0)
+            .await
+            .unwrap();
+        let alpha = items.iter().find(|i| i.plain_text == "alpha").unwrap();
+        assert!(alpha.is_favorited);
+        let photo = items.iter().find(|i| i.content_type == "image").unwrap();
+        let saved = photo.image_path.as_deref().unwrap();
+        assert!(saved.starts_with(&*desktop.app_data.to_string_lossy()));
+        assert_eq!(std::fs::read(saved).unwrap(), b"png-bytes");
+
+        // Ingested items are already in the folder, only new content is written
+        let mut summary = SyncSummary::default();
+        export_items(&desktop, &shared, 0, &mut summary)
+            .await
+            .unwrap();
+        assert_eq!(summary.sent, 1);
+        ingest_items(&desktop, &shared, &mut summary).await.unwrap();
+        assert_eq!((summary.inserted, summary.updated), (0, 0));
+
+        let mut summary = SyncSummary::default();
+        ingest_items(&laptop, &shared, &mut summary).await.unwrap();
+        assert_eq!(summary.inserted, 1);
+        assert_eq!(std::fs::read_dir(&shared).unwrap().count(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_entries_of_another_pairing_are_ignored() {
+        let shared = temp_dir();
+        let laptop = node("Laptop").await;
+        let mut stranger = node("Stranger").await;
+        stranger.key = [3u8; crypto::KEY_LEN];
+        queries::insert_item(&stranger.pool, &text_item("secret"))
+            .await
+            .unwrap();
+
+        let mut summary = SyncSummary::default();
+        export_items(&stranger, &shared, 0, &mut summary)
+            .await
+            .unwrap();
+        ingest_items(&laptop, &shared, &mut summary).await.unwrap();
+        assert_eq!(summary.inserted, 0);
+        assert!(queries::get_items(&laptop.pool, None, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_ne!(entry_name(&laptop.key, "h"), entry_name(&stranger.key, "h"));
+    }
+}
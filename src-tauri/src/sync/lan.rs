@@ -0,0 +1,530 @@
+//! LAN transport: framed, encrypted messages over TCP.
+//!
+//! Each frame is a big-endian u32 length followed by a JSON message sealed
+//! with XChaCha20-Poly1305. The opening `Hello`s are sealed with the pairing
+//! key, so only machines paired with the same secret get past them; the rest
+//! of the session is sealed with a key mixed from both sides' random nonces,
+//! so a recorded session can't be replayed. Both sides answer with a `Ready`
+//! sealed with that key, which shows they hold it; only then is a peer
+//! trusted with large frames and its address remembered. Each side then pulls
+//! the other's changes in turn, the connecting side first.
+
+use super::{apply_items, collect_changes, SyncContext, SyncItem};
+use crate::crypto::{self, Key};
+use crate::db::{models::SyncSummary, queries};
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Largest frame accepted; a page of changes is cut well below this.
+const MAX_FRAME: usize = 128 * 1024 * 1024;
+
+/// Limit on connecting and on each read or write.
+const IO_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Frame size and read limits before the peer has shown it holds the session
+/// key: enough for a `Hello` or a `Pull`, and no longer to wait for one.
+const HANDSHAKE_MAX_FRAME: usize = 4 * 1024;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+const NONCE_LEN: usize = 16;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    Hello {
+        version: u32,
+        device_id: String,
+        name: String,
+        /// Port the sender accepts peers on.
+        port: u16,
+        /// Base64 random bytes for the session key.
+        nonce: String,
+    },
+    /// Ask for the changes past `since` (a seq of the receiving side).
+    Pull { since: i64 },
+    Changes {
+        items: Vec<SyncItem>,
+        next: i64,
+        more: bool,
+    },
+    /// No more pulls from this side.
+    Done,
+    /// First message under the session key.
+    Ready,
+}
+
+/// The other side of a sync session.
+#[derive(Debug, Clone)]
+pub struct Peer {
+    pub device_id: String,
+    pub name: String,
+}
+
+/// An accepted connection whose peer has sent a valid `Hello`.
+pub struct Accepted {
+    pub peer: Peer,
+    /// Where the peer listens, as its `Hello` claims.
+    address: Option<String>,
+    conn: Connection,
+}
+
+/// Connect to a peer and exchange changes both ways.
+pub async fn connect(ctx: &SyncContext, addr: SocketAddr) -> Result<(Peer, SyncSummary), String> {
+    let stream = tokio::time::timeout(IO_TIMEOUT, TcpStream::connect(addr))
+        .await
+        .map_err(|_| format!("Timed out connecting to {}", addr))?
+        .map_err(|e| format!("Failed to connect to {}: {}", addr, e))?;
+    let mut conn = Connection::new(stream, ctx.key);
+
+    let nonce = random_nonce();
+    conn.send(&hello(ctx, &nonce)).await?;
+    let (peer, peer_nonce) = read_hello(&mut conn, ctx).await?;
+    conn.begin_session(session_key(&ctx.key, &nonce, &peer_nonce));
+    // A replayed `Hello` can't be followed by a frame under the session key
+    expect_ready(&mut conn).await?;
+    conn.send(&Message::Ready).await?;
+    queries::upsert_sync_peer(
+        &ctx.pool,
+        &peer.device_id,
+        &peer.name,
+        Some(&addr.to_string()),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut summary = pull(&mut conn, ctx, &peer).await?;
+    summary.sent = serve(&mut conn, ctx).await?;
+    summary.peers = 1;
+    Ok((peer, summary))
+}
+
+/// Read the opening `Hello` of a connection accepted by the sync listener and
+/// answer it. Cheap for strangers: small frames and a short timeout.
+pub async fn accept_handshake(ctx: &SyncContext, stream: TcpStream) -> Result<Accepted, String> {
+    let remote_ip = stream.peer_addr().map(|a| a.ip()).ok();
+    let mut conn = Connection::new(stream, ctx.key);
+
+    let (peer, peer_nonce, peer_port) = match conn.recv().await? {
+        Message::Hello {
+            version,
+            device_id,
+            name,
+            port,
+            nonce,
+        } => {
+            check_hello(ctx, version, &device_id)?;
+            (Peer { device_id, name }, decode_nonce(&nonce)?, port)
+        }
+        _ => return Err("Peer did not start with a handshake".to_string()),
+    };
+    let nonce = random_nonce();
+    conn.send(&hello(ctx, &nonce)).await?;
+    conn.begin_session(session_key(&ctx.key, &peer_nonce, &nonce));
+    // Sent before waiting for the session lock, so the peer isn't kept waiting
+    conn.send(&Message::Ready).await?;
+    // Where the peer listens, not the port it connected from
+    let address = remote_ip.map(|ip| SocketAddr::new(ip, peer_port).to_string());
+    Ok(Accepted {
+        peer,
+        address,
+        conn,
+    })
+}
+
+/// Exchange changes with a peer past `accept_handshake`. A `Hello` can be
+/// replayed, so frames stay small and the peer's address is only remembered
+/// once its `Ready` decrypts under the session key.
+pub async fn accept_session(ctx: &SyncContext, accepted: Accepted) -> Result<SyncSummary, String> {
+    let Accepted {
+        peer,
+        address,
+        mut conn,
+    } = accepted;
+    expect_ready(&mut conn).await?;
+    queries::upsert_sync_peer(&ctx.pool, &peer.device_id, &peer.name, address.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    let sent = serve(&mut conn, ctx).await?;
+    let mut summary = pull(&mut conn, ctx, &peer).await?;
+    summary.sent = sent;
+    summary.peers = 1;
+    Ok(summary)
+}
+
+/// Read the peer's `Ready`; decrypting it marks the connection trusted.
+async fn expect_ready(conn: &mut Connection) -> Result<(), String> {
+    match conn.recv().await? {
+        Message::Ready => Ok(()),
+        _ => Err("Peer did not confirm the session".to_string()),
+    }
+}
+
+/// Pull the peer's changes past our cursor for it, page by page.
+async fn pull(
+    conn: &mut Connection,
+    ctx: &SyncContext,
+    peer: &Peer,
+) -> Result<SyncSummary, String> {
+    let mut summary = SyncSummary::default();
+    let mut since = queries::get_sync_cursor(&ctx.pool, &peer.device_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    loop {
+        conn.send(&Message::Pull { since }).await?;
+        let (items, next, more) = match conn.recv().await? {
+            Message::Changes { items, next, more } => (items, next, more),
+            _ => return Err("Unexpected message from peer".to_string()),
+        };
+        apply_items(ctx, items, &mut summary).await?;
+        // Advanced only once the page is applied, so a failed page is pulled again
+        queries::set_sync_cursor(&ctx.pool, &peer.device_id, next)
+            .await
+            .map_err(|e| e.to_string())?;
+        if !more || next <= since {
+            break;
+        }
+        since = next;
+    }
+    conn.send(&Message::Done).await?;
+    Ok(summary)
+}
+
+/// Answer the peer's pulls until it is done. Returns the number of items sent.
+async fn serve(conn: &mut Connection, ctx: &SyncContext) -> Result<usize, String> {
+    let mut sent = 0;
+    loop {
+        match conn.recv().await? {
+            Message::Pull { since } => {
+                let (items, next, more) = collect_changes(&ctx.pool, since).await?;
+                sent += items.len();
+                conn.send(&Message::Changes { items, next, more }).await?;
+            }
+            Message::Done => return Ok(sent),
+            _ => return Err("Unexpected message from peer".to_string()),
+        }
+    }
+}
+
+fn hello(ctx: &SyncContext, nonce: &[u8]) -> Message {
+    Message::Hello {
+        version: PROTOCOL_VERSION,
+        device_id: ctx.device_id.clone(),
+        name: ctx.device_name.clone(),
+        port: ctx.port,
+        nonce: B64.encode(nonce),
+    }
+}
+
+async fn read_hello(conn: &mut Connection, ctx: &SyncContext) -> Result<(Peer, Vec<u8>), String> {
+    match conn.recv().await? {
+        Message::Hello {
+            version,
+            device_id,
+            name,
+            nonce,
+            ..
+        } => {
+            check_hello(ctx, version, &device_id)?;
+            Ok((Peer { device_id, name }, decode_nonce(&nonce)?))
+        }
+        _ => Err("Peer did not answer the handshake".to_string()),
+    }
+}
+
+fn check_hello(ctx: &SyncContext, version: u32, device_id: &str) -> Result<(), String> {
+    if version != PROTOCOL_VERSION {
+        return Err(format!(
+            "Peer uses sync protocol {} (this machine: {})",
+            version, PROTOCOL_VERSION
+        ));
+    }
+    if device_id == ctx.device_id {
+        return Err("Refusing to sync with this machine itself".to_string());
+    }
+    Ok(())
+}
+
+fn random_nonce() -> Vec<u8> {
+    let mut nonce = vec![0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    nonce
+}
+
+fn decode_nonce(nonce: &str) -> Result<Vec<u8>, String> {
+    B64.decode(nonce)
+        .ok()
+        .filter(|n| n.len() == NONCE_LEN)
+        .ok_or_else(|| "Invalid handshake nonce".to_string())
+}
+
+/// Key for the rest of a session, from the pairing key and both sides' nonces.
+fn session_key(key: &Key, connector_nonce: &[u8], acceptor_nonce: &[u8]) -> Key {
+    let mut hasher = Sha256::new();
+    hasher.update(b"recopy-sync-session");
+    hasher.update(key);
+    hasher.update(connector_nonce);
+    hasher.update(acceptor_nonce);
+    hasher.finalize().into()
+}
+
+struct Connection {
+    stream: TcpStream,
+    key: Key,
+    /// `key` is the session key rather than the pairing key.
+    in_session: bool,
+    /// The peer has sent a frame sealed with the session key, so it may send
+    /// large frames and take longer (see `HANDSHAKE_MAX_FRAME`).
+    trusted: bool,
+}
+
+impl Connection {
+    fn new(stream: TcpStream, key: Key) -> Self {
+        Connection {
+            stream,
+            key,
+            in_session: false,
+            trusted: false,
+        }
+    }
+
+    fn begin_session(&mut self, key: Key) {
+        self.key = key;
+        self.in_session = true;
+    }
+
+    async fn send(&mut self, message: &Message) -> Result<(), String> {
+        let json = serde_json::to_vec(message).map_err(|e| e.to_string())?;
+        let frame = crypto::encrypt(&self.key, &json);
+        if frame.len() > MAX_FRAME {
+            return Err("Message too large".to_string());
+        }
+        let write = async {
+            self.stream.write_u32(frame.len() as u32).await?;
+            self.stream.write_all(&frame).await?;
+            self.stream.flush().await
+        };
+        tokio::time::timeout(IO_TIMEOUT, write)
+            .await
+            .map_err(|_| "Timed out sending to peer".to_string())?
+            .map_err(|e| format!("Failed to send to peer: {}", e))
+    }
+
+    async fn recv(&mut self) -> Result<Message, String> {
+        let (max_frame, timeout) = if self.trusted {
+            (MAX_FRAME, IO_TIMEOUT)
+        } else {
+            (HANDSHAKE_MAX_FRAME, HANDSHAKE_TIMEOUT)
+        };
+        let read = async {
+            let len = self.stream.read_u32().await? as usize;
+            if len > max_frame {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "message too large",
+                ));
+            }
+            let mut frame = vec![0u8; len];
+            self.stream.read_exact(&mut frame).await?;
+            Ok(frame)
+        };
+        let frame = tokio::time::timeout(timeout, read)
+            .await
+            .map_err(|_| "Timed out waiting for peer".to_string())?
+            .map_err(|e| format!("Failed to read from peer: {}", e))?;
+        let json = crypto::decrypt(&self.key, &frame)
+            .map_err(|_| "Peer is not paired with this machine".to_string())?;
+        if self.in_session {
+            self.trusted = true;
+        }
+        serde_json::from_slice(&json).map_err(|e| format!("Invalid message from peer: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{ContentType, NewClipboardItem};
+    use crate::db::test_pool;
+    use tokio::net::TcpListener;
+
+    async fn node(name: &str, key: Key) -> SyncContext {
+        let app_data = std::env::temp_dir().join(format!("recopy-sync-{}", uuid::Uuid::new_v4()));
+        SyncContext {
+            pool: test_pool().await,
+            key,
+            device_id: uuid::Uuid::new_v4().to_string(),
+            device_name: name.to_string(),
+            port: 0,
+            app_data,
+        }
+    }
+
+    async fn add_text(ctx: &SyncContext, text: &str) -> String {
+        let item = NewClipboardItem {
+            content_type: ContentType::PlainText,
+            plain_text: text.into(),
+            rich_content: None,
+            thumbnail: None,
+            image_path: None,
+            file_path: None,
+            file_name: None,
+            source_app: "".into(),
+            source_app_name: ctx.device_name.clone(),
+            content_size: text.len() as i64,
+            content_hash: format!("hash-{}", text),
+            sensitive: false,
+            files: vec![],
+            image_width: None,
+            image_height: None,
+            image_format: None,
+            image_phash: None,
+            expires_at: None,
+        };
+        queries::insert_item(&ctx.pool, &item).await.unwrap()
+    }
+
+    /// One session from `a` to `b` over loopback; returns both sides' summaries.
+    async fn sync(a: &SyncContext, b: &SyncContext) -> Result<(SyncSummary, SyncSummary), String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let acceptor = b.clone();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let accepted = accept_handshake(&acceptor, stream).await?;
+            let peer = accepted.peer.clone();
+            accept_session(&acceptor, accepted).await.map(|s| (peer, s))
+        });
+        let client = connect(a, addr).await;
+        let server = server.await.unwrap();
+        Ok((client?.1, server?.1))
+    }
+
+    async fn texts(ctx: &SyncContext) -> Vec<String> {
+        let mut texts: Vec<String> = queries::get_items(&ctx.pool, None, 100, 0)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|i| i.plain_text)
+            .collect();
+        texts.sort();
+        texts
+    }
+
+    #[tokio::test]
+    async fn test_sync_over_loopback() {
+        let key = [7u8; crypto::KEY_LEN];
+        let laptop = node("Laptop", key).await;
+        let desktop = node("Desktop", key).await;
+
+        let alpha = add_text(&laptop, "alpha").await;
+        add_text(&desktop, "beta").await;
+        // Same content on both: not duplicated
+        add_text(&desktop, "alpha").await;
+        // changed_at has millisecond resolution
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let group = queries::create_group(&laptop.pool, "Work").await.unwrap();
+        queries::add_item_to_group(&laptop.pool, &alpha, &group)
+            .await
+            .unwrap();
+
+        let (to_laptop, to_desktop) = sync(&laptop, &desktop).await.unwrap();
+        assert_eq!(to_laptop.inserted, 1);
+        assert_eq!(to_desktop.inserted, 0);
+        assert_eq!(texts(&laptop).await, ["alpha", "beta"]);
+        assert_eq!(texts(&desktop).await, ["alpha", "beta"]);
+
+        // The laptop's grouping is newer, so the desktop's copy joins the group
+        let groups = queries::get_groups(&desktop.pool).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name, "Work");
+        let grouped = queries::get_group_items(&desktop.pool, &groups[0].id, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped[0].plain_text, "alpha");
+
+        // A trash on one side reaches the other, and applied changes are not echoed
+        queries::trash_item(&laptop.pool, &alpha).await.unwrap();
+        let (to_laptop, to_desktop) = sync(&laptop, &desktop).await.unwrap();
+        assert_eq!(to_desktop.updated, 1);
+        assert_eq!(to_laptop.updated, 0);
+        assert_eq!(texts(&desktop).await, ["beta"]);
+        let (to_laptop, to_desktop) = sync(&desktop, &laptop).await.unwrap();
+        assert_eq!((to_laptop.updated, to_laptop.inserted), (0, 0));
+        assert_eq!((to_desktop.updated, to_desktop.inserted), (0, 0));
+
+        // Both sides remember each other
+        let peers = queries::get_sync_peers(&desktop.pool).await.unwrap();
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].name, "Laptop");
+        assert!(peers[0].last_sync_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_large_frame_before_handshake_is_rejected() {
+        let desktop = node("Desktop", [3u8; crypto::KEY_LEN]).await;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            accept_handshake(&desktop, stream).await.err()
+        });
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_u32(MAX_FRAME as u32).await.unwrap();
+        let error = server.await.unwrap().unwrap();
+        assert!(error.contains("too large"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_replayed_hello_is_not_remembered() {
+        let key = [4u8; crypto::KEY_LEN];
+        let laptop = node("Laptop", key).await;
+        let desktop = node("Desktop", key).await;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let acceptor = desktop.clone();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let accepted = accept_handshake(&acceptor, stream).await?;
+            accept_session(&acceptor, accepted).await
+        });
+
+        // A valid Hello, but nothing under the session key after it
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut conn = Connection::new(stream, key);
+        conn.send(&hello(&laptop, &random_nonce())).await.unwrap();
+        assert!(matches!(conn.recv().await, Ok(Message::Hello { .. })));
+        drop(conn);
+
+        assert!(server.await.unwrap().is_err());
+        assert!(queries::get_sync_peers(&desktop.pool)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unpaired_peer_is_rejected() {
+        let laptop = node("Laptop", [1u8; crypto::KEY_LEN]).await;
+        let stranger = node("Stranger", [2u8; crypto::KEY_LEN]).await;
+        add_text(&stranger, "secret").await;
+
+        assert!(sync(&laptop, &stranger).await.is_err());
+        assert!(texts(&laptop).await.is_empty());
+        assert!(queries::get_sync_peers(&stranger.pool)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
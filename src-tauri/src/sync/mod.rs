@@ -0,0 +1,433 @@
+//! Sync of clipboard history between a user's machines.
+//!
+//! Items are matched across machines by `content_hash`. Every local change to
+//! an item's synced state (new copy, favorite, groups, trash) bumps its
+//! `sync_seq` (see migration 015), so a peer asks for everything past the last
+//! seq it has seen and receives the full current state of each changed item.
+//!
+//! Conflicts are settled per item when a peer's state is applied:
+//! - the side whose `changed_at` is newer wins (favorite, groups, trash state);
+//! - an item copied again after the peer trashed it stays live;
+//! - a trashed item this machine never had is not created;
+//! - items removed for good (emptied trash, purges) are not propagated.
+//!
+//! Peers exchange changes directly over the local network ([`lan`]) or, as
+//! an alternative mode, through a folder shared by a file-sync tool ([`folder`]).
+
+pub mod folder;
+pub mod lan;
+
+use crate::archive::{self, ArchiveItem};
+use crate::clipboard as clip_util;
+use crate::crypto::{self, Key};
+use crate::db::{
+    models::{ContentType, ItemSyncState, SyncSummary, SyncedState},
+    queries,
+};
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Fixed salt for pairing keys: every machine must derive the same key from
+/// the same secret, and generated secrets carry enough entropy on their own.
+const PAIRING_SALT: &[u8] = b"recopy-sync-pairing";
+
+/// Crockford base32, without letters that are easily misread.
+const SECRET_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const SECRET_LEN: usize = 20;
+
+/// Most items sent per page of changes.
+const PAGE_ITEMS: i64 = 200;
+
+/// Image bytes after which a page of changes is cut short.
+const PAGE_IMAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// One item's state as sent to peers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncItem {
+    /// Content and metadata, in the export archive's form. `image_file`
+    /// carries the original image's file name (for its extension).
+    #[serde(flatten)]
+    pub item: ArchiveItem,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+    pub changed_at: String,
+    /// The original image of a live image item.
+    #[serde(
+        default,
+        with = "archive::base64_bytes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub image: Option<Vec<u8>>,
+}
+
+/// This machine's side of a sync.
+#[derive(Clone)]
+pub struct SyncContext {
+    pub pool: SqlitePool,
+    /// Key derived from the pairing secret.
+    pub key: Key,
+    pub device_id: String,
+    pub device_name: String,
+    /// Port this machine accepts peers on, announced to peers.
+    pub port: u16,
+    /// App data folder; received images are saved under its `images/`.
+    pub app_data: PathBuf,
+}
+
+// ---- Pairing ----
+
+/// Generate a pairing secret to enter on the other machines, e.g. `7KQ2-...`.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; SECRET_LEN];
+    OsRng.fill_bytes(&mut bytes);
+    let chars: Vec<char> = bytes
+        .iter()
+        .map(|b| SECRET_ALPHABET[*b as usize % SECRET_ALPHABET.len()] as char)
+        .collect();
+    chars
+        .chunks(4)
+        .map(|c| c.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Derive the pairing key from a secret. Case, spaces and dashes are ignored
+/// so the secret can be typed as displayed or not.
+pub fn derive_pairing_key(secret: &str) -> Result<Key, String> {
+    let normalized: String = secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .flat_map(char::to_uppercase)
+        .collect();
+    if normalized.len() < 8 {
+        return Err("Pairing secret is too short".to_string());
+    }
+    let mut key = [0u8; crypto::KEY_LEN];
+    Argon2::default()
+        .hash_password_into(normalized.as_bytes(), PAIRING_SALT, &mut key)
+        .map_err(|e| format!("Failed to derive key: {}", e))?;
+    Ok(key)
+}
+
+// ---- Conflict rules ----
+
+/// What to do with an item received from a peer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Merge {
+    Insert,
+    /// Take the peer's state, with the trash state settled as given.
+    Update {
+        deleted_at: Option<String>,
+    },
+    Skip,
+}
+
+/// Decide how a peer's item is applied given the local item with the same hash.
+pub fn merge(local: Option<&ItemSyncState>, remote: &SyncItem) -> Merge {
+    let Some(local) = local else {
+        return if remote.deleted_at.is_some() {
+            Merge::Skip
+        } else {
+            Merge::Insert
+        };
+    };
+    if remote.changed_at <= local.changed_at {
+        return Merge::Skip;
+    }
+    let deleted_at = match &remote.deleted_at {
+        // Copied again here after the peer trashed it
+        Some(at) if local.updated_at > *at => None,
+        other => other.clone(),
+    };
+    Merge::Update { deleted_at }
+}
+
+// ---- Changes ----
+
+/// A page of local changes past `since`: the items, the seq to continue from,
+/// and whether more are waiting. Images are read and attached to live items.
+pub async fn collect_changes(
+    pool: &SqlitePool,
+    since: i64,
+) -> Result<(Vec<SyncItem>, i64, bool), String> {
+    // Sealed content would be sent as empty text
+    if crypto::is_locked() {
+        return Err("History is locked".to_string());
+    }
+    let changes = queries::get_sync_changes(pool, since, PAGE_ITEMS)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut more = changes.len() as i64 == PAGE_ITEMS;
+
+    let mut items = Vec::with_capacity(changes.len());
+    let mut next = since;
+    let mut image_bytes = 0;
+    for (seq, mut item, image_path) in changes {
+        if image_bytes >= PAGE_IMAGE_BYTES {
+            more = true;
+            break;
+        }
+        if let (None, Some(path)) = (&item.deleted_at, image_path) {
+            match crypto::read_image_file(&path) {
+                Ok(data) => {
+                    image_bytes += data.len();
+                    item.item.image_file = archive::image_entry_name(&path);
+                    item.image = Some(data);
+                }
+                Err(e) => log::warn!("Sending item without its image {}: {}", path, e),
+            }
+        }
+        items.push(item);
+        next = seq;
+    }
+
+    Ok((items, next, more))
+}
+
+/// Apply items received from a peer, adding the outcome to `summary`.
+pub async fn apply_items(
+    ctx: &SyncContext,
+    items: Vec<SyncItem>,
+    summary: &mut SyncSummary,
+) -> Result<(), String> {
+    let mut groups = GroupIds::load(&ctx.pool).await?;
+    for item in items {
+        let local = queries::get_item_sync_state(&ctx.pool, &item.item.content_hash)
+            .await
+            .map_err(|e| e.to_string())?;
+        match merge(local.as_ref(), &item) {
+            Merge::Skip => {}
+            Merge::Insert => {
+                if insert_item(ctx, item, &mut groups).await? {
+                    summary.inserted += 1;
+                }
+            }
+            Merge::Update { deleted_at } => {
+                let Some(local) = local else { continue };
+                let state = SyncedState {
+                    is_favorited: item.item.is_favorited,
+                    updated_at: item.item.updated_at,
+                    deleted_at,
+                    changed_at: item.changed_at,
+                    group_ids: groups.resolve(&ctx.pool, &item.item.groups).await?,
+                };
+                queries::update_synced_item(&ctx.pool, &local.id, &state)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                summary.updated += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Insert a new item from a peer, saving its image first. Returns false for
+/// items of a type this version doesn't know.
+async fn insert_item(
+    ctx: &SyncContext,
+    item: SyncItem,
+    groups: &mut GroupIds,
+) -> Result<bool, String> {
+    let Some(content_type) = ContentType::from_str(&item.item.content_type) else {
+        log::warn!(
+            "Skipping synced item of unknown type {}",
+            item.item.content_type
+        );
+        return Ok(false);
+    };
+    let group_ids = groups.resolve(&ctx.pool, &item.item.groups).await?;
+    let image_path = match &item.image {
+        Some(data) => Some(save_image(
+            &ctx.app_data,
+            item.item.image_file.as_deref(),
+            data,
+        )?),
+        None => None,
+    };
+
+    let import = item
+        .item
+        .into_import(content_type, image_path.clone(), group_ids);
+    if let Err(e) = queries::insert_synced_item(&ctx.pool, &import, &item.changed_at).await {
+        if let Some(path) = image_path {
+            let _ = std::fs::remove_file(path);
+        }
+        return Err(e.to_string());
+    }
+    Ok(true)
+}
+
+/// Save a received image (sealed while the history is encrypted).
+fn save_image(app_data: &Path, name: Option<&str>, data: &[u8]) -> Result<String, String> {
+    let mut ext = name
+        .and_then(|n| Path::new(n).extension())
+        .and_then(|e| e.to_str())
+        .unwrap_or("png")
+        .to_string();
+    if crypto::is_enabled() {
+        ext.push_str(crypto::FILE_SUFFIX);
+    }
+    clip_util::save_original_image(app_data, &crypto::seal_bytes(data), &ext)
+}
+
+/// Group name to local id, creating groups that don't exist here yet.
+struct GroupIds(HashMap<String, String>);
+
+impl GroupIds {
+    async fn load(pool: &SqlitePool) -> Result<Self, String> {
+        let mut ids = HashMap::new();
+        for group in queries::get_groups(pool).await.map_err(|e| e.to_string())? {
+            ids.entry(group.name).or_insert(group.id);
+        }
+        Ok(GroupIds(ids))
+    }
+
+    async fn resolve(
+        &mut self,
+        pool: &SqlitePool,
+        names: &[String],
+    ) -> Result<Vec<String>, String> {
+        let mut out = Vec::with_capacity(names.len());
+        for name in names {
+            let id = match self.0.get(name) {
+                Some(id) => id.clone(),
+                None => {
+                    let id = queries::create_group(pool, name)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    self.0.insert(name.clone(), id.clone());
+                    id
+                }
+            };
+            out.push(id);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote(changed_at: &str, deleted_at: Option<&str>) -> SyncItem {
+        SyncItem {
+            item: ArchiveItem {
+                content_type: "plain_text".into(),
+                plain_text: "text".into(),
+                rich_content: None,
+                thumbnail: None,
+                image_file: None,
+                file_path: None,
+                file_name: None,
+                files: vec![],
+                source_app: "".into(),
+                source_app_name: "".into(),
+                content_size: 4,
+                content_hash: "hash".into(),
+                is_favorited: false,
+                sensitive: false,
+                image_width: None,
+                image_height: None,
+                image_format: None,
+                image_phash: None,
+                groups: vec![],
+                created_at: "2026-03-01 09:00:00".into(),
+                updated_at: "2026-03-01 09:00:00".into(),
+            },
+            deleted_at: deleted_at.map(String::from),
+            changed_at: changed_at.into(),
+            image: None,
+        }
+    }
+
+    fn local(updated_at: &str, changed_at: &str) -> ItemSyncState {
+        ItemSyncState {
+            id: "local".into(),
+            updated_at: updated_at.into(),
+            deleted_at: None,
+            changed_at: changed_at.into(),
+        }
+    }
+
+    #[test]
+    fn test_merge_rules() {
+        // Unknown content is inserted, unless it is already trashed
+        assert_eq!(
+            merge(None, &remote("2026-03-01 09:00:00.000", None)),
+            Merge::Insert
+        );
+        assert_eq!(
+            merge(
+                None,
+                &remote("2026-03-01 09:00:00.000", Some("2026-03-01 09:00:00"))
+            ),
+            Merge::Skip
+        );
+
+        // The newer change wins
+        let here = local("2026-03-01 09:00:00", "2026-03-01 10:00:00.000");
+        assert_eq!(
+            merge(Some(&here), &remote("2026-03-01 09:30:00.000", None)),
+            Merge::Skip
+        );
+        assert_eq!(
+            merge(Some(&here), &remote("2026-03-01 10:00:00.000", None)),
+            Merge::Skip
+        );
+        assert_eq!(
+            merge(
+                Some(&here),
+                &remote("2026-03-01 11:00:00.000", Some("2026-03-01 11:00:00"))
+            ),
+            Merge::Update {
+                deleted_at: Some("2026-03-01 11:00:00".into())
+            }
+        );
+
+        // Copied again here after the peer trashed it: stays live
+        let recopied = local("2026-03-01 12:00:00", "2026-03-01 10:00:00.000");
+        assert_eq!(
+            merge(
+                Some(&recopied),
+                &remote("2026-03-01 13:00:00.000", Some("2026-03-01 11:00:00"))
+            ),
+            Merge::Update { deleted_at: None }
+        );
+    }
+
+    #[test]
+    fn test_pairing_secret() {
+        let secret = generate_secret();
+        assert_eq!(secret.len(), SECRET_LEN + SECRET_LEN / 4 - 1);
+        assert!(secret
+            .chars()
+            .all(|c| c == '-' || SECRET_ALPHABET.contains(&(c as u8))));
+        assert_ne!(secret, generate_secret());
+
+        // Typed differently, same key
+        let key = derive_pairing_key("ABCD-EFGH-JKMN").unwrap();
+        assert_eq!(derive_pairing_key(" abcd efgh jkmn ").unwrap(), key);
+        assert_ne!(derive_pairing_key("ABCD-EFGH-JKMP").unwrap(), key);
+        assert!(derive_pairing_key("abc").is_err());
+    }
+
+    #[test]
+    fn test_sync_item_wire_format() {
+        let mut item = remote("2026-03-01 09:00:00.000", None);
+        item.image = Some(vec![1, 2, 3]);
+        let json = serde_json::to_value(&item).unwrap();
+        // Archive fields are inlined next to the sync fields
+        assert_eq!(json["content_hash"], "hash");
+        assert_eq!(json["image"], "AQID");
+        assert!(json.get("deleted_at").is_none());
+
+        let back: SyncItem = serde_json::from_value(json).unwrap();
+        assert_eq!(back.image, Some(vec![1, 2, 3]));
+        assert_eq!(back.item.plain_text, "text");
+    }
+}
@@ -0,0 +1,589 @@
+//! Text transformations applied at paste time. Each one is a plain function
+//! from text to text; a pipeline applies several in order, e.g. `trim` then
+//! `snake_case`. Transforms that can't handle their input (invalid Base64,
+//! JSON, …) fail rather than paste something half-converted.
+
+use base64::Engine;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transform {
+    Upper,
+    Lower,
+    Title,
+    Snake,
+    Camel,
+    Trim,
+    CollapseWhitespace,
+    UrlEncode,
+    UrlDecode,
+    Base64Encode,
+    Base64Decode,
+    JsonPretty,
+    JsonMinify,
+    ShellEscape,
+    SqlEscape,
+    RegexEscape,
+    StripHtml,
+    SortLines,
+    DedupeLines,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 19] = [
+        Transform::Upper,
+        Transform::Lower,
+        Transform::Title,
+        Transform::Snake,
+        Transform::Camel,
+        Transform::Trim,
+        Transform::CollapseWhitespace,
+        Transform::UrlEncode,
+        Transform::UrlDecode,
+        Transform::Base64Encode,
+        Transform::Base64Decode,
+        Transform::JsonPretty,
+        Transform::JsonMinify,
+        Transform::ShellEscape,
+        Transform::SqlEscape,
+        Transform::RegexEscape,
+        Transform::StripHtml,
+        Transform::SortLines,
+        Transform::DedupeLines,
+    ];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Transform::Upper => "upper_case",
+            Transform::Lower => "lower_case",
+            Transform::Title => "title_case",
+            Transform::Snake => "snake_case",
+            Transform::Camel => "camel_case",
+            Transform::Trim => "trim",
+            Transform::CollapseWhitespace => "collapse_whitespace",
+            Transform::UrlEncode => "url_encode",
+            Transform::UrlDecode => "url_decode",
+            Transform::Base64Encode => "base64_encode",
+            Transform::Base64Decode => "base64_decode",
+            Transform::JsonPretty => "json_pretty",
+            Transform::JsonMinify => "json_minify",
+            Transform::ShellEscape => "shell_escape",
+            Transform::SqlEscape => "sql_escape",
+            Transform::RegexEscape => "regex_escape",
+            Transform::StripHtml => "strip_html",
+            Transform::SortLines => "sort_lines",
+            Transform::DedupeLines => "dedupe_lines",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == s)
+    }
+
+    pub fn apply(&self, text: &str) -> Result<String, String> {
+        Ok(match self {
+            Transform::Upper => text.to_uppercase(),
+            Transform::Lower => text.to_lowercase(),
+            Transform::Title => title_case(text),
+            Transform::Snake => snake_case(text),
+            Transform::Camel => camel_case(text),
+            Transform::Trim => text.trim().to_string(),
+            Transform::CollapseWhitespace => collapse_whitespace(text),
+            Transform::UrlEncode => url_encode(text),
+            Transform::UrlDecode => url_decode(text)?,
+            Transform::Base64Encode => base64_encode(text),
+            Transform::Base64Decode => base64_decode(text)?,
+            Transform::JsonPretty => json_pretty(text)?,
+            Transform::JsonMinify => json_minify(text)?,
+            Transform::ShellEscape => shell_escape(text),
+            Transform::SqlEscape => sql_escape(text),
+            Transform::RegexEscape => regex_escape(text),
+            Transform::StripHtml => strip_html(text),
+            Transform::SortLines => sort_lines(text),
+            Transform::DedupeLines => dedupe_lines(text),
+        })
+    }
+}
+
+/// Parse a pipeline of transform names. An empty pipeline is an error: it
+/// would paste the text unchanged.
+pub fn parse_pipeline(names: &[String]) -> Result<Vec<Transform>, String> {
+    if names.is_empty() {
+        return Err("Choose at least one transformation".to_string());
+    }
+    names
+        .iter()
+        .map(|name| {
+            Transform::from_str(name).ok_or_else(|| format!("Unknown transformation {}", name))
+        })
+        .collect()
+}
+
+/// Apply each transform in turn.
+pub fn apply_all(text: &str, pipeline: &[Transform]) -> Result<String, String> {
+    pipeline
+        .iter()
+        .try_fold(text.to_string(), |text, transform| transform.apply(&text))
+}
+
+// ---- Case ----
+
+/// Capitalize the first letter of every whitespace-separated word.
+pub fn title_case(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut word_start = true;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            out.push(c);
+            word_start = true;
+        } else if word_start {
+            out.extend(c.to_uppercase());
+            word_start = false;
+        } else {
+            out.extend(c.to_lowercase());
+        }
+    }
+    out
+}
+
+/// Split identifiers and phrases into words: at anything that isn't a letter
+/// or digit, and at case changes ("parseHTTPRequest" → parse, HTTP, Request).
+fn words(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !current.is_empty() {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_lower) {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+pub fn snake_case(text: &str) -> String {
+    words(text)
+        .iter()
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+pub fn camel_case(text: &str) -> String {
+    words(text)
+        .iter()
+        .enumerate()
+        .map(|(i, w)| {
+            let lower = w.to_lowercase();
+            if i == 0 {
+                return lower;
+            }
+            let mut chars = lower.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+// ---- Whitespace and lines ----
+
+/// Turn every run of whitespace, line breaks included, into one space.
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Split into lines and rejoin after `f`, keeping a trailing line break.
+fn map_lines(text: &str, f: impl FnOnce(Vec<&str>) -> Vec<&str>) -> String {
+    let mut out = f(text.lines().collect()).join("\n");
+    if text.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+pub fn sort_lines(text: &str) -> String {
+    map_lines(text, |mut lines| {
+        lines.sort();
+        lines
+    })
+}
+
+/// Drop repeated lines, keeping the first of each.
+pub fn dedupe_lines(text: &str) -> String {
+    map_lines(text, |lines| {
+        let mut seen = std::collections::HashSet::new();
+        lines
+            .into_iter()
+            .filter(|line| seen.insert(*line))
+            .collect()
+    })
+}
+
+// ---- Encoding ----
+
+/// Percent-encode everything but RFC 3986 unreserved characters, so the
+/// result is safe anywhere in a URL.
+pub fn url_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// Decode %XX escapes; `+` is left as is. Malformed escapes are kept as text.
+pub fn url_decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).map_err(|_| "Decoded URL text is not valid UTF-8".to_string())
+}
+
+pub fn base64_encode(text: &str) -> String {
+    base64::engine::general_purpose::STANDARD.encode(text)
+}
+
+/// Decode standard or URL-safe Base64, padded or not, ignoring whitespace.
+pub fn base64_decode(text: &str) -> Result<String, String> {
+    use base64::engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD};
+
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let compact = compact.trim_end_matches('=');
+    let bytes = STANDARD_NO_PAD
+        .decode(compact)
+        .or_else(|_| URL_SAFE_NO_PAD.decode(compact))
+        .map_err(|_| "Text is not valid Base64".to_string())?;
+    String::from_utf8(bytes).map_err(|_| "Decoded Base64 is not text".to_string())
+}
+
+// ---- JSON ----
+
+// Reformatting works on the text rather than a parsed value, so key order
+// and number spelling are kept exactly as written.
+
+fn check_json(text: &str) -> Result<(), String> {
+    serde_json::from_str::<serde::de::IgnoredAny>(text)
+        .map(|_| ())
+        .map_err(|e| format!("Invalid JSON: {}", e))
+}
+
+/// Call `f` with each character outside strings; characters inside strings
+/// (and the quotes) are copied as is.
+fn rewrite_json(
+    text: &str,
+    mut f: impl FnMut(char, &mut String, &mut std::iter::Peekable<std::str::Chars>),
+) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    let mut escaped = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            out.push(c);
+            in_string = true;
+        } else if !c.is_whitespace() {
+            f(c, &mut out, &mut chars);
+        }
+    }
+    out
+}
+
+pub fn json_minify(text: &str) -> Result<String, String> {
+    check_json(text)?;
+    Ok(rewrite_json(text, |c, out, _| out.push(c)))
+}
+
+/// Indent with two spaces, one member per line; empty arrays and objects
+/// stay on one line.
+pub fn json_pretty(text: &str) -> Result<String, String> {
+    check_json(text)?;
+    let mut depth = 0usize;
+    let newline = |out: &mut String, depth: usize| {
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth));
+    };
+    Ok(rewrite_json(text, |c, out, rest| match c {
+        '{' | '[' => {
+            out.push(c);
+            while rest.peek().is_some_and(|n| n.is_whitespace()) {
+                rest.next();
+            }
+            if matches!(rest.peek(), Some('}' | ']')) {
+                out.extend(rest.next());
+            } else {
+                depth += 1;
+                newline(out, depth);
+            }
+        }
+        '}' | ']' => {
+            depth = depth.saturating_sub(1);
+            newline(out, depth);
+            out.push(c);
+        }
+        ',' => {
+            out.push(c);
+            newline(out, depth);
+        }
+        ':' => out.push_str(": "),
+        _ => out.push(c),
+    }))
+}
+
+// ---- Escaping ----
+
+/// Quote for a POSIX shell: one single-quoted word.
+pub fn shell_escape(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// Quote as an SQL string literal.
+pub fn sql_escape(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// Backslash the regex metacharacters. Nothing else: JavaScript's `u` and `v`
+/// modes reject needless escapes such as `\#` or `\~`.
+pub fn regex_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+// ---- HTML ----
+
+/// Remove tags, comments, scripts and styles, decode entities, and keep line
+/// breaks where block elements ended.
+pub fn strip_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&decode_entities(&rest[..start]));
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            out.push_str(&decode_entities(rest));
+            rest = "";
+            break;
+        };
+        let tag = rest[1..end].trim().to_ascii_lowercase();
+        rest = &rest[end + 1..];
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        if matches!(name.as_str(), "script" | "style") && !tag.starts_with('/') {
+            let close = format!("</{}", name);
+            rest = rest
+                .to_ascii_lowercase()
+                .find(&close)
+                .and_then(|i| rest[i..].find('>').map(|j| &rest[i + j + 1..]))
+                .unwrap_or("");
+            continue;
+        }
+        let breaks_line = name == "br"
+            || (tag.starts_with('/')
+                && matches!(
+                    name.as_str(),
+                    "p" | "div" | "li" | "tr" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+                ));
+        if breaks_line {
+            out.push('\n');
+        }
+    }
+    out.push_str(&decode_entities(rest));
+    out.trim().to_string()
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                entity => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case() {
+        assert_eq!(Transform::Upper.apply("Grüße").unwrap(), "GRÜSSE");
+        assert_eq!(Transform::Lower.apply("HeLLo").unwrap(), "hello");
+        assert_eq!(title_case("the QUICK\tbrown-fox"), "The Quick\tBrown-fox");
+
+        assert_eq!(snake_case("parseHTTPRequest"), "parse_http_request");
+        assert_eq!(snake_case("  Hello, big World! "), "hello_big_world");
+        assert_eq!(
+            snake_case("version2Beta kebab-case"),
+            "version2_beta_kebab_case"
+        );
+        assert_eq!(camel_case("user_id"), "userId");
+        assert_eq!(camel_case("Parse HTTP request"), "parseHttpRequest");
+        assert_eq!(camel_case(""), "");
+    }
+
+    #[test]
+    fn test_whitespace_and_lines() {
+        assert_eq!(Transform::Trim.apply("\n  a b \t\n").unwrap(), "a b");
+        assert_eq!(collapse_whitespace("  a \t b\n\n c  "), "a b c");
+        assert_eq!(sort_lines("b\nc\na\n"), "a\nb\nc\n");
+        assert_eq!(sort_lines("b\r\na"), "a\nb");
+        assert_eq!(dedupe_lines("x\ny\nx\n\ny\n\n"), "x\ny\n\n");
+    }
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(url_encode("a b/ü?x=1&y~"), "a%20b%2F%C3%BC%3Fx%3D1%26y~");
+        assert_eq!(url_decode("a%20b%2f%C3%BC+%zz%").unwrap(), "a b/ü+%zz%");
+        assert!(url_decode("%FF").is_err());
+
+        assert_eq!(base64_encode("hello?"), "aGVsbG8/");
+        assert_eq!(base64_decode("aGVsbG8/").unwrap(), "hello?");
+        assert_eq!(base64_decode("aGVsbG8_\n").unwrap(), "hello?");
+        assert_eq!(base64_decode("aGk=").unwrap(), "hi");
+        assert!(base64_decode("not base64!").is_err());
+        assert!(base64_decode("/w==").is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let json = r#"{ "b": [1, 2.50, {}], "a": "x, \"y\": [z]", "e": [ ] }"#;
+        assert_eq!(
+            json_minify(json).unwrap(),
+            r#"{"b":[1,2.50,{}],"a":"x, \"y\": [z]","e":[]}"#
+        );
+        assert_eq!(
+            json_pretty(json).unwrap(),
+            "{\n  \"b\": [\n    1,\n    2.50,\n    {}\n  ],\n  \"a\": \"x, \\\"y\\\": [z]\",\n  \"e\": []\n}"
+        );
+        assert_eq!(json_pretty("\"s\"").unwrap(), "\"s\"");
+        assert!(json_pretty("{\"a\": }").is_err());
+        assert!(json_minify("[1,]").is_err());
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(shell_escape("it's $HOME"), r#"'it'\''s $HOME'"#);
+        assert_eq!(sql_escape("O'Brien"), "'O''Brien'");
+        assert_eq!(regex_escape("a.b*(c)/[d]"), r"a\.b\*\(c\)/\[d\]");
+        assert_eq!(regex_escape("#a&b-c~d"), "#a&b-c~d");
+    }
+
+    #[test]
+    fn test_strip_html() {
+        assert_eq!(
+            strip_html("<p>Fish &amp; chips</p><div>a&lt;b<br/>&#233;&#x41;</div>"),
+            "Fish & chips\na<b\néA"
+        );
+        assert_eq!(
+            strip_html("<style>p { x: 1 }</style><!-- note -->Hi<SCRIPT>x < 1</script> there &bogus; 1 < 2"),
+            "Hi there &bogus; 1 < 2"
+        );
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let names: Vec<String> = ["trim", "snake_case", "upper_case"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let pipeline = parse_pipeline(&names).unwrap();
+        assert_eq!(
+            apply_all("  Max retry Count ", &pipeline).unwrap(),
+            "MAX_RETRY_COUNT"
+        );
+
+        assert!(parse_pipeline(&[]).is_err());
+        assert!(parse_pipeline(&["shout".to_string()]).is_err());
+        assert!(apply_all("{", &[Transform::JsonPretty]).is_err());
+
+        for transform in Transform::ALL {
+            assert_eq!(Transform::from_str(transform.as_str()), Some(transform));
+        }
+    }
+}
@@ -12,9 +12,11 @@ import { ViewTabs } from "./components/ViewTabs";
 import { ClipboardList } from "./components/ClipboardList";
 import { SettingsPage } from "./components/SettingsPage";
 import { UpdateBanner } from "./components/UpdateBanner";
+import { LockScreen } from "./components/LockScreen";
 import { useClipboardStore } from "./stores/clipboard-store";
 import { useSettingsStore, type ShowEventPayload } from "./stores/settings-store";
 import { useUpdateStore } from "./stores/update-store";
+import { useLockStore } from "./stores/lock-store";
 import { useKeyboardNav } from "./hooks/useKeyboardNav";
 
 // Expose update store to devtools console for UI testing
@@ -44,11 +46,15 @@ function MainApp() {
   const fetchItems = useClipboardStore((s) => s.fetchItems);
   const refreshOnChange = useClipboardStore((s) => s.refreshOnChange);
   const onPanelShow = useClipboardStore((s) => s.onPanelShow);
+  const presetSearch = useClipboardStore((s) => s.presetSearch);
   const loadSettings = useSettingsStore((s) => s.loadSettings);
   const syncSettingsFromEvent = useSettingsStore((s) => s.syncSettingsFromEvent);
   const settingsLoaded = useSettingsStore((s) => s.loaded);
   const updateCheckInterval = useSettingsStore((s) => s.settings.update_check_interval);
   const checkForUpdate = useUpdateStore((s) => s.checkForUpdate);
+  const locked = useLockStore((s) => s.locked);
+  const checkLock = useLockStore((s) => s.checkLock);
+  const setLocked = useLockStore((s) => s.setLocked);
   const panelRef = useRef<HTMLDivElement>(null);
 
   // Keyboard navigation
@@ -69,19 +75,36 @@ function MainApp() {
     return () => clearInterval(timer);
   }, [settingsLoaded, checkForUpdate, updateCheckInterval]);
 
-  // Initial load
+  // Initial load (history stays empty until unlocked)
   useEffect(() => {
-    fetchItems();
-  }, [fetchItems]);
+    checkLock().then(() => {
+      if (!useLockStore.getState().locked) fetchItems();
+    });
+  }, [checkLock, fetchItems]);
 
-  // Listen for clipboard change events from Rust backend
+  // Backend locked the history (idle timeout, system sleep, or lock_app)
   useEffect(() => {
-    const unlisten = listen("clipboard-changed", () => {
-      refreshOnChange();
+    const unlisten = listen("recopy-locked", () => {
+      setLocked(true);
     });
     return () => {
       unlisten.then((fn) => fn());
     };
+  }, [setLocked]);
+
+  // Listen for clipboard change events from Rust backend
+  useEffect(() => {
+    const onChange = () => {
+      if (useLockStore.getState().locked) return;
+      refreshOnChange();
+    };
+    const unlistenChanged = listen("clipboard-changed", onChange);
+    // Expired items removed by the periodic purge
+    const unlistenPurged = listen("items-purged", onChange);
+    return () => {
+      unlistenChanged.then((fn) => fn());
+      unlistenPurged.then((fn) => fn());
+    };
   }, [refreshOnChange]);
 
   // Dismiss context menus on blur (always needed regardless of close_on_blur setting).
@@ -102,6 +125,17 @@ function MainApp() {
     };
   }, []);
 
+  // recopy://search links set the query just before the panel shows,
+  // and the show handler below runs the search.
+  useEffect(() => {
+    const unlisten = listen<{ query: string }>("recopy-search", (event) => {
+      presetSearch(event.payload.query);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [presetSearch]);
+
   // Reset panel visual state when the backend decides to hide the window.
   // This is driven by the "recopy-hide" event from Rust (emitted by hide_main_window),
   // NOT by blur — so close_on_blur=false correctly keeps content visible.
@@ -124,7 +158,9 @@ function MainApp() {
   useEffect(() => {
     const unlisten = listen<ShowEventPayload>("recopy-show", async (event) => {
       syncSettingsFromEvent(event.payload);
-      await onPanelShow();
+      const isLocked = event.payload.locked ?? false;
+      setLocked(isLocked);
+      if (!isLocked) await onPanelShow();
 
       // Wait for React re-render + useLayoutEffect (scroll) to complete before animating.
       // requestAnimationFrame fires after layout/paint, ensuring scroll has settled.
@@ -149,7 +185,7 @@ function MainApp() {
     return () => {
       unlisten.then((fn) => fn());
     };
-  }, [onPanelShow, syncSettingsFromEvent]);
+  }, [onPanelShow, syncSettingsFromEvent, setLocked]);
 
   const panelPosition = useSettingsStore((s) => s.settings.panel_position);
   const menuBarHeight = useSettingsStore((s) => s.menuBarHeight);
@@ -220,7 +256,7 @@ function MainApp() {
           className="flex-1 min-h-0 pb-1"
           style={isTop && menuBarHeight > 0 ? { paddingTop: menuBarHeight } : undefined}
         >
-          <ClipboardList />
+          {locked ? <LockScreen onUnlock={fetchItems} /> : <ClipboardList />}
         </div>
       </div>
     </div>
@@ -71,7 +71,9 @@ export function FileCard({ item, selected, onClick }: FileCardProps) {
             </p>
             <p className="text-sm text-muted-foreground">
               {formatSize(item.content_size)}
-              {ext && ` \u00B7 .${ext}`}
+              {item.file_count > 1
+                ? ` \u00B7 ${t("card.fileCount", { count: item.file_count })}`
+                : ext && ` \u00B7 .${ext}`}
             </p>
           </div>
         </div>
@@ -17,6 +17,14 @@ export function ImageCard({ item, selected, onClick }: ImageCardProps) {
     enableKeyboardHandler: true,
   });
   const thumbnailUrl = useThumbnail(item.id);
+  const imageMeta = [
+    item.image_width && item.image_height
+      ? `${item.image_width}\u00D7${item.image_height}`
+      : "",
+    item.image_format?.toUpperCase() ?? "",
+  ]
+    .filter(Boolean)
+    .join(" \u00B7 ");
 
   return (
     <div
@@ -42,7 +50,8 @@ export function ImageCard({ item, selected, onClick }: ImageCardProps) {
           <ImageIcon size={32} className="text-muted-foreground/40" />
         )}
       </div>
-      <div className="flex items-center justify-end text-sm text-muted-foreground mt-auto pt-1.5">
+      <div className="flex items-center justify-between text-sm text-muted-foreground mt-auto pt-1.5">
+        <span>{imageMeta}</span>
         <span>{relativeTime(item.updated_at)}</span>
       </div>
     </div>
@@ -0,0 +1,63 @@
+import { useEffect, useRef, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { Lock } from "lucide-react";
+import { useLockStore } from "../stores/lock-store";
+import { Input } from "./ui/input";
+import { Button } from "./ui/button";
+
+interface LockScreenProps {
+  onUnlock: () => void;
+}
+
+export function LockScreen({ onUnlock }: LockScreenProps) {
+  const { t } = useTranslation();
+  const inputRef = useRef<HTMLInputElement>(null);
+  const [passphrase, setPassphrase] = useState("");
+  const unlocking = useLockStore((s) => s.unlocking);
+  const error = useLockStore((s) => s.error);
+  const unlock = useLockStore((s) => s.unlock);
+
+  useEffect(() => {
+    inputRef.current?.focus();
+  }, []);
+
+  const handleUnlock = async () => {
+    if (!passphrase || unlocking) return;
+    const ok = await unlock(passphrase);
+    setPassphrase("");
+    if (ok) {
+      onUnlock();
+    } else {
+      inputRef.current?.focus();
+    }
+  };
+
+  return (
+    <div className="h-full flex flex-col items-center justify-center gap-3">
+      <Lock size={28} className="text-muted-foreground" />
+      <span className="text-sm font-medium text-foreground">{t("lock.title")}</span>
+      <div className="flex items-center gap-2 w-64">
+        <Input
+          ref={inputRef}
+          type="password"
+          value={passphrase}
+          placeholder={t("lock.placeholder")}
+          aria-invalid={error ? true : undefined}
+          disabled={unlocking}
+          onChange={(e) => setPassphrase(e.target.value)}
+          onKeyDown={(e) => {
+            if (e.key === "Enter") {
+              e.preventDefault();
+              handleUnlock();
+            }
+          }}
+          className="h-8"
+        />
+        <Button size="sm" disabled={!passphrase || unlocking} onClick={handleUnlock}>
+          {t("lock.unlock")}
+        </Button>
+      </div>
+      {error && <span className="text-xs text-destructive">{error}</span>}
+    </div>
+  );
+}
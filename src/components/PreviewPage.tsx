@@ -87,7 +87,7 @@ function PreviewContent({ detail }: { detail: ItemDetail }) {
     case "image":
       return (
         <WithTitleBar title={getTitle(detail)} size={detail.content_size}>
-          <ImagePreview imagePath={detail.image_path} />
+          <ImagePreview id={detail.id} imagePath={detail.image_path} />
         </WithTitleBar>
       );
     case "file":
@@ -139,7 +139,7 @@ function WithTitleBar({
 function getTitle(detail: ItemDetail): string {
   if (detail.file_name) return detail.file_name;
   if (detail.content_type === "image" && detail.image_path) {
-    const parts = detail.image_path.split("/");
+    const parts = detail.image_path.replace(/\.enc$/, "").split("/");
     return parts[parts.length - 1] || "Image";
   }
   if (detail.file_path) {
@@ -231,8 +231,37 @@ function RichTextPreview({ html, fallback }: { html?: string; fallback: string }
   );
 }
 
-function ImagePreview({ imagePath }: { imagePath?: string }) {
-  const assetUrl = imagePath ? convertFileSrc(imagePath) : null;
+function ImagePreview({ id, imagePath }: { id: string; imagePath?: string }) {
+  // Encrypted originals can't be served by the asset protocol; decrypt via IPC
+  const encrypted = imagePath?.endsWith(".enc") ?? false;
+  const [blobUrl, setBlobUrl] = useState<string | null>(null);
+  const [failed, setFailed] = useState(false);
+
+  useEffect(() => {
+    if (!encrypted) return;
+    let cancelled = false;
+    let objectUrl: string | null = null;
+    setBlobUrl(null);
+    setFailed(false);
+    invoke<number[]>("get_image_data", { id })
+      .then((data) => {
+        if (cancelled) return;
+        objectUrl = URL.createObjectURL(new Blob([new Uint8Array(data)]));
+        setBlobUrl(objectUrl);
+      })
+      .catch((e) => {
+        console.error("Failed to load image:", e);
+        if (!cancelled) setFailed(true);
+      });
+    return () => {
+      cancelled = true;
+      if (objectUrl) URL.revokeObjectURL(objectUrl);
+    };
+  }, [id, encrypted]);
+
+  if (encrypted && !blobUrl && !failed) return null;
+
+  const assetUrl = encrypted ? blobUrl : imagePath ? convertFileSrc(imagePath) : null;
 
   if (!assetUrl) {
     return (
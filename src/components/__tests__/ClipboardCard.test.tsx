@@ -30,6 +30,8 @@ const mockItem = (overrides: Partial<ClipboardItem> = {}): ClipboardItem => ({
   content_size: 11,
   content_hash: "abc",
   is_favorited: false,
+  sensitive: false,
+  file_count: 0,
   created_at: "2026-02-23 10:00:00",
   updated_at: "2026-02-23 10:00:00",
   ...overrides,
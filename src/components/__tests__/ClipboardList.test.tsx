@@ -85,6 +85,8 @@ const mockItem = (overrides: Partial<ClipboardItem> = {}): ClipboardItem => ({
   content_size: 5,
   content_hash: "hash",
   is_favorited: false,
+  sensitive: false,
+  file_count: 0,
   created_at: "2026-03-18 10:00:00",
   updated_at: "2026-03-18 10:00:00",
   ...overrides,
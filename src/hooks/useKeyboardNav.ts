@@ -3,6 +3,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { useClipboardStore } from "../stores/clipboard-store";
 import { useSettingsStore } from "../stores/settings-store";
+import { useLockStore } from "../stores/lock-store";
 import { useCopyHud } from "../components/CopyHud";
 import { pasteItem, copyToClipboard } from "../lib/paste";
 import { dateGroupLabel } from "../lib/time";
@@ -71,6 +72,15 @@ export function useKeyboardNav() {
       // Skip all keyboard shortcuts during IME composition (e.g. Chinese input)
       if (e.isComposing || e.keyCode === 229) return;
 
+      // Locked: the unlock prompt handles its own keys; Escape still hides the panel
+      if (useLockStore.getState().locked) {
+        if (e.key === "Escape") {
+          e.preventDefault();
+          invoke("hide_window");
+        }
+        return;
+      }
+
       const modifierHeld = e.metaKey || e.ctrlKey;
       setModifierHeld(modifierHeld);
 
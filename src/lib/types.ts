@@ -13,10 +13,48 @@ export interface ClipboardItem {
   content_size: number;
   content_hash: string;
   is_favorited: boolean;
+  sensitive: boolean;
+  file_count: number;
+  image_width?: number;
+  image_height?: number;
+  image_format?: string;
+  deleted_at?: string;
   created_at: string;
   updated_at: string;
 }
 
+export interface ItemFile {
+  path: string;
+  name: string;
+  size: number;
+  is_dir: boolean;
+}
+
+export interface Group {
+  id: string;
+  name: string;
+  sort_order: number;
+  item_count: number;
+  created_at: string;
+}
+
+export interface Snippet {
+  id: string;
+  name: string;
+  content: string;
+  created_at: string;
+  updated_at: string;
+}
+
+export type IgnoreMatchType = "bundle_id" | "executable" | "window_title";
+
+export interface IgnoredApp {
+  id: string;
+  match_type: IgnoreMatchType;
+  pattern: string;
+  created_at: string;
+}
+
 export interface ItemDetail {
   id: string;
   content_type: ContentType;
@@ -39,5 +77,91 @@ export interface FilePreviewData {
   total_lines: number;
 }
 
+export type EncryptionMode = "off" | "passphrase" | "keychain";
+
+export interface EncryptionStatus {
+  mode: EncryptionMode;
+  locked: boolean;
+}
+
+export interface LockStatus {
+  enabled: boolean;
+  locked: boolean;
+}
+
+export interface ExportFilter {
+  content_types?: ContentType[];
+  favorites_only?: boolean;
+  /** SQLite datetime (UTC), compared against updated_at */
+  since?: string;
+  until?: string;
+}
+
+export interface ExportSummary {
+  items: number;
+  images: number;
+}
+
+export interface ImportSummary {
+  imported: number;
+  merged: number;
+  groups_created: number;
+  settings: number;
+}
+
+export interface BackupInfo {
+  id: string;
+  created_at: string;
+  size: number;
+}
+
+export interface SyncPeer {
+  device_id: string;
+  name: string;
+  address: string | null;
+  last_sync_at: string | null;
+  online: boolean;
+}
+
+export interface SyncStatus {
+  enabled: boolean;
+  paired: boolean;
+  mode: "lan" | "folder";
+  folder: string | null;
+  device_id: string;
+  device_name: string;
+  port: number;
+  peers: SyncPeer[];
+}
+
+export interface SyncSummary {
+  peers: number;
+  inserted: number;
+  updated: number;
+  sent: number;
+}
+
+export interface HttpApiStatus {
+  enabled: boolean;
+  running: boolean;
+  port: number;
+  token: string | null;
+}
+
+export interface PasteQueueStatus {
+  collecting: boolean;
+  order: "fifo" | "lifo";
+  shortcut: string;
+  items: ClipboardItem[];
+}
+
+export type MergeFormat = "newline" | "comma" | "tab" | "markdown_list" | "json_array";
+
+export interface TransformShortcut {
+  shortcut: string;
+  /** Transform names from get_transforms, applied in order */
+  transforms: string[];
+}
+
 export type ViewMode = "history" | "pins";
 export type FilterType = "all" | "plain_text" | "rich_text" | "image" | "file" | "link";
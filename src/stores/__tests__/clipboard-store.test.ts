@@ -14,6 +14,8 @@ const mockItem = (overrides: Partial<ClipboardItem> = {}): ClipboardItem => ({
   content_size: 11,
   content_hash: "abc123",
   is_favorited: false,
+  sensitive: false,
+  file_count: 0,
   created_at: "2026-02-23 10:00:00",
   updated_at: "2026-02-23 10:00:00",
   ...overrides,
@@ -121,6 +123,17 @@ describe("useClipboardStore", () => {
     expect(useClipboardStore.getState().selectedIndex).toBe(0);
   });
 
+  it("should preset a history search without fetching", () => {
+    useClipboardStore.setState({ viewMode: "pins", selectedIndex: 3 });
+    useClipboardStore.getState().presetSearch("docker");
+
+    const state = useClipboardStore.getState();
+    expect(state.viewMode).toBe("history");
+    expect(state.searchQuery).toBe("docker");
+    expect(state.selectedIndex).toBe(0);
+    expect(mockedInvoke).not.toHaveBeenCalled();
+  });
+
   it("should change view mode", () => {
     mockedInvoke.mockResolvedValueOnce([]);
     useClipboardStore.getState().setViewMode("pins");
@@ -0,0 +1,78 @@
+import { describe, it, expect, vi, beforeEach } from "vitest";
+import { invoke } from "@tauri-apps/api/core";
+import { useLockStore } from "../lock-store";
+import { useClipboardStore } from "../clipboard-store";
+import type { ClipboardItem } from "../../lib/types";
+
+const mockedInvoke = vi.mocked(invoke);
+
+describe("useLockStore", () => {
+  beforeEach(() => {
+    vi.clearAllMocks();
+    useLockStore.setState({ locked: false, unlocking: false, error: null });
+    useClipboardStore.setState({
+      items: [{ id: "a" } as ClipboardItem],
+      selectedIndex: 0,
+    });
+  });
+
+  describe("checkLock", () => {
+    it("should adopt the backend lock state", async () => {
+      mockedInvoke.mockResolvedValueOnce({ enabled: true, locked: true });
+
+      await useLockStore.getState().checkLock();
+
+      expect(mockedInvoke).toHaveBeenCalledWith("get_lock_status");
+      expect(useLockStore.getState().locked).toBe(true);
+    });
+
+    it("should stay unlocked when the status call fails", async () => {
+      mockedInvoke.mockRejectedValueOnce(new Error("boom"));
+
+      await useLockStore.getState().checkLock();
+
+      expect(useLockStore.getState().locked).toBe(false);
+    });
+  });
+
+  describe("setLocked", () => {
+    it("should clear loaded items when locking", () => {
+      useLockStore.getState().setLocked(true);
+
+      expect(useLockStore.getState().locked).toBe(true);
+      expect(useClipboardStore.getState().items).toEqual([]);
+    });
+
+    it("should keep items when unlocking", () => {
+      useLockStore.getState().setLocked(false);
+
+      expect(useClipboardStore.getState().items).toHaveLength(1);
+    });
+  });
+
+  describe("unlock", () => {
+    it("should unlock on success", async () => {
+      useLockStore.setState({ locked: true });
+      mockedInvoke.mockResolvedValueOnce(undefined);
+
+      const ok = await useLockStore.getState().unlock("secret");
+
+      expect(ok).toBe(true);
+      expect(mockedInvoke).toHaveBeenCalledWith("unlock_app", { passphrase: "secret" });
+      expect(useLockStore.getState().locked).toBe(false);
+      expect(useLockStore.getState().error).toBeNull();
+    });
+
+    it("should stay locked and keep the error on failure", async () => {
+      useLockStore.setState({ locked: true });
+      mockedInvoke.mockRejectedValueOnce("Wrong passphrase");
+
+      const ok = await useLockStore.getState().unlock("nope");
+
+      expect(ok).toBe(false);
+      expect(useLockStore.getState().locked).toBe(true);
+      expect(useLockStore.getState().error).toBe("Wrong passphrase");
+      expect(useLockStore.getState().unlocking).toBe(false);
+    });
+  });
+});
@@ -18,6 +18,8 @@ interface ClipboardState {
 
   // Actions
   setSearchQuery: (query: string) => void;
+  /** Search history for `query` the next time the panel shows (recopy://search). */
+  presetSearch: (query: string) => void;
   setFilterType: (filter: FilterType) => void;
   setViewMode: (mode: ViewMode) => void;
   setSelectedIndex: (index: number) => void;
@@ -171,6 +173,9 @@ export const useClipboardStore = create<ClipboardState>((set, get) => {
 
     setSearchQuery: (query: string) => set({ searchQuery: query, selectedIndex: 0 }),
 
+    presetSearch: (query: string) =>
+      set({ viewMode: "history", searchQuery: query, selectedIndex: 0 }),
+
     setFilterType: (filter: FilterType) => {
       set({ filterType: filter, selectedIndex: 0, items: [] });
       const { searchQuery, viewMode } = get();
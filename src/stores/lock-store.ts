@@ -0,0 +1,49 @@
+import { create } from "zustand";
+import { invoke } from "@tauri-apps/api/core";
+import type { LockStatus } from "../lib/types";
+import { useClipboardStore } from "./clipboard-store";
+
+interface LockState {
+  locked: boolean;
+  unlocking: boolean;
+  error: string | null;
+
+  checkLock: () => Promise<void>;
+  setLocked: (locked: boolean) => void;
+  unlock: (passphrase: string) => Promise<boolean>;
+}
+
+export const useLockStore = create<LockState>((set, get) => ({
+  locked: false,
+  unlocking: false,
+  error: null,
+
+  checkLock: async () => {
+    try {
+      const status = await invoke<LockStatus>("get_lock_status");
+      get().setLocked(status.locked);
+    } catch (e) {
+      console.error("Failed to get lock status:", e);
+    }
+  },
+
+  setLocked: (locked) => {
+    if (locked) {
+      // Drop history already in memory so nothing stays on screen behind the prompt
+      useClipboardStore.setState({ items: [], selectedIndex: 0, hasMore: false });
+    }
+    set({ locked, error: null });
+  },
+
+  unlock: async (passphrase) => {
+    set({ unlocking: true, error: null });
+    try {
+      await invoke("unlock_app", { passphrase });
+      set({ locked: false, unlocking: false });
+      return true;
+    } catch (e) {
+      set({ unlocking: false, error: String(e) });
+      return false;
+    }
+  },
+}));
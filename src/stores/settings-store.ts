@@ -47,6 +47,7 @@ export interface ShowEventPayload {
   panel_position?: string;
   flat_mode_tb?: string;
   menu_bar_height?: number;
+  locked?: boolean;
 }
 
 interface SettingsState {